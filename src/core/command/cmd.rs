@@ -1,17 +1,193 @@
 // scr/core/command/cmd.rs
-use clap::{Parser, ArgGroup, Args, Subcommand };
-use chrono::Local;
+use clap::{Parser, ArgGroup, Args, Subcommand, ValueEnum };
+use chrono::{Local, Weekday};
+use std::path::PathBuf;
+
+use crate::core::helpers::parse_weekday;
+use crate::core::wallet::Amount;
 
 #[derive(Parser, Debug)]
-#[command(author="ethal", version="1.O.0")]
+#[command(author="ethal", version="1.O.0", args_override_self = true)]
 pub struct Cli {
     /// Verbose
     #[arg(short, long, global = true, help = "Increase verbosity level")]
     pub verbose: bool,
+    /// Strict balance
+    #[arg(long, global = true, help = "Reject any change that makes the running balance negative at some point")]
+    pub strict_balance: bool,
+    /// Future dates
+    #[arg(long, global = true, alias = "future", help = "Accept operations dated after today (rejected by default)")]
+    pub allow_future: bool,
+    /// Former opt-in of the rejection of the future dates, now the default: accepted and ignored
+    #[arg(long, global = true, hide = true)]
+    pub strict_dates: bool,
+    /// Description normalization
+    #[arg(long, global = true, value_enum, default_value_t = NormalizeDesc::Off, help = "Normalize the descriptions of the new operations")]
+    pub normalize_desc: NormalizeDesc,
+    /// Number format
+    #[arg(long, global = true, value_enum, env = "CODEXI_NUMBER_FORMAT", help = "Separators of the displayed amounts (us: 1,234.56, eu: 1.234,56; default: us or the config file)")]
+    pub number_format: Option<NumberFormat>,
+    /// Currency symbol
+    #[arg(long, global = true, env = "CODEXI_CURRENCY", value_name = "SYMBOL", help = "Currency symbol of the displayed amounts (ex: €, $)")]
+    pub currency: Option<String>,
+    /// No color
+    #[arg(long, global = true, help = "Print the tables without colors (also when the NO_COLOR environment variable is set)")]
+    pub no_color: bool,
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human, help = "Output of the balance report, the search, the resume and the schema (json: for scripts)")]
+    pub format: OutputFormat,
+    /// Compact json
+    #[arg(long, global = true, conflicts_with = "json_pretty", help = "Write the json outputs and exports on a single line (default when the output is not a terminal)")]
+    pub json_compact: bool,
+    /// Pretty json
+    #[arg(long, global = true, help = "Write the json outputs and exports indented (default on a terminal)")]
+    pub json_pretty: bool,
+    /// Log format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text, help = "Format of the log lines")]
+    pub log_format: LogFormat,
+    /// Pretty errors
+    #[arg(long, global = true, help = "Explain the integrity errors with their context and a suggestion")]
+    pub pretty_errors: bool,
+    /// Archive directory
+    #[arg(long, global = true, value_name = "DIR", env = "CODEXI_ARCHIVE_DIR", help = "Directory of the archive files (default: archives/ in the data directory)")]
+    pub archive_dir: Option<PathBuf>,
+    /// Snapshot directory
+    #[arg(long, global = true, value_name = "DIR", env = "CODEXI_SNAPSHOT_DIR", help = "Directory of the snapshots (default: snapshots/ in the data directory)")]
+    pub snapshot_dir: Option<PathBuf>,
+    /// Account
+    #[arg(long, global = true, value_name = "NAME", env = "CODEXI_ACCOUNT", help = "Account to use, each one has its own ledger, archives and snapshots (see 'accounts', default: 'default')")]
+    pub account: Option<String>,
     /// Command
     #[command(subcommand)]
     pub command: Commands,
 }
+/// Methods for Cli
+impl Cli {
+    /// Take the global flags of an outer command line (ex: the ones given before `run`):
+    /// a flag given there wins over the value of this command line.
+    pub fn merge_globals(&mut self, outer: &Cli) {
+        self.verbose |= outer.verbose;
+        self.strict_balance |= outer.strict_balance;
+        self.allow_future |= outer.allow_future;
+        self.no_color |= outer.no_color;
+        self.pretty_errors |= outer.pretty_errors;
+        if outer.json_compact || outer.json_pretty {
+            self.json_compact = outer.json_compact;
+            self.json_pretty = outer.json_pretty;
+        }
+        if outer.normalize_desc != NormalizeDesc::Off {
+            self.normalize_desc = outer.normalize_desc;
+        }
+        if outer.format != OutputFormat::Human {
+            self.format = outer.format;
+        }
+        if outer.log_format != LogFormat::Text {
+            self.log_format = outer.log_format;
+        }
+        self.number_format = outer.number_format.or(self.number_format);
+        self.currency = outer.currency.clone().or(self.currency.take());
+        self.archive_dir = outer.archive_dir.clone().or(self.archive_dir.take());
+        self.snapshot_dir = outer.snapshot_dir.clone().or(self.snapshot_dir.take());
+        self.account = outer.account.clone().or(self.account.take());
+    }
+}
+
+/// Normalization of the descriptions
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizeDesc {
+    /// Keep the descriptions as entered
+    Off,
+    /// Trim and collapse the whitespace
+    Spaces,
+    /// Collapse the whitespace and capitalize each word
+    Title,
+}
+
+/// Period of a split export
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitBy {
+    /// One file per month (codexi_YYYY-MM)
+    Month,
+    /// One file per year (codexi_YYYY)
+    Year,
+}
+
+/// Column of the search view
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// Index of the operation (with the flag marker)
+    Index,
+    /// Date of the operation
+    Date,
+    /// Flow: credit or debit
+    #[value(alias = "flow")]
+    Type,
+    /// Amount of the operation
+    Amount,
+    /// Running balance of the whole codexi
+    Balance,
+    /// Description
+    #[value(alias = "description")]
+    Desc,
+}
+
+/// Order of the search results
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    /// Chronological order
+    Date,
+    /// Smallest amount first
+    Amount,
+    /// Largest amount first
+    DescAmount,
+}
+
+/// Fields scanned by the text filter of the search
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchIn {
+    /// The description
+    #[value(alias = "description")]
+    Desc,
+    /// The category
+    Category,
+    /// The description or the category
+    All,
+}
+
+/// Breakdown of the balance report
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakdown {
+    /// Spend per payee (the description before " - ")
+    Payee,
+}
+
+/// Separators of the displayed amounts
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// 1,234,567.89
+    Us,
+    /// 1.234.567,89
+    Eu,
+}
+
+/// Format of a command output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable tables
+    #[value(alias = "table")]
+    Human,
+    /// JSON document
+    Json,
+}
+
+/// Format of the log lines
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable text
+    Text,
+    /// One JSON object per line (timestamp, level, message)
+    Json,
+}
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -20,11 +196,15 @@ pub enum Commands {
     Init {
         /// The initial account balance.
         #[arg(index = 1, value_name = "INITIAL_BALANCE", required = true, allow_negative_numbers = false)]
-        initial_amount: f64,
+        initial_amount: Amount,
 
         /// The start date of the initialization (YYYY-MM-DD).
         #[arg(index = 2, value_name = "DATE", default_value_t = Local::now().date_naive().to_string())]
         date: String,
+
+        /// Do nothing if the codexi already has operations
+        #[arg(long, help = "Do nothing (no error) if the codexi already has operations")]
+        if_empty: bool,
     },
 
     /// Add a regular debit operation
@@ -33,10 +213,18 @@ pub enum Commands {
         date: String,
 
         #[arg(index = 2, value_name = "AMOUNT", required = true, help = "Amount of the debit operation", allow_negative_numbers = false )]
-        amount: f64,
+        amount: Amount,
 
         #[arg(index = 3, value_name = "DESCRIPTION...", help = "Description of the debit operation", default_value = "no description")]
         description: Vec<String>,
+
+        /// Kind of the operation, an unknown name is a custom category (ex: salary, rent)
+        #[arg(short = 'k', long, value_name = "KIND", default_value = "transaction", help = "Kind: 'transaction', 'fee', 'transfer', 'refund' or a custom category (ex: salary)")]
+        kind: String,
+
+        /// Category for the category report (see report categories)
+        #[arg(long, value_name = "CATEGORY", help = "Category for the category report (ex: groceries)")]
+        category: Option<String>,
     },
 
     /// Add a regular credit operation
@@ -45,35 +233,204 @@ pub enum Commands {
         date: String,
 
         #[arg(index = 2, value_name = "AMOUNT", required = true, help = "Amount of the credit operation", allow_negative_numbers = false)]
-        amount: f64,
+        amount: Amount,
 
         #[arg(index = 3, value_name = "DESCRIPTION...", help = "Description of the credit operation", default_value = "no description")]
         description: Vec<String>,
+
+        /// Kind of the operation, an unknown name is a custom category (ex: salary, rent)
+        #[arg(short = 'k', long, value_name = "KIND", default_value = "transaction", help = "Kind: 'transaction', 'fee', 'transfer', 'refund' or a custom category (ex: salary)")]
+        kind: String,
+
+        /// Category for the category report (see report categories)
+        #[arg(long, value_name = "CATEGORY", help = "Category for the category report (ex: groceries)")]
+        category: Option<String>,
     },
 
-    /// Remove an operation by index.
+    /// Remove an operation by index (or by id).
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
     Rm {
         #[arg(value_name = "INDEX", help = "Index of the operation to remove", allow_negative_numbers = false)]
-        index: usize
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to remove (full or short id)")]
+        id: Option<String>,
+    },
+
+    /// Star an operation for a follow-up (see search --flagged).
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
+    Flag {
+        #[arg(value_name = "INDEX", help = "Index of the operation to flag", allow_negative_numbers = false)]
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to flag (full or short id)")]
+        id: Option<String>,
+    },
+
+    /// Remove the star of an operation.
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
+    Unflag {
+        #[arg(value_name = "INDEX", help = "Index of the operation to unflag", allow_negative_numbers = false)]
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to unflag (full or short id)")]
+        id: Option<String>,
+    },
+
+    /// Edit an operation: date, amount, kind, flow or description (only the amount of a system entry).
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
+    #[command(group(ArgGroup::new("change").required(true).multiple(true).args(["date", "amount", "kind", "flow", "desc", "append_desc", "prepend_desc"])))]
+    Edit {
+        #[arg(value_name = "INDEX", help = "Index of the operation to edit", allow_negative_numbers = false)]
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to edit (full or short id)")]
+        id: Option<String>,
+
+        #[arg(long, value_name = "YYYY-MM-DD", help = "New date (checked against the closes and anchors, the operation is moved)")]
+        date: Option<String>,
+
+        #[arg(long, value_name = "AMOUNT", allow_negative_numbers = false, help = "New amount")]
+        amount: Option<Amount>,
+
+        #[arg(short = 'k', long, value_name = "KIND", help = "New kind: 'transaction', 'fee', 'transfer', 'refund' or a custom category")]
+        kind: Option<String>,
+
+        #[arg(long, value_name = "FLOW", help = "New flow: 'debit' or 'credit'")]
+        flow: Option<String>,
+
+        #[arg(long, value_name = "TEXT", help = "New description")]
+        desc: Option<String>,
+
+        #[arg(long, value_name = "TEXT", help = "Text added at the end of the description")]
+        append_desc: Option<String>,
+
+        #[arg(long, value_name = "TEXT", help = "Text added at the start of the description")]
+        prepend_desc: Option<String>,
+
+        #[arg(long, value_name = "SEP", default_value = " ", help = "Separator between the description and the added text")]
+        separator: String,
+    },
+
+    /// List the accounts of the data directory (see --account).
+    Accounts {},
+
+    /// Revert the last change (debit, credit, rm, edit, init, adjust, close, ...) from its automatic snapshot.
+    Undo {},
+
+    /// Enter many operations in a row: the codexi is loaded once, saved on :save and on exit.
+    Repl {},
+
+    /// Check the integrity of the codexi (date order, running balance, anchors, amounts), nothing is changed.
+    Verify {},
+
+    /// Add the operations of a .codexi batch file (ex: 2025-11-06 -12.50 Lunch #food @Bistro).
+    Apply {
+        #[arg(value_name = "FILE", help = "Batch file, one operation per line: DATE +/-AMOUNT [description] [#category] [@payee]")]
+        file: String,
+    },
+
+    /// Add the operations piped on the standard input, one flow,date,amount,description per line.
+    Batch {},
+
+    /// Change the kind (and optionally the flow) of an operation.
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
+    Recategorize {
+        #[arg(value_name = "INDEX", help = "Index of the operation to change", allow_negative_numbers = false)]
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to change (full or short id)")]
+        id: Option<String>,
+
+        #[arg(short, long, value_name = "KIND", help = "New kind (transaction, fee, transfer, refund or a custom category)")]
+        kind: String,
+
+        #[arg(long, value_name = "FLOW", help = "New flow (debit or credit)")]
+        flow: Option<String>,
+    },
+
+    /// Show the details of an operation by index (or by id).
+    #[command(group(ArgGroup::new("target").required(true).args(["index", "id"])))]
+    Show {
+        #[arg(value_name = "INDEX", help = "Index of the operation to show", allow_negative_numbers = false)]
+        index: Option<usize>,
+
+        #[arg(long, value_name = "ID", help = "Id of the operation to show (full or short id)")]
+        id: Option<String>,
+    },
+
+    /// Remove all the operations matching the filters (protected system entries are kept).
+    RmMatch {
+        #[arg(long, alias = "since", help = "Start date for filtering operations", value_name = "FROM_DATE")]
+        from: Option<String>,
+
+        #[arg(long, alias = "until", help = "End date for filtering operations", value_name = "TO_DATE")]
+        to: Option<String>,
+
+        #[arg(short = 't', long, help = "Filter by text in description", value_name = "TEXT")]
+        text: Option<String>,
+
+        #[arg(short = 'k', long, help = "Filter by kind: 'transaction', 'fee', 'transfer', 'refund' or a custom category", value_name = "KIND")]
+        kind: Option<String>,
+
+        #[arg(short = 'f', long, help = "Filter by flow: 'debit' or 'credit'", value_name = "FLOW")]
+        flow: Option<String>,
+
+        #[arg(short = 'd', long, value_name = "YYYY-MM-DD", help = "Filter by specific day (YYYY-MM-DD)")]
+        day: Option<String>,
+
+        /// Filter by day of the week
+        #[arg(long, value_parser = parse_weekday, value_name = "WEEKDAY", help = "Filter by day of the week: mon..sun, monday..sunday or 1..7 (1 = monday)")]
+        weekday: Option<Weekday>,
+
+        #[arg(long = "a-min", help = "Minimum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
+        amount_min: Option<Amount>,
+
+        #[arg(long = "a-max", help = "Maximum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
+        amount_max: Option<Amount>,
+
+        /// Guard: exact number of operations expected to be removed.
+        #[arg(long, value_name = "N", help = "Abort unless exactly N operations are removed")]
+        expect_removed: Option<usize>,
+
+        /// Guard: maximum number of operations allowed to be removed.
+        #[arg(long, value_name = "N", help = "Abort if more than N operations would be removed")]
+        max_removed: Option<usize>,
     },
 
     /// Search in operation.
     Search {
         // Filtres granulaire (Plage de dates arbitraire)
-        #[arg(long, help = "Start date for filtering operations", value_name = "FROM_DATE")]
+        #[arg(long, alias = "since", help = "Start date for filtering operations", value_name = "FROM_DATE")]
         from: Option<String>,
 
-        #[arg(long, help = "End date for filtering operations", value_name = "TO_DATE")]
+        #[arg(long, alias = "until", help = "End date for filtering operations", value_name = "TO_DATE")]
         to: Option<String>,
 
-        /// Filter by text contained in description
-        #[arg(short = 't', long, help = "Filter by text in description", value_name = "TEXT")]
-        text: Option<String>,
+        /// Filter by text contained in description, any of the terms
+        #[arg(short = 't', long, num_args = 1.., help = "Filter by text in description, several terms match any of them (ex: -t coffee tea)", value_name = "TEXT")]
+        text: Vec<String>,
+
+        /// Match the text as a regular expression
+        #[arg(long, requires = "text", help = "Match --text as regular expressions, case-insensitive (ex: '^ATM', 'coffee|tea')")]
+        regex: bool,
+
+        /// Fields scanned by the text filter
+        #[arg(long = "in", value_enum, requires = "text", default_value = "desc", value_name = "FIELDS", help = "Fields scanned by --text: 'desc', 'category' or 'all' (any of them)")]
+        text_in: SearchIn,
 
         /// Filter by type of kind operation (Init, Adjust, Close, Transaction, ...)
-        #[arg(short = 'k', long, help = "Filter by kind: 'init', 'adjust', 'close', 'transaction', 'fee', 'transfer', 'refund'", value_name = "KIND")]
+        #[arg(short = 'k', long, help = "Filter by kind: 'init', 'adjust', 'close', 'transaction', 'fee', 'transfer', 'refund' or a custom category", value_name = "KIND")]
         kind: Option<String>,
 
+        /// Only the system operations (Init, Adjust, Close)
+        #[arg(long, conflicts_with = "only_regular", help = "Only the system operations (init, adjust, close)")]
+        only_system: bool,
+
+        /// Only the regular operations
+        #[arg(long, help = "Only the regular operations (no system anchor)")]
+        only_regular: bool,
+
         /// Filter by the flow of operation (debit, credit)
         #[arg(short = 'f', long, help = "Filter by flow: 'debit' or 'credit'", value_name = "FLOW")]
         flow: Option<String>,
@@ -82,17 +439,76 @@ pub enum Commands {
         #[arg(short = 'd', long, value_name = "YYYY-MM-DD", help = "Filter by specific day (YYYY-MM-DD)")]
         day: Option<String>,
 
+        /// Filter by day of the week
+        #[arg(long, value_parser = parse_weekday, value_name = "WEEKDAY", help = "Filter by day of the week: mon..sun, monday..sunday or 1..7 (1 = monday)")]
+        weekday: Option<Weekday>,
+
         /// Minimum amount
         #[arg(long = "a-min", help = "Minimum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
-        amount_min: Option<f64>,
+        amount_min: Option<Amount>,
 
         /// Maximum amount
         #[arg(long = "a-max", help = "Maximum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
-        amount_max: Option<f64>,
+        amount_max: Option<Amount>,
+
+        /// Only the starred operations
+        #[arg(long, help = "Only the flagged operations (see flag)")]
+        flagged: bool,
 
         /// The latest operations to display.
-        #[arg(long, help = "The latest N operations to display", value_name = "NUMBER", allow_negative_numbers = false)]
+        #[arg(long, value_enum, default_value = "date", value_name = "ORDER", help = "Order of the results: 'date', 'amount' or 'desc-amount' (the balance column stays chronological)")]
+        sort: SortBy,
+
+        #[arg(long, help = "The latest N operations to display (the N first ones with an amount sort)", value_name = "NUMBER", allow_negative_numbers = false)]
         latest: Option<usize>,
+
+        #[arg(long, help = "Show only this page of the results, from 1 (after the filters, --sort and --latest)", value_name = "N", allow_negative_numbers = false)]
+        page: Option<usize>,
+
+        #[arg(long, requires = "page", default_value_t = 20, help = "Operations per page (used with --page)", value_name = "N", allow_negative_numbers = false)]
+        page_size: usize,
+
+        /// Export the results to a file
+        #[arg(long, help = "Export the results to a file, format given by the extension (.csv, .toml, .json)", value_name = "PATH")]
+        export: Option<String>,
+
+        /// Only export, do not display the results
+        #[arg(long, requires = "export", help = "Do not display the results (used with --export)")]
+        export_only: bool,
+
+        /// Only print the number of matching operations
+        #[arg(long, conflicts_with_all = ["export", "export_only"], help = "Only print the number of matching operations (no table, no log)")]
+        count_only: bool,
+
+        #[arg(long, value_name = "NAME", help = "Save the search and its flags as a preset (see 'run')")]
+        save_as: Option<String>,
+
+        #[arg(long, value_name = "N", env = "CODEXI_DESC_WIDTH", default_value_t = 30, help = "Width of the description column (at least 11)")]
+        desc_width: usize,
+
+        #[arg(long, value_enum, value_delimiter = ',', value_name = "COLUMNS", help = "Columns to show, in order (ex: date,amount,desc; default: index,date,type,amount,balance,desc)")]
+        columns: Vec<Column>,
+
+        #[arg(long, help = "Hide the balance column (the running balance of the whole codexi, not of the filtered operations)")]
+        no_balance_col: bool,
+    },
+
+    /// Describe the data model (fields, csv columns, kinds and flows) for third-party tools.
+    Schema {},
+
+    /// Run a saved report/search preset, extra flags override the saved ones.
+    Run {
+        #[arg(value_name = "NAME", help = "Name of the preset (saved with --save-as)")]
+        name: String,
+
+        #[arg(value_name = "ARGS", trailing_var_arg = true, allow_hyphen_values = true, help = "Flags overriding the saved ones")]
+        args: Vec<String>,
+    },
+
+    /// Set the title of the ledger shown in the view headers (no title to remove it)
+    SetTitle {
+        #[arg(value_name = "TITLE", help = "Title of the ledger (ex: Joint Account), empty to remove it")]
+        title: Vec<String>,
     },
 
     /// Report.
@@ -104,23 +520,31 @@ pub enum Commands {
     /// Manages accounting anchors (Initial Balance, Adjustment, Closing).
     System(SystemArgs),
 
+    /// Manages the recurring operations (rent, salary) and generates them up to today.
+    Recurring(RecurringArgs),
+
 }
 
 #[derive(Parser, Debug)]
 pub struct ReportArgs {
     #[command(subcommand)]
     pub report_name: ReportName, // ReportName contient Balance, etc.
+
+    #[arg(long, global = true, value_name = "NAME", help = "Save the report and its flags as a preset (see 'run')")]
+    pub save_as: Option<String>,
 }
 
+// Parsed once per run, the size of the Balance variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum ReportName {
     /// Show the balance and debit/credit. Available criteria, --from --to --day, --month, --year.
     Balance {
         // Filtres granulaire (Plage de dates arbitraire)
-        #[arg(long, value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations", value_name = "FROM_DATE")]
+        #[arg(long, alias = "since", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations", value_name = "FROM_DATE")]
         from: Option<String>,
 
-        #[arg(long, value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations", value_name = "TO_DATE")]
+        #[arg(long, alias = "until", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations", value_name = "TO_DATE")]
         to: Option<String>,
 
         // Optionnel : balance pour une journée spécifique (Ex: -d 2025-11-24)
@@ -134,9 +558,108 @@ pub enum ReportName {
         // Optionnel : balance pour une année spécifique (Ex: -y 2025)
         #[arg(short = 'y', long, value_name = "YYYY", help = "Filter by specific year (YYYY)")]
         year: Option<String>,
+
+        // Optionnel : comparaison avec le mois précédent (Ex: -m 2025-11 --compare)
+        #[arg(long, requires = "month", conflicts_with_all = ["from", "to", "day", "year"], help = "Compare the month with the previous one (requires --month)")]
+        compare: bool,
+
+        // Optionnel : plage de montants (Ex: --a-max 1000 pour exclure un gros virement)
+        #[arg(long = "a-min", alias = "min-amount", help = "Minimum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
+        amount_min: Option<Amount>,
+
+        #[arg(long = "a-max", alias = "max-amount", help = "Maximum amount", value_name = "AMOUNT", allow_negative_numbers = false)]
+        amount_max: Option<Amount>,
+
+        // Optionnel : dépense moyenne par jour et par mois sur la période
+        #[arg(long, conflicts_with = "compare", help = "Show the average spend per day and per month over the calendar span of the filters")]
+        average: bool,
+
+        // Optionnel : relevé ligne à ligne avec le solde cumulé
+        #[arg(long, conflicts_with_all = ["compare", "average", "breakdown"], help = "Show each operation with the running balance of the filtered operations, instead of the summary")]
+        running: bool,
+
+        // Optionnel : suivi d'un objectif d'épargne (Ex: --target 5000)
+        #[arg(long, value_name = "AMOUNT", allow_negative_numbers = false, help = "Show the progress of the balance toward a savings target")]
+        target: Option<Amount>,
+
+        // Optionnel : aperçu de la clôture à une date (Ex: --projected-close 2025-11-30, aujourd'hui sans date)
+        #[arg(long, value_name = "YYYY-MM-DD", num_args = 0..=1, default_missing_value = "today", help = "Preview the carry-forward and the operations archived by a close at this date (default: today, a month or a token is closed at its last day), nothing is closed")]
+        projected_close: Option<String>,
+
+        // Optionnel : répartition des dépenses par bénéficiaire (Ex: --breakdown payee --top 10)
+        #[arg(long, value_enum, value_name = "BY", conflicts_with = "compare", help = "Show the spend of the period broken down by payee, sorted by total")]
+        breakdown: Option<Breakdown>,
+
+        #[arg(long, value_name = "N", requires = "breakdown", help = "Keep the N biggest payees, the others are merged into Other")]
+        top: Option<usize>,
+
+        #[arg(long, value_name = "AMOUNT", requires = "breakdown", allow_negative_numbers = false, help = "Merge the payees with a total below this amount into Other")]
+        threshold: Option<Amount>,
+
+        // Optionnel : exclure les opérations système (Init, Adjust, Close) des totaux
+        #[arg(long, help = "Exclude the system operations (init, adjust, close) from the credit/debit totals")]
+        exclude_system: bool,
+
+        // Optionnel : exclure un type d'opération des totaux (Ex: --exclude-kind transfer)
+        #[arg(long, value_name = "KIND", help = "Exclude a kind from the credit/debit totals (repeatable, ex: --exclude-kind transfer)")]
+        exclude_kind: Vec<String>,
+    },
+    /// Show the balance of each month.
+    Monthly {
+        #[arg(long, value_name = "YYYY-MM", help = "First month (default: month of the first operation)")]
+        from: Option<String>,
+
+        #[arg(long, value_name = "YYYY-MM", help = "Last month (default: month of the last operation)")]
+        to: Option<String>,
+
+        // Optionnel : tendance par rapport au mois précédent (▲/▼ et pourcentage)
+        #[arg(long, help = "Show the change of the net against the previous month (▲/▼ and percent)")]
+        trend: bool,
     },
     /// Show the codexi resume.
-    Resume {},
+    Resume {
+        #[arg(long, help = "Print the resume as JSON (balance, counts and latest dates)")]
+        json: bool,
+    },
+    /// Show the history of the period closes.
+    Closes {},
+    /// Show the gaps in the records: the intervals without any operation longer than a number of days.
+    Gaps {
+        #[arg(long, value_name = "DAYS", default_value_t = 30, help = "Report the intervals between two operations longer than this number of days")]
+        max_gap: i64,
+    },
+    /// Show the operations per day: busiest day, highest spend day, average and median.
+    Activity {
+        #[arg(long, alias = "since", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations")]
+        from: Option<String>,
+
+        #[arg(long, alias = "until", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations")]
+        to: Option<String>,
+    },
+    /// Show the discretionary spend: transactions and custom categories, minus refunds.
+    Discretionary {
+        #[arg(long, alias = "since", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations")]
+        from: Option<String>,
+
+        #[arg(long, alias = "until", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations")]
+        to: Option<String>,
+    },
+    /// Show the credit and debit per category (see --category of debit/credit).
+    Categories {
+        #[arg(long, alias = "since", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations")]
+        from: Option<String>,
+
+        #[arg(long, alias = "until", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations")]
+        to: Option<String>,
+    },
+    /// Show the count, mean, median, min and max of the credit and debit amounts.
+    Stats {
+        #[arg(long, alias = "since", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "Start date for filtering operations")]
+        from: Option<String>,
+
+        #[arg(long, alias = "until", value_name = "YYYY-MM-DD, YYYY-MM, YYYY", help = "End date for filtering operations")]
+        to: Option<String>,
+    },
 }
 
 // Nouvelle structure DataArgs
@@ -148,20 +671,45 @@ pub struct DataArgs {
 
 #[derive(Subcommand, Debug)]
 pub enum DataAction {
-    /// Export the data to an external format (CSV, TOML)
-    #[command(group = ArgGroup::new("format").required(true))]
+    /// Export the data to an external format (CSV, TOML, JSON)
+    #[command(group = ArgGroup::new("file_format").required(true))]
     Export(ExportArgs),
 
-    /// Importing data from an external format (CSV, TOML)
-    #[command(group = ArgGroup::new("format").required(true))]
+    /// Importing data from an external format (CSV, TOML, JSON)
+    #[command(group = ArgGroup::new("file_format").required(true))]
     Import(ImportArgs),
 
+    /// Importing the operations of a bank CSV export into the codexi
+    ImportBank(ImportBankArgs),
+
+    /// Remove the duplicated operations (same date, flow, kind, amount and description)
+    Dedupe {
+        #[arg(long, help = "Only report the number of duplicates, do not remove them")]
+        dry_run: bool,
+    },
+
+    /// Show the differences between the codexi and an external file (CSV, TOML)
+    #[command(group = ArgGroup::new("file_format").required(true))]
+    Diff(DiffArgs),
+
     /// Performed a snapshot
-    Snapshot {},
+    Snapshot {
+        #[arg(long, value_name = "NOTE", help = "Note explaining why the snapshot was taken (shown by 'list-snapshot')")]
+        note: Option<String>,
+    },
 
     /// list the available snapshot
     ListSnapshot {},
 
+    /// Delete the snapshots beyond the newest ones
+    PruneSnapshot {
+        #[arg(long, value_name = "N", help = "Number of snapshots to keep (the newest, by the timestamp of their name; default: snapshot_keep in config.toml)")]
+        keep: Option<usize>,
+
+        #[arg(long, help = "Delete the files (without it, only list them)")]
+        yes: bool,
+    },
+
     /// Restore a snapshot
     RestoreSnapshot {
         #[arg(value_name = "SNAPSHOT_FILE", help = "Used 'ListSnapShot' for the available snapshot files")]
@@ -173,24 +721,121 @@ pub enum DataAction {
 pub struct ExportArgs {
 
     /// Export to csv format
-    #[arg(short = 'c', long, conflicts_with = "toml", group = "format", help = "Export to CSV format")]
+    #[arg(short = 'c', long, conflicts_with = "toml", group = "file_format", help = "Export to CSV format")]
     pub csv: bool,
 
     /// Export to toml format
-    #[arg(short = 't', long, conflicts_with = "csv", group = "format", help = "Export to TOML format")]
+    #[arg(short = 't', long, conflicts_with = "csv", group = "file_format", help = "Export to TOML format")]
     pub toml: bool,
+
+    /// Export to json format
+    #[arg(short = 'j', long, group = "file_format", help = "Export to JSON format")]
+    pub json: bool,
+
+    /// Csv without the header row
+    #[arg(long, alias = "raw", requires = "csv", help = "Write only the data rows, without the column header (to append to an existing sheet)")]
+    pub no_header: bool,
+
+    /// One file per period
+    #[arg(long, value_enum, value_name = "PERIOD", help = "Write one file per month or year (codexi_YYYY-MM.csv, codexi_YYYY.toml, ...)")]
+    pub split_by: Option<SplitBy>,
+
+    /// Gzip the files
+    #[arg(long, help = "Compress the exported files with gzip (codexi.csv.gz, ...), the import reads them back")]
+    pub gzip: bool,
 }
 
 #[derive(Args, Debug)]
 pub struct ImportArgs {
 
     /// Import from csv format
-    #[arg(short = 'c', long, conflicts_with = "toml", group = "format", help = "Import from CSV format")]
+    #[arg(short = 'c', long, conflicts_with = "toml", group = "file_format", help = "Import from CSV format")]
     pub csv: bool,
 
     /// Import from toml format
-    #[arg(short = 't', long, conflicts_with = "csv", group = "format", help = "Import from TOML format")]
+    #[arg(short = 't', long, conflicts_with = "csv", group = "file_format", help = "Import from TOML format")]
+    pub toml: bool,
+
+    /// Import from json format
+    #[arg(short = 'j', long, group = "file_format", help = "Import from JSON format")]
+    pub json: bool,
+
+    /// Lenient csv import
+    #[arg(long, requires = "csv", help = "Import the rows with an unknown kind (as Transaction) or flow (from the amount sign) with a warning")]
+    pub lenient: bool,
+
+    /// Only check the file
+    #[arg(long, help = "Parse and validate the file, report the problems and change nothing")]
+    pub validate_only: bool,
+
+    /// Merge instead of replace
+    #[arg(long, help = "Merge the file into the codexi instead of replacing it (the operations in a closed period or before an anchor are skipped)")]
+    pub merge: bool,
+
+    /// Merge without duplicates
+    #[arg(long, help = "Merge (as --merge) and skip the operations already in the codexi (same date, flow, kind, amount and description)")]
+    pub dedup: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(group = ArgGroup::new("amount_columns").required(true))]
+pub struct ImportBankArgs {
+
+    /// The bank CSV file
+    #[arg(value_name = "FILE", help = "The bank CSV file to import")]
+    pub file: String,
+
+    /// Column of the date
+    #[arg(long, value_name = "NAME", default_value = "Date", help = "Header of the date column")]
+    pub date_col: String,
+
+    /// Format of the date
+    #[arg(long, value_name = "FORMAT", default_value = "%Y-%m-%d", help = "Format of the dates (ex: %d/%m/%Y)")]
+    pub date_format: String,
+
+    /// Column of the description
+    #[arg(long, value_name = "NAME", default_value = "Description", help = "Header of the description column")]
+    pub desc_col: String,
+
+    /// Signed amount column
+    #[arg(long, value_name = "NAME", group = "amount_columns", help = "Header of the signed amount column (negative = debit)")]
+    pub amount_col: Option<String>,
+
+    /// Debit amount column
+    #[arg(long, value_name = "NAME", group = "amount_columns", requires = "credit_col", help = "Header of the debit column (used with --credit-col)")]
+    pub debit_col: Option<String>,
+
+    /// Credit amount column
+    #[arg(long, value_name = "NAME", requires = "debit_col", help = "Header of the credit column (used with --debit-col)")]
+    pub credit_col: Option<String>,
+
+    /// Field delimiter
+    #[arg(long, value_name = "CHAR", default_value_t = ',', help = "Field delimiter of the CSV file")]
+    pub delimiter: char,
+
+    /// Skip the rows already imported
+    #[arg(long, help = "Skip the rows already in the codexi (same date, flow, amount and description), for overlapping exports")]
+    pub skip_existing: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+
+    /// Compare with csv format
+    #[arg(short = 'c', long, conflicts_with = "toml", group = "file_format", help = "Compare with the CSV file")]
+    pub csv: bool,
+
+    /// Compare with toml format
+    #[arg(short = 't', long, conflicts_with = "csv", group = "file_format", help = "Compare with the TOML file")]
     pub toml: bool,
+
+    /// Compare with json format
+    #[arg(short = 'j', long, group = "file_format", help = "Compare with the JSON file")]
+    pub json: bool,
+
+    /// Summary line
+    #[arg(long, help = "Print a summary line (+added -removed ~changed) at the end")]
+    pub stat: bool,
 }
 
 // structure System
@@ -206,7 +851,7 @@ pub enum SystemAction {
     Adjust {
         /// The actual physical balance.
         #[arg(index = 1, value_name = "PHYSICAL_BALANCE", allow_negative_numbers = false, help = "The actual physical balance to adjust the codexi to this amount.")]
-        physical_balance: f64,
+        physical_balance: Amount,
 
         /// The start date of the initialization (YYYY-MM-DD).
         #[arg(index = 2, value_name = "DATE", default_value_t = Local::now().date_naive().to_string(), help = "The date of the adjustment (YYYY-MM-DD).")]
@@ -222,11 +867,58 @@ pub enum SystemAction {
         /// Description of the balance carried forward (ex: 'Closing Year 2025').
         #[arg(value_name = "DESCRIPTION...", help = "Description of the closing operation")]
         description: Vec<String>,
+
+        /// Export the operations to archive to this file first (.csv, .toml or .json)
+        #[arg(long, value_name = "PATH", help = "Export the operations to archive to this file (.csv, .toml or .json) before closing")]
+        keep_open_copy: Option<String>,
+    },
+
+    /// Closes each month of a range in sequence, one archive per month.
+    CloseBatch {
+        /// The first month to close (YYYY-MM).
+        #[arg(long, value_name = "YYYY-MM", required = true, help = "The first month to close (YYYY-MM)")]
+        from: String,
+
+        /// The last month to close (YYYY-MM).
+        #[arg(long, value_name = "YYYY-MM", required = true, help = "The last month to close (YYYY-MM)")]
+        to: String,
+
+        /// Description of the closing operations, completed by the month.
+        #[arg(value_name = "DESCRIPTION...", help = "Description of the closing operations")]
+        description: Vec<String>,
     },
 
     /// List the archive file
     List {},
 
+    /// Delete the archive files closed before a date
+    PruneArchives {
+        #[arg(long, value_name = "YYYY-MM-DD", required = true, help = "Delete the archives closed before this date")]
+        before: String,
+
+        #[arg(long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+
+    /// Delete the backup files of a directory, except the newest ones
+    PruneBackups {
+        #[arg(long, value_name = "DIR", required = true, help = "Directory of the codexi_backup_*.zip files")]
+        dir: PathBuf,
+
+        #[arg(long, value_name = "N", required = true, help = "Number of backups to keep (the newest, by the timestamp of their name)")]
+        keep: usize,
+
+        #[arg(long, help = "Delete the files (without it, only list them)")]
+        yes: bool,
+    },
+
+    /// Reopen the latest closed period: merge its archive back into the codexi
+    Reopen {
+        /// The archive of the latest close (ex: codexi_2025-11-30.cld)
+        #[arg(value_name = "FILENAME", help = "The archive filename of the latest close (see system list)")]
+        filename: String,
+    },
+
     /// View the content of an archive file
     View {
         /// Load an archieve file (view only)
@@ -238,12 +930,96 @@ pub enum SystemAction {
     Backup {
         #[arg(long, value_name = "DIR or PATH", help = "Target directory or full path for the backup ZIP file. If a directory is provided, a default filename with timestamp will be used.")]
         target_dir: Option<String>,
+
+        #[arg(long, value_name = "MB", default_value_t = 100, help = "Skip the files bigger than this size (MiB)")]
+        max_file_size: u64,
+
+        #[arg(long, value_name = "MB", default_value_t = 1024, help = "Abort the backup if the data exceeds this size (MiB)")]
+        max_total_size: u64,
+
+        #[arg(long, help = "Encrypt the backup with a passphrase (CODEXI_BACKUP_PASSPHRASE or prompted), written as .zip.enc")]
+        encrypt: bool,
     },
 
     /// Restore datas from a backup file
     Restore {
-        #[arg(value_name = "FILENAME", help = "The backup ZIP filename to restore from")]
+        #[arg(value_name = "FILENAME", help = "The backup ZIP filename to restore from (an encrypted .zip.enc asks for its passphrase)")]
+        filename: String,
+
+        #[arg(long, help = "Only list the files that would be written or overwritten, do not restore")]
+        dry_run: bool,
+    },
+
+    /// Show the resume of a backup file without restoring it
+    InspectBackup {
+        #[arg(value_name = "FILENAME", help = "The backup ZIP filename to inspect (an encrypted .zip.enc asks for its passphrase)")]
         filename: String,
     },
 
+    /// Salvage an unreadable codexi.dat from the newest readable snapshot or backup
+    Recover {
+        #[arg(long, value_name = "DIR", help = "Also look for codexi_backup_*.zip files in this directory (repeatable, default: the documents directory)")]
+        backup_dir: Vec<PathBuf>,
+
+        #[arg(long, help = "Replace codexi.dat without asking for confirmation")]
+        yes: bool,
+    },
+
+}
+
+// structure Recurring
+#[derive(Parser, Debug)]
+pub struct RecurringArgs {
+    #[command(subcommand)]
+    pub action: RecurringAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RecurringAction {
+    /// Add a rule repeated each month (ex: rent on the 1st).
+    Add {
+        #[arg(long, value_name = "FLOW", required = true, help = "Flow: 'debit' or 'credit'")]
+        flow: String,
+
+        #[arg(long, value_name = "AMOUNT", required = true, allow_negative_numbers = false, help = "Amount of each operation")]
+        amount: Amount,
+
+        #[arg(long, value_name = "DAY", required = true, help = "Day of the month (1-31), the last day of the shorter months")]
+        day: u32,
+
+        #[arg(long, value_name = "DATE", default_value_t = Local::now().date_naive().to_string(), help = "No operation is generated before this date (YYYY-MM-DD, default: today)")]
+        start: String,
+
+        /// Kind of the operations, an unknown name is a custom category (ex: salary, rent)
+        #[arg(short = 'k', long, value_name = "KIND", default_value = "transaction", help = "Kind: 'transaction', 'fee', 'transfer', 'refund' or a custom category (ex: rent)")]
+        kind: String,
+
+        #[arg(value_name = "DESCRIPTION...", required = true, help = "Description of the operations")]
+        description: Vec<String>,
+    },
+
+    /// List the rules with their latest generated date.
+    List {},
+
+    /// Remove a rule by index (see list), the generated operations are kept.
+    Remove {
+        #[arg(value_name = "INDEX", help = "Index of the rule")]
+        index: usize,
+    },
+
+    /// Generate the operations of the rules up to today (the closed periods are skipped).
+    Materialize {},
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        // Catches the clashes between the global flags and the subcommands (ex: an ArgGroup named like a flag)
+        Cli::command().debug_assert();
+    }
 }
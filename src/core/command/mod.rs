@@ -8,4 +8,14 @@ pub use cmd::{
     ReportName,
     DataAction,
     SystemAction,
+    RecurringAction,
+    LogFormat,
+    NormalizeDesc,
+    OutputFormat,
+    NumberFormat,
+    SplitBy,
+    Column,
+    SortBy,
+    SearchIn,
+    Breakdown,
 };
@@ -0,0 +1,160 @@
+// src/core/config.rs
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::core::command::{Cli, Commands, DataAction};
+use crate::core::helpers::{get_base_data_dir, CurrencyPosition, Formatter, NumberStyle};
+
+/// Struct for the configuration file (config.toml in the data directory, shared by the accounts)
+/// Each setting is a default, the command line flags (and their environment variables) win.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// Debug logs, as with --verbose
+    #[serde(default)]
+    pub verbose: bool,
+    /// false prints the tables without colors, as with --no-color
+    pub color: Option<bool>,
+    /// Account used without --account (and CODEXI_ACCOUNT)
+    pub account: Option<String>,
+    /// Number of snapshots kept by `data prune-snapshot` without --keep
+    pub snapshot_keep: Option<usize>,
+    /// Currency symbol of the displayed amounts, as with --currency
+    pub currency: Option<String>,
+    /// Place of the currency symbol: "before" or "after" (default)
+    pub currency_position: Option<CurrencyPosition>,
+    /// Thousands separator of the displayed amounts, without --number-format (ex: " ")
+    pub thousands_separator: Option<String>,
+    /// Decimal separator of the displayed amounts, without --number-format (ex: ",")
+    pub decimal_separator: Option<String>,
+}
+/// Methods for Config
+impl Config {
+
+    /// Load the configuration of a directory (the defaults if the file does not exist)
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| anyhow!("Invalid config file {:?}: {}", path, e))
+    }
+    /// Load the configuration of the data directory, an unreadable file gives the defaults.
+    /// The problem is returned rather than logged: the logger is set up from the configuration.
+    pub fn load_or_default() -> (Self, Option<String>) {
+        match get_base_data_dir().and_then(|dir| Self::load(&dir)) {
+            Ok(config) => (config, None),
+            Err(e) => (Self::default(), Some(format!("{} The default settings are used.", e))),
+        }
+    }
+    /// Fill in the settings not given on the command line
+    /// The flags can only turn the verbosity on and the colors off, so the config adds to them.
+    pub fn apply(&self, cli: &mut Cli) {
+        cli.verbose |= self.verbose;
+        cli.no_color |= self.color == Some(false);
+        if cli.account.is_none() {
+            cli.account = self.account.clone();
+        }
+        if cli.currency.is_none() {
+            cli.currency = self.currency.clone();
+        }
+        if let Commands::Data(data_args) = &mut cli.command
+            && let DataAction::PruneSnapshot { keep, .. } = &mut data_args.action
+            && keep.is_none()
+        {
+            *keep = self.snapshot_keep;
+        }
+    }
+    /// Formatter of the displayed amounts: the separators of the number style given on the command line,
+    /// else the ones of the config over the default style.
+    pub fn formatter(&self, style: Option<NumberStyle>, currency: Option<String>) -> Formatter {
+        let mut formatter = Formatter::from(style.unwrap_or_default());
+        if style.is_none() {
+            if let Some(grouping) = &self.thousands_separator {
+                formatter.grouping = grouping.clone();
+            }
+            if let Some(decimal) = &self.decimal_separator {
+                formatter.decimal = decimal.clone();
+            }
+        }
+        formatter.currency = currency.filter(|symbol| !symbol.trim().is_empty());
+        formatter.currency_position = self.currency_position.unwrap_or_default();
+        formatter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use clap::Parser;
+    use crate::core::wallet::Amount;
+
+    #[test]
+    fn test_config_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert_eq!(Config::load(dir.path())?, Config::default(), "No file gives the defaults.");
+
+        fs::write(dir.path().join("config.toml"), "verbose = true\ncolor = false\naccount = \"business\"\nsnapshot_keep = 20\n")?;
+        let config = Config::load(dir.path())?;
+        assert!(config.verbose);
+        assert_eq!(config.color, Some(false));
+        assert_eq!(config.account.as_deref(), Some("business"));
+        assert_eq!(config.snapshot_keep, Some(20));
+
+        fs::write(dir.path().join("config.toml"), "currency = \"€\"\nthousands_separator = \" \"\ndecimal_separator = \",\"\n")?;
+        let config = Config::load(dir.path())?;
+        let formatter = config.formatter(None, config.currency.clone());
+        assert_eq!(formatter.format(Amount::from_cents(123456), false), "1 234,56 €");
+        assert_eq!(config.formatter(Some(NumberStyle::Us), None), Formatter::default(), "--number-format wins over the separators.");
+
+        fs::write(dir.path().join("config.toml"), "snapshot_keep = \"many\"\n")?;
+        assert!(Config::load(dir.path()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_load_or_default_malformed() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        crate::core::helpers::set_test_data_dir(dir.path());
+        fs::write(dir.path().join("config.toml"), "verbose = [")?;
+
+        let (config, warning) = Config::load_or_default();
+        assert_eq!(config, Config::default(), "A malformed file must not abort.");
+        assert!(warning.is_some_and(|w| w.contains("config.toml")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_cli_wins() -> Result<()> {
+        let config = Config {
+            verbose: true,
+            color: Some(false),
+            account: Some("business".to_string()),
+            snapshot_keep: Some(20),
+            ..Config::default()
+        };
+
+        let mut cli = Cli::try_parse_from(["codexi", "data", "prune-snapshot"])?;
+        config.apply(&mut cli);
+        assert!(cli.verbose && cli.no_color);
+        assert_eq!(cli.account.as_deref(), Some("business"));
+        assert!(matches!(cli.command, Commands::Data(ref d) if matches!(d.action, DataAction::PruneSnapshot { keep: Some(20), .. })));
+
+        let mut cli = Cli::try_parse_from(["codexi", "--account", "perso", "data", "prune-snapshot", "--keep", "5"])?;
+        config.apply(&mut cli);
+        assert_eq!(cli.account.as_deref(), Some("perso"), "The flag wins over the config.");
+        assert!(matches!(cli.command, Commands::Data(ref d) if matches!(d.action, DataAction::PruneSnapshot { keep: Some(5), .. })));
+
+        let mut cli = Cli::try_parse_from(["codexi", "search"])?;
+        Config::default().apply(&mut cli);
+        assert!(!cli.verbose && !cli.no_color && cli.account.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+// src/core/crypto.rs
+
+use anyhow::{Result, anyhow};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::aead::rand_core::RngCore;
+use argon2::Argon2;
+
+/// Magic bytes at the start of an encrypted file (.zip.enc)
+const ENC_MAGIC: &[u8; 4] = b"CDXE";
+/// Current version of the encrypted layout
+/// 1: magic, version, argon2id salt (16 bytes), AES-256-GCM nonce (12 bytes), ciphertext and tag
+const ENC_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = ENC_MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+/// Derive the 256 bits key of a passphrase (argon2id, default parameters)
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts data with a passphrase, a new salt and nonce are drawn for each call.
+/// ex: let bytes = encrypt(&zip_bytes, "secret")?;
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase cannot be empty."));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher.encrypt(&nonce, data)
+        .map_err(|_| anyhow!("Encryption failed."))?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    bytes.extend_from_slice(ENC_MAGIC);
+    bytes.push(ENC_VERSION);
+    bytes.extend_from_slice(&salt);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+/// Decrypts data written by encrypt.
+/// ex: let zip_bytes = decrypt(&bytes, "secret")?;
+/// A wrong passphrase or a tampered file fails (the GCM tag does not match), nothing is returned.
+pub fn decrypt(bytes: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(bytes) || bytes.len() < HEADER_LEN {
+        return Err(anyhow!("Not an encrypted codexi file."));
+    }
+    let version = bytes[ENC_MAGIC.len()];
+    if version != ENC_VERSION {
+        return Err(anyhow!("Unsupported encryption version {} (expected {}).", version, ENC_VERSION));
+    }
+
+    let salt = &bytes[ENC_MAGIC.len() + 1..ENC_MAGIC.len() + 1 + SALT_LEN];
+    let nonce = Nonce::from_slice(&bytes[HEADER_LEN - NONCE_LEN..HEADER_LEN]);
+
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt)?);
+    cipher.decrypt(nonce, &bytes[HEADER_LEN..])
+        .map_err(|_| anyhow!("Decryption failed: wrong passphrase or corrupted file."))
+}
+
+/// Check if data starts with the encrypted header
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENC_MAGIC)
+}
+
+/// Check if a file is encrypted (reads its header only)
+/// ex: is_encrypted_file(Path::new("codexi_backup.zip.enc"));
+pub fn is_encrypted_file(file_path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let read = File::open(file_path)?.read(&mut magic)?;
+    Ok(is_encrypted(&magic[..read]))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() -> Result<()> {
+        let bytes = encrypt(b"codexi data", "correct horse")?;
+        assert!(is_encrypted(&bytes));
+        assert!(!bytes.windows(11).any(|w| w == b"codexi data"), "The data must not be stored in the clear.");
+
+        assert_eq!(decrypt(&bytes, "correct horse")?, b"codexi data");
+        assert!(decrypt(&bytes, "wrong horse").is_err(), "A wrong passphrase must fail.");
+        assert!(decrypt(b"PK\x03\x04", "correct horse").is_err(), "A plain ZIP is not encrypted.");
+
+        // A damaged file fails with an error, never a panic
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(decrypt(&tampered, "correct horse").is_err(), "A tampered file must fail.");
+        assert!(decrypt(&bytes[..HEADER_LEN - 1], "correct horse").is_err(), "A truncated header must fail.");
+        assert!(decrypt(&bytes[..HEADER_LEN], "correct horse").is_err(), "A missing tag must fail.");
+
+        Ok(())
+    }
+}
@@ -2,18 +2,134 @@
 
 use anyhow::{Result, anyhow};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use log::LevelFilter;
-use chrono::{Local, NaiveDate, Datelike};
+use chrono::{Local, NaiveDate, NaiveDateTime, Datelike, Weekday};
 use directories::{ProjectDirs, UserDirs};
+use thousands::Separable;
+use owo_colors::Style;
+use serde::{Serialize, Deserialize};
 
-use crate::core::wallet::{OperationFlow, Operation};
+use crate::core::wallet::{Amount, OperationFlow, Operation, CodexiError};
 
 pub fn round_to_2_dec(value: f64) -> f64 {
     (value * 100.0).round() / 100.0
 }
 
-pub fn init_logger(lvl: bool) {
+/// Enum for the grouping and decimal separators of the displayed amounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberStyle {
+    /// 1,234,567.89
+    #[default]
+    Us,
+    /// 1.234.567,89
+    Eu,
+}
+/// Enum for the place of the currency symbol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrencyPosition {
+    /// $1,234.56
+    Before,
+    /// 1 234,56 €
+    #[default]
+    After,
+}
+/// Struct for the display of the amounts: separators and currency symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatter {
+    /// Thousands separator (ex: "," or " ")
+    pub grouping: String,
+    /// Decimal separator (ex: "." or ",")
+    pub decimal: String,
+    /// Currency symbol (ex: "€"), none by default
+    pub currency: Option<String>,
+    pub currency_position: CurrencyPosition,
+}
+/// Implement From<NumberStyle> for Formatter: the separators of the style, without currency
+impl From<NumberStyle> for Formatter {
+    fn from(style: NumberStyle) -> Self {
+        let (grouping, decimal) = match style {
+            NumberStyle::Us => (",", "."),
+            NumberStyle::Eu => (".", ","),
+        };
+        Formatter {
+            grouping: grouping.to_string(),
+            decimal: decimal.to_string(),
+            currency: None,
+            currency_position: CurrencyPosition::default(),
+        }
+    }
+}
+/// Implement Default for Formatter: 1,234.56
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter::from(NumberStyle::default())
+    }
+}
+/// Methods for Formatter
+impl Formatter {
+
+    /// Format an amount with 2 decimals, the separators and the currency symbol
+    /// ex: "1 234,56 €", signed: "+1 234,56 €"
+    pub fn format(&self, value: Amount, signed: bool) -> String {
+        let text = value.abs().to_string();
+        let (int_part, dec_part) = text.split_once('.').unwrap_or((&text, ""));
+        let grouped = int_part.separate_with_commas().replace(',', &self.grouping);
+        let sign = if value.is_negative() { "-" } else if signed { "+" } else { "" };
+
+        match (&self.currency, self.currency_position) {
+            (Some(symbol), CurrencyPosition::Before) => format!("{}{}{}{}{}", sign, symbol, grouped, self.decimal, dec_part),
+            (Some(symbol), CurrencyPosition::After) => format!("{}{}{}{} {}", sign, grouped, self.decimal, dec_part, symbol),
+            (None, _) => format!("{}{}{}{}", sign, grouped, self.decimal, dec_part),
+        }
+    }
+    /// Format an amount (ex: 1,234.50)
+    pub fn amount(&self, value: Amount) -> String {
+        self.format(value, false)
+    }
+    /// Same as amount, with the sign of positive amounts (ex: +1,234.50)
+    pub fn signed_amount(&self, value: Amount) -> String {
+        self.format(value, true)
+    }
+}
+/// Enum for the layout of the json outputs and exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Indented, for reading
+    Pretty,
+    /// On a single line, for piping
+    Compact,
+}
+/// Methods for JsonStyle
+impl JsonStyle {
+    /// The style asked by --json-compact or --json-pretty, else pretty on a terminal and compact otherwise
+    pub fn from_flags(compact: bool, pretty: bool, terminal: bool) -> Self {
+        if compact || (!pretty && !terminal) { JsonStyle::Compact } else { JsonStyle::Pretty }
+    }
+    /// Serialize a value to json in this style
+    pub fn format<T: Serialize + ?Sized>(self, value: &T) -> Result<String> {
+        let json = match self {
+            JsonStyle::Pretty => serde_json::to_string_pretty(value),
+            JsonStyle::Compact => serde_json::to_string(value),
+        };
+        json.map_err(|e| anyhow!("{}", e))
+    }
+}
+/// Colors of the viewers, set once at startup (disabled by --no-color or NO_COLOR)
+static COLORS: OnceLock<bool> = OnceLock::new();
+
+/// Enables or disables the colors of the viewers (the first call wins)
+pub fn set_colors(enabled: bool) {
+    let _ = COLORS.set(enabled);
+}
+/// Style to apply to a displayed value: the given one, or a plain style (no escape) without colors
+pub fn paint(style: Style) -> Style {
+    if COLORS.get().copied().unwrap_or(true) { style } else { Style::new() }
+}
+pub fn init_logger(lvl: bool, json: bool) {
 
     // Configuration of the logger
     let log_level = if lvl {
@@ -22,17 +138,31 @@ pub fn init_logger(lvl: bool) {
         LevelFilter::Info
     };
 
-    env_logger::Builder::new()
-        .filter_level(log_level)
-        .format_timestamp_millis()
-        .init();
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(log_level);
+
+    if json {
+        // One JSON object per line, for log aggregators
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().to_string(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    } else {
+        builder.format_timestamp_millis();
+    }
+
+    builder.init();
 }
 
 
 pub fn calculate_new_balance(
-    mut cur_bal: f64,
+    mut cur_bal: Amount,
     op: &Operation,
-) -> Result<f64>
+) -> Result<Amount>
 {
     match op.flow {
         OperationFlow::Credit => cur_bal += op.amount,
@@ -44,11 +174,64 @@ pub fn calculate_new_balance(
 
 }
 
+/// Resolves a natural date token to its first and last day, relative to `today`.
+/// ex: date_token_range("lastmonth", today);
+/// The tokens are today, yesterday, thismonth, lastmonth, thisyear and lastyear (case-insensitive).
+pub fn date_token_range(token: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let month = |day: NaiveDate| month_bounds(&day.format("%Y-%m").to_string()).ok();
+    let year = |y: i32| Some((NaiveDate::from_ymd_opt(y, 1, 1)?, NaiveDate::from_ymd_opt(y, 12, 31)?));
+
+    match token.trim().to_ascii_lowercase().as_str() {
+        "today" => Some((today, today)),
+        "yesterday" => today.pred_opt().map(|d| (d, d)),
+        "thismonth" => month(today),
+        "lastmonth" => month(today.with_day(1)?.pred_opt()?),
+        "thisyear" => year(today.year()),
+        "lastyear" => year(today.year() - 1),
+        _ => None,
+    }
+}
+
+/// Parses a day of the week: a name (mon, monday, case-insensitive) or a number from 1 (monday) to 7 (sunday)
+/// ex: parse_weekday("sat");
+pub fn parse_weekday(value: &str) -> Result<Weekday> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<u8>() {
+        return match number {
+            1..=7 => Ok(Weekday::try_from(number - 1).map_err(|e| anyhow!("{}", e))?),
+            _ => Err(anyhow!("Invalid weekday number {}: expected 1 (monday) to 7 (sunday)", number)),
+        };
+    }
+    value.parse::<Weekday>()
+        .map_err(|_| anyhow!("Invalid weekday '{}': expected mon..sun, monday..sunday or 1..7", value))
+}
+
+/// Parses the from/to bounds of a date filter (see parse_flexible_date_range).
+/// ex: date_range_bounds(Some("lastmonth"), None);
+/// A natural token given as from without a to covers its whole range
+/// (--from lastmonth is the first to the last day of the previous month).
+pub fn date_range_bounds(from: Option<&str>, to: Option<&str>) -> Result<(Option<NaiveDate>, Option<NaiveDate>)> {
+    if let (Some(token), None) = (from, to)
+        && let Some((start, end)) = date_token_range(token, Local::now().date_naive())
+    {
+        return Ok((Some(start), Some(end)));
+    }
+
+    let start_date = from.map(|d| parse_flexible_date_range(d, true)).transpose()?;
+    let end_date = to.map(|d| parse_flexible_date_range(d, false)).transpose()?;
+    Ok((start_date, end_date))
+}
+
 pub fn parse_flexible_date_range(
     date_str: &str,
     is_start_date: bool,
 ) -> Result<NaiveDate>
 {
+    // 0. Natural token: today, yesterday, thismonth, lastmonth, thisyear, lastyear
+    if let Some((start, end)) = date_token_range(date_str, Local::now().date_naive()) {
+        return Ok(if is_start_date { start } else { end });
+    }
+
     // 1. Full format: YYYY-MM-DD
     if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
         return Ok(date);
@@ -71,7 +254,7 @@ pub fn parse_flexible_date_range(
     }
 
     Err(anyhow!(
-        "Invalid date format. Expected YYYY-MM-DD, YYYY-MM, or YYYY."
+        "Invalid date format. Expected YYYY-MM-DD, YYYY-MM, YYYY or today, yesterday, thismonth, lastmonth, thisyear, lastyear."
     ))
 }
 
@@ -95,11 +278,47 @@ pub fn month_bounds(month_str: &str) -> Result<(NaiveDate, NaiveDate)> {
     Ok((start, end))
 }
 
+/// Derives the month preceding a "YYYY-MM" month.
+/// ex: previous_month("2025-01") => "2024-12"
+pub fn previous_month(month_str: &str) -> Result<String> {
+    let (start, _) = month_bounds(month_str)?;
+
+    let previous = start
+        .pred_opt()
+        .ok_or_else(|| anyhow!("Error computing the previous month"))?;
+
+    Ok(previous.format("%Y-%m").to_string())
+}
+
 const fn project_dirs_args() -> (&'static str, &'static str, &'static str) {
     ("fr", "ethal", "codexi")
 }
 
-pub fn get_data_dir() -> Result<PathBuf> {
+#[cfg(test)]
+thread_local! {
+    /// Data directory used by the tests running on the current thread
+    static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Redirects the data directory of the current test thread (ex: to a temporary directory)
+#[cfg(test)]
+pub fn set_test_data_dir(dir: &std::path::Path) {
+    TEST_DATA_DIR.with(|d| *d.borrow_mut() = Some(dir.to_path_buf()));
+}
+
+/// Name of the account using the data directory itself (the single ledger of the older installs)
+pub const DEFAULT_ACCOUNT: &str = "default";
+/// Subdirectory of the data directory holding the other accounts, one subdirectory each
+const ACCOUNTS_DIR: &str = "accounts";
+
+/// Root of the data directory, shared by all the accounts
+pub fn get_base_data_dir() -> Result<PathBuf> {
+    #[cfg(test)]
+    if let Some(dir) = TEST_DATA_DIR.with(|d| d.borrow().clone()) {
+        fs::create_dir_all(&dir)?;
+        return Ok(dir);
+    }
+
     let (q, o, a) = project_dirs_args();
     if let Some(proj_dirs) = ProjectDirs::from(q, o, a) {
         let data_dir = proj_dirs.data_dir().to_path_buf();
@@ -111,11 +330,121 @@ pub fn get_data_dir() -> Result<PathBuf> {
     Err(anyhow::anyhow!("Could not determine data directory for codexi."))
 }
 
-pub fn get_archive_path(close_date_str: &str) -> Result<PathBuf> {
+/// Data directory of the current account (see --account): the base data directory for the
+/// default account, accounts/<name> for the others. Each account has its own codexi.dat,
+/// archives/ and snapshots/.
+pub fn get_data_dir() -> Result<PathBuf> {
+    let base_dir = get_base_data_dir()?;
+
+    match current_account()? {
+        None => Ok(base_dir),
+        Some(account) => {
+            let data_dir = base_dir.join(ACCOUNTS_DIR).join(account);
+            fs::create_dir_all(&data_dir)?;
+            Ok(data_dir)
+        }
+    }
+}
+
+/// Directory of the other accounts, left out of the backups of the default account
+pub fn get_accounts_dir() -> Result<PathBuf> {
+    Ok(get_base_data_dir()?.join(ACCOUNTS_DIR))
+}
 
-    let data_dir =  get_data_dir()?;
+/// Name of the current account, None for the default account
+fn current_account() -> Result<Option<String>> {
+    let account = dir_overrides().account.unwrap_or_default();
+    let account = account.trim();
 
-    let archive_dir = data_dir.join("archives");
+    if account.is_empty() || account == DEFAULT_ACCOUNT {
+        return Ok(None);
+    }
+    if !account.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("Invalid account name '{}': use letters, digits, '-' and '_' only.", account));
+    }
+    Ok(Some(account.to_string()))
+}
+
+/// Lists the accounts of the data directory: the default account first, then the others by name.
+pub fn list_accounts() -> Result<Vec<String>> {
+    let mut accounts = Vec::new();
+
+    let accounts_dir = get_accounts_dir()?;
+    if accounts_dir.exists() {
+        for entry in fs::read_dir(&accounts_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                accounts.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    accounts.sort();
+    accounts.insert(0, DEFAULT_ACCOUNT.to_string());
+    Ok(accounts)
+}
+
+/// Locations overriding the archives/ and snapshots/ subdirectories of the data directory
+/// (ex: snapshots on a cloud-synced volume), and the current account
+#[derive(Debug, Clone, Default)]
+pub struct DirOverrides {
+    pub archive_dir: Option<PathBuf>,
+    pub snapshot_dir: Option<PathBuf>,
+    /// Account name (see get_data_dir), None or "default" for the default account
+    pub account: Option<String>,
+}
+
+static DIR_OVERRIDES: OnceLock<DirOverrides> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    /// Directory overrides used by the tests running on the current thread
+    static TEST_DIR_OVERRIDES: std::cell::RefCell<DirOverrides> = std::cell::RefCell::new(DirOverrides::default());
+}
+
+/// Sets the archive and snapshot directories (the first call wins)
+pub fn set_dir_overrides(overrides: DirOverrides) {
+    let _ = DIR_OVERRIDES.set(overrides);
+}
+
+/// Overrides the archive and snapshot directories of the current test thread
+#[cfg(test)]
+pub fn set_test_dir_overrides(overrides: DirOverrides) {
+    TEST_DIR_OVERRIDES.with(|o| *o.borrow_mut() = overrides);
+}
+
+fn dir_overrides() -> DirOverrides {
+    #[cfg(test)]
+    return TEST_DIR_OVERRIDES.with(|o| o.borrow().clone());
+
+    #[cfg(not(test))]
+    DIR_OVERRIDES.get().cloned().unwrap_or_default()
+}
+
+/// Directory of the archive files: the override (--archive-dir) or data_dir/archives
+/// An account other than the default one uses a subdirectory of the override, named after it.
+/// It is not created here, the path helpers (get_archive_path) create it as needed.
+pub fn get_archive_dir() -> Result<PathBuf> {
+    match (dir_overrides().archive_dir, current_account()?) {
+        (Some(dir), None) => Ok(dir),
+        (Some(dir), Some(account)) => Ok(dir.join(account)),
+        (None, _) => Ok(get_data_dir()?.join("archives")),
+    }
+}
+
+/// Directory of the snapshot files: the override (--snapshot-dir) or data_dir/snapshots
+/// An account other than the default one uses a subdirectory of the override, named after it.
+/// It is not created here, the path helpers (get_snapshot_path) create it as needed.
+pub fn get_snapshot_dir() -> Result<PathBuf> {
+    match (dir_overrides().snapshot_dir, current_account()?) {
+        (Some(dir), None) => Ok(dir),
+        (Some(dir), Some(account)) => Ok(dir.join(account)),
+        (None, _) => Ok(get_data_dir()?.join("snapshots")),
+    }
+}
+
+pub fn get_archive_path(close_date_str: &str) -> Result<PathBuf> {
+
+    let archive_dir = get_archive_dir()?;
     fs::create_dir_all(&archive_dir)?;
 
     // Filename : close_YYYY-MM-DD.cld
@@ -125,24 +454,139 @@ pub fn get_archive_path(close_date_str: &str) -> Result<PathBuf> {
 
 pub fn get_snapshot_path() -> Result<PathBuf> {
 
-    let data_dir =  get_data_dir()?;
+    let snapshot_dir = get_snapshot_dir()?;
+    fs::create_dir_all(&snapshot_dir)?;
+
+    // Nom du fichier : codexi_YYYYMMDD_HHMMSS.snp, suffixé (_1, _2, ...) dans la même seconde
+    let stamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let mut file_path = snapshot_dir.join(format!("codexi_{}.snp", stamp));
+    let mut n = 1;
+    while file_path.exists() {
+        file_path = snapshot_dir.join(format!("codexi_{}_{}.snp", stamp, n));
+        n += 1;
+    }
 
-    let snapshot_dir = data_dir.join("snapshots");
+    Ok(file_path)
+}
+
+/// Order of a snapshot file name: its timestamp, then its suffix in the same second
+/// ex: codexi_20251101_120000_10.snp comes after codexi_20251101_120000_9.snp (not before, as the text order would put it)
+pub fn snapshot_order(file_name: &str) -> (String, u32) {
+    let stem = file_name.strip_prefix("codexi_").and_then(|n| n.strip_suffix(".snp")).unwrap_or(file_name);
+    // YYYYMMDD_HHMMSS is 15 characters, the suffix follows with a '_'
+    match stem.get(15..).and_then(|rest| rest.strip_prefix('_')).and_then(|n| n.parse().ok()) {
+        Some(n) => (stem[..15].to_string(), n),
+        None => (stem.to_string(), 0),
+    }
+}
+
+/// Writes a file atomically: the content goes to `<file>.tmp`, is flushed to disk,
+/// then renamed over the file. A crash or a failure mid-write leaves the previous file intact.
+/// ex: write_atomic(&data_dir.join("codexi.dat"), &bytes);
+pub fn write_atomic(file_path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file path {:?}", file_path))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = file_path.with_file_name(tmp_name);
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, file_path)?;
+        Ok(())
+    })();
+
+    if result.is_err() && tmp_path.is_file() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.map_err(|e| anyhow!("Cannot write {:?}: {}", file_path, e))
+}
+
+/// Move a file, the archive directory may be on another file system than the data:
+/// the file is then copied atomically and the source removed.
+/// ex: move_file(&archive_path, &snapshot_path.with_extension("cld"));
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        write_atomic(to, fs::read(from)?)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Asks the user a yes/no question on the terminal (default: no).
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Read the passphrase of an encrypted backup from CODEXI_BACKUP_PASSPHRASE, or prompt for it.
+/// A new passphrase (confirm_new) is asked twice when prompted.
+pub fn read_passphrase(confirm_new: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("CODEXI_BACKUP_PASSPHRASE")
+        && !passphrase.is_empty()
+    {
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase cannot be empty."));
+    }
+    if confirm_new && rpassword::prompt_password("Confirm the passphrase: ")? != passphrase {
+        return Err(anyhow!("The passphrases do not match."));
+    }
+    Ok(passphrase)
+}
+
+/// Determines the path of the safety backup taken before a restore.
+/// It is stored with the snapshots, so it is not included in the full backups.
+pub fn get_safety_backup_path() -> Result<PathBuf> {
+
+    let snapshot_dir = get_snapshot_dir()?;
     fs::create_dir_all(&snapshot_dir)?;
 
-    // Nom du fichier : codexi_YYYY-MM-DD.snp
+    // Nom du fichier : restore_safety_YYYYMMDD_HHMMSS.zip
     let now = Local::now();
-    let filename = format!("codexi_{}.snp", now.format("%Y%m%d_%H%M%S"));
+    let filename = format!("restore_safety_{}.zip", now.format("%Y%m%d_%H%M%S"));
 
     Ok(snapshot_dir.join(filename))
 }
 
+/// Timestamp of the default backup file names (codexi_backup_YYYYMMDD_HHMMSS.zip)
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Reads the timestamp of a default backup file name, see get_final_backup_path
+/// ex: parse_backup_timestamp("codexi_backup_20251105_143000.zip");
+/// The encrypted backups (.zip.enc) are recognized too, any other name returns None.
+pub fn parse_backup_timestamp(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name.strip_prefix("codexi_backup_")?;
+    let stamp = stamp.strip_suffix(".zip.enc").or_else(|| stamp.strip_suffix(".zip"))?;
+    NaiveDateTime::parse_from_str(stamp, BACKUP_TIMESTAMP_FORMAT).ok()
+}
+
+/// Default directory of the backups: the documents directory of the user, or the home directory.
+pub fn get_default_backup_dir() -> Result<PathBuf> {
+    let user_dirs = UserDirs::new().ok_or_else(|| anyhow!("Unable to find user directory (UserDirs)."))?;
+
+    Ok(user_dirs.document_dir()
+        .unwrap_or_else(|| user_dirs.home_dir())
+        .to_path_buf())
+}
+
 /// Determines the full path to the ZIP backup file.
 /// Uses `target_dir_arg` (optional string) or the default user directory.
 pub fn get_final_backup_path(target_dir_arg: Option<&str>) -> Result<PathBuf> {
 
     let now = Local::now();
-    let default_filename = format!("codexi_backup_{}.zip", now.format("%Y%m%d_%H%M%S"));
+    let default_filename = format!("codexi_backup_{}.zip", now.format(BACKUP_TIMESTAMP_FORMAT));
 
     let target_dir: PathBuf;
     let final_filename: String;
@@ -152,7 +596,7 @@ pub fn get_final_backup_path(target_dir_arg: Option<&str>) -> Result<PathBuf> {
     if let Some(path_str) = target_dir_arg {
         let path = PathBuf::from(path_str);
 
-        if path.extension().map_or(false, |ext| ext.to_ascii_lowercase() == "zip") {
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
 
             final_filename = path.file_name()
                 .ok_or_else(|| anyhow!("The path specified for the backup is invalid."))?
@@ -178,12 +622,7 @@ pub fn get_final_backup_path(target_dir_arg: Option<&str>) -> Result<PathBuf> {
             final_filename = default_filename;
         }
     } else {
-        let user_dirs = UserDirs::new().ok_or_else(|| anyhow!("Unable to find user directory (UserDirs)."))?;
-
-        target_dir = user_dirs.document_dir()
-            .unwrap_or_else(|| user_dirs.home_dir())
-            .to_path_buf();
-
+        target_dir = get_default_backup_dir()?;
         final_filename = default_filename;
     };
 
@@ -193,3 +632,157 @@ pub fn get_final_backup_path(target_dir_arg: Option<&str>) -> Result<PathBuf> {
 
     Ok(final_path)
 }
+
+/// Message of an error for --pretty-errors: the error chain, with the context and a suggestion
+/// for the integrity errors of the codexi (see CodexiError).
+/// The amounts are displayed with the formatter of the viewers.
+/// ex: eprintln!("{}", pretty_error(&err, &Formatter::default()));
+pub fn pretty_error(err: &anyhow::Error, fmt: &Formatter) -> String {
+    let Some(codexi_err) = err.downcast_ref::<CodexiError>() else {
+        return format!("Error: {:#}", err);
+    };
+
+    let detail = match codexi_err {
+        CodexiError::ClosedPeriod { date, close_date } => format!(
+            "The date {} is in a closed period (last close: {}).\n\
+             Hint: use a date after {}, run `codexi system list` to review the closes.",
+            date, close_date, close_date
+        ),
+        CodexiError::BeforeAnchor { date, anchor_date } => format!(
+            "The date {} is before the latest init/adjust anchor ({}).\n\
+             Hint: use a date on or after {}, run `codexi search --only-system` to review the anchors.",
+            date, anchor_date, anchor_date
+        ),
+        CodexiError::InsufficientFunds { balance, amount } => format!(
+            "The current balance is {}, the debit of {} is {} short.\n\
+             Hint: add the missing credit first, or run `codexi system adjust` if the balance is out of date.",
+            fmt.amount(*balance), fmt.amount(*amount), fmt.amount(*amount - *balance)
+        ),
+    };
+    format!("Error: {}\n{}", codexi_err, detail)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_fmt_amount_us_and_eu() {
+        let us = Formatter::from(NumberStyle::Us);
+        let eu = Formatter::from(NumberStyle::Eu);
+        assert_eq!(us.format(Amount::from_cents(123456789), false), "1,234,567.89");
+        assert_eq!(eu.format(Amount::from_cents(123456789), false), "1.234.567,89");
+        assert_eq!(eu.format(Amount::from_cents(-123450), true), "-1.234,50");
+        assert_eq!(us.format(Amount::from_cents(1200), true), "+12.00");
+        assert_eq!(us.format(Amount::from_cents(-12), false), "-0.12");
+    }
+
+    #[test]
+    fn test_formatter_currency() {
+        let euro = Formatter {
+            grouping: " ".to_string(),
+            decimal: ",".to_string(),
+            currency: Some("€".to_string()),
+            currency_position: CurrencyPosition::After,
+        };
+        assert_eq!(euro.format(Amount::from_cents(123456), false), "1 234,56 €");
+        assert_eq!(euro.format(Amount::from_cents(-123456789), false), "-1 234 567,89 €");
+        assert_eq!(euro.format(Amount::from_cents(500), true), "+5,00 €");
+
+        let dollar = Formatter { currency: Some("$".to_string()), currency_position: CurrencyPosition::Before, ..Formatter::default() };
+        assert_eq!(dollar.format(Amount::from_cents(-123456), false), "-$1,234.56");
+        assert_eq!(Formatter::default().format(Amount::from_cents(123456), false), "1,234.56", "The default keeps the current style.");
+    }
+
+    #[test]
+    fn test_date_tokens() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let today = day(2025, 3, 15);
+
+        assert_eq!(date_token_range("today", today), Some((today, today)));
+        assert_eq!(date_token_range("yesterday", today), Some((day(2025, 3, 14), day(2025, 3, 14))));
+        assert_eq!(date_token_range("thismonth", today), Some((day(2025, 3, 1), day(2025, 3, 31))));
+        assert_eq!(date_token_range("lastmonth", today), Some((day(2025, 2, 1), day(2025, 2, 28))));
+        assert_eq!(date_token_range("ThisYear", today), Some((day(2025, 1, 1), day(2025, 12, 31))));
+        assert_eq!(date_token_range("lastyear", today), Some((day(2024, 1, 1), day(2024, 12, 31))));
+        assert_eq!(date_token_range("lastmonth", day(2025, 1, 10)), Some((day(2024, 12, 1), day(2024, 12, 31))), "January looks back to December.");
+        assert_eq!(date_token_range("2025-03", today), None);
+
+        let (start, end) = date_range_bounds(Some("lastmonth"), None).unwrap();
+        let expected = date_token_range("lastmonth", Local::now().date_naive()).unwrap();
+        assert_eq!((start, end), (Some(expected.0), Some(expected.1)), "A single --from token covers its whole range.");
+
+        let (start, end) = date_range_bounds(Some("lastyear"), Some("2030-01-01")).unwrap();
+        assert_eq!(start.map(|d| d.month()), Some(1));
+        assert_eq!(end, Some(day(2030, 1, 1)), "An explicit --to is kept.");
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_previous_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("codexi.dat");
+
+        write_atomic(&file_path, b"first")?;
+        write_atomic(&file_path, b"second")?;
+        assert_eq!(fs::read(&file_path)?, b"second");
+        assert!(!dir.path().join("codexi.dat.tmp").exists(), "The temp file must be renamed away.");
+
+        // A write that cannot complete (the target is a directory) leaves no temp file behind
+        let blocked = dir.path().join("blocked");
+        fs::create_dir(&blocked)?;
+        fs::write(blocked.join("inner"), b"keep")?;
+        assert!(write_atomic(&blocked, b"partial").is_err());
+        assert!(!dir.path().join("blocked.tmp").exists(), "The temp file must be cleaned up.");
+        assert_eq!(fs::read(blocked.join("inner"))?, b"keep");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_weekday() {
+        assert_eq!(parse_weekday("sat").unwrap(), Weekday::Sat);
+        assert_eq!(parse_weekday("Saturday").unwrap(), Weekday::Sat);
+        assert_eq!(parse_weekday("1").unwrap(), Weekday::Mon);
+        assert_eq!(parse_weekday("7").unwrap(), Weekday::Sun);
+        assert!(parse_weekday("0").is_err());
+        assert!(parse_weekday("someday").is_err());
+    }
+
+    #[test]
+    fn test_pretty_error_insufficient_funds() -> Result<()> {
+        use crate::core::wallet::{Codexi, OperationKind, RegularKind};
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        let err = codexi.add_operation(
+            OperationKind::Regular(RegularKind::Transaction),
+            OperationFlow::Debit,
+            "2025-11-02",
+            150.0,
+            "Rent",
+        ).unwrap_err();
+
+        assert_eq!(err.to_string(), "Insufficient funds.");
+        assert_eq!(
+            pretty_error(&err, &Formatter::default()),
+            "Error: Insufficient funds.\n\
+             The current balance is 100.00, the debit of 150.00 is 50.00 short.\n\
+             Hint: add the missing credit first, or run `codexi system adjust` if the balance is out of date."
+        );
+
+        let other = anyhow!("Operation index 9 is out of bounds.");
+        assert_eq!(pretty_error(&other, &Formatter::default()), "Error: Operation index 9 is out of bounds.", "Other errors keep their message.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_backup_timestamp() {
+        let stamp = parse_backup_timestamp("codexi_backup_20251105_143000.zip");
+        assert_eq!(stamp.map(|s| s.to_string()), Some("2025-11-05 14:30:00".to_string()));
+        assert!(parse_backup_timestamp("codexi_backup_20251105_143000.zip.enc").is_some());
+        assert!(parse_backup_timestamp("codexi_backup_manual.zip").is_none());
+        assert!(parse_backup_timestamp("restore_safety_20251105_143000.zip").is_none());
+    }
+}
@@ -1,6 +1,10 @@
 // src/core/mod.rs
 
 pub mod helpers;
+pub mod crypto;
 pub mod command;
 pub mod wallet;
+pub mod presets;
+pub mod config;
+pub mod repl;
 
@@ -0,0 +1,121 @@
+// src/core/presets.rs
+
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::core::helpers::get_data_dir;
+use crate::core::helpers::write_atomic;
+
+/// Struct for a saved report preset: the command line arguments to replay
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Preset {
+    pub args: Vec<String>,
+}
+/// Struct for the presets file (presets.toml in the data directory)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Presets {
+    #[serde(default)]
+    pub presets: BTreeMap<String, Preset>,
+}
+/// Methods for Presets
+impl Presets {
+
+    /// Get the path of the presets file
+    fn path() -> Result<PathBuf> {
+        Ok(get_data_dir()?.join("presets.toml"))
+    }
+    /// Load the presets (empty if the file does not exist yet)
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| anyhow!("Invalid presets file {:?}: {}", path, e))
+    }
+    /// Save the presets
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        write_atomic(&path, toml::to_string_pretty(self)?)?;
+        log::debug!("Presets saved to {:?}", path);
+        Ok(())
+    }
+    /// Save the arguments of a command line under a name (replacing a preset of the same name)
+    /// ex: Presets::save_as("monthly", &["codexi", "report", "balance", "--month", "2025-11", "--save-as", "monthly"]);
+    /// The program name and the --save-as option are left out.
+    pub fn save_as(name: &str, argv: &[String]) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("A preset name is required."));
+        }
+
+        let mut args = Vec::new();
+        let mut iter = argv.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            if arg == "--save-as" {
+                iter.next();
+            } else if !arg.starts_with("--save-as=") {
+                args.push(arg.clone());
+            }
+        }
+
+        let mut presets = Self::load()?;
+        presets.presets.insert(name.to_string(), Preset { args });
+        presets.save()?;
+        log::info!("Preset '{}' saved.", name);
+        Ok(())
+    }
+    /// Get the command line of a preset, followed by the extra arguments (which override the saved ones)
+    /// ex: Presets::command_line("monthly", &["--month".into(), "2025-12".into()]);
+    pub fn command_line(name: &str, extra: &[String]) -> Result<Vec<String>> {
+        let presets = Self::load()?;
+        let preset = presets.presets.get(name).ok_or_else(|| {
+            let names: Vec<&str> = presets.presets.keys().map(String::as_str).collect();
+            anyhow!("No preset named '{}' (available: {}).", name, if names.is_empty() { "none".to_string() } else { names.join(", ") })
+        })?;
+
+        let mut argv = vec!["codexi".to_string()];
+        argv.extend(preset.args.iter().cloned());
+        argv.extend(extra.iter().cloned());
+        Ok(argv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use clap::Parser;
+    use crate::core::helpers::set_test_data_dir;
+    use crate::core::command::{Cli, Commands, ReportName};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_preset_save_and_override() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+
+        Presets::save_as("monthly", &args("codexi report balance --month 2025-11 --save-as monthly"))?;
+        assert_eq!(Presets::load()?.presets["monthly"].args, args("report balance --month 2025-11"));
+
+        let argv = Presets::command_line("monthly", &args("--month 2025-12"))?;
+        let cli = Cli::try_parse_from(argv)?;
+        let Commands::Report(report) = cli.command else {
+            panic!("The preset must replay a report.");
+        };
+        let ReportName::Balance { month, .. } = report.report_name else {
+            panic!("The preset must replay a balance report.");
+        };
+        assert_eq!(month.as_deref(), Some("2025-12"), "A flag given at run time overrides the saved one.");
+
+        assert!(Presets::command_line("weekly", &[]).is_err());
+
+        Ok(())
+    }
+}
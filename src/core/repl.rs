@@ -0,0 +1,214 @@
+// src/core/repl.rs
+
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+use crate::core::wallet::{Amount, Codexi, OperationFlow, OperationKind, RegularKind};
+
+const HELP: &str = "\
+debit DATE AMOUNT DESCRIPTION   add a debit (ex: debit 2025-01-03 12.50 coffee)
+credit DATE AMOUNT DESCRIPTION  add a credit
+rm INDEX                        remove an operation
+balance                         show the current balance
+:save                           save the codexi
+:quit, exit                     save and leave (Ctrl-D too, Ctrl-C leaves without saving)";
+
+/// Enum for what the loop does after a line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplAction {
+    /// Print the message (if any) and read the next line
+    Continue(Option<String>),
+    /// Save the codexi and read the next line
+    Save,
+    /// Save the codexi and leave
+    Quit,
+}
+/// Struct for an interactive session: the codexi is loaded once and changed line by line
+pub struct Repl {
+    pub codexi: Codexi,
+    /// Check the running balance after each change (see --strict-balance)
+    pub strict_balance: bool,
+    /// The codexi before the first unsaved change, for the snapshot taken when saving
+    before: Option<Codexi>,
+}
+/// Methods for Repl
+impl Repl {
+
+    pub fn new(codexi: Codexi, strict_balance: bool) -> Self {
+        Self { codexi, strict_balance, before: None }
+    }
+    /// Changes not saved yet
+    pub fn is_dirty(&self) -> bool {
+        self.before.is_some()
+    }
+    /// Run a line with the same methods as the commands (add_operation, delete_operation)
+    /// ex: repl.handle("debit 2025-01-03 12.50 coffee");
+    /// A failed change leaves the codexi as it was.
+    pub fn handle(&mut self, line: &str) -> Result<ReplAction> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(ReplAction::Continue(None));
+        };
+        let args: Vec<&str> = words.collect();
+
+        let previous = self.codexi.clone();
+        let message = match command {
+            ":save" => return Ok(ReplAction::Save),
+            ":quit" | ":q" | "exit" | "quit" => return Ok(ReplAction::Quit),
+            ":help" | "help" => return Ok(ReplAction::Continue(Some(HELP.to_string()))),
+            "balance" => {
+                return Ok(ReplAction::Continue(Some(format!("Balance: {}", self.codexi.resume()?.current_balance))));
+            }
+            "debit" | "credit" => {
+                let [date, amount, description @ ..] = args.as_slice() else {
+                    return Err(anyhow!("Usage: {} DATE AMOUNT DESCRIPTION", command));
+                };
+                let amount = amount.parse::<Amount>()
+                    .ok()
+                    .filter(|a| a.is_positive())
+                    .ok_or_else(|| anyhow!("Invalid amount '{}', a positive amount is expected.", amount))?;
+                let flow = if command == "debit" { OperationFlow::Debit } else { OperationFlow::Credit };
+                let balance = self.codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), flow, date, amount, &description.join(" "))?;
+                format!("{} {} added, balance: {}.", command, amount, balance)
+            }
+            "rm" => {
+                let [index] = args.as_slice() else {
+                    return Err(anyhow!("Usage: rm INDEX"));
+                };
+                let index: usize = index.parse().map_err(|_| anyhow!("Invalid index '{}'.", index))?;
+                self.codexi.delete_operation(index)?;
+                format!("Operation #{} removed.", index)
+            }
+            _ => return Err(anyhow!("Unknown command '{}', type :help.", command)),
+        };
+
+        if self.strict_balance
+            && let Err(e) = self.codexi.check_running_balance()
+        {
+            self.codexi = previous;
+            return Err(e);
+        }
+        self.before.get_or_insert(previous);
+        Ok(ReplAction::Continue(Some(message)))
+    }
+    /// Save the changes, a snapshot of the codexi before them is taken first (see undo)
+    pub fn save(&mut self, dir: &Path) -> Result<()> {
+        if let Some(before) = self.before.take() {
+            before.snapshot(Some("before repl"))?;
+            self.codexi.save(dir)?;
+            println!("Saved.");
+        }
+        Ok(())
+    }
+    /// Read the lines until :quit (or Ctrl-D), the history is kept in repl_history.txt of the data directory
+    /// An invalid line prints its error and the loop goes on.
+    pub fn run(&mut self, dir: &Path) -> Result<()> {
+        let history_path = dir.join("repl_history.txt");
+        let mut editor = DefaultEditor::new()?;
+        let _ = editor.load_history(&history_path);
+        println!("codexi interactive mode, type :help for the commands.");
+
+        loop {
+            let line = match editor.readline("codexi> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Eof) => break,
+                Err(ReadlineError::Interrupted) => {
+                    if self.is_dirty() {
+                        println!("Left without saving.");
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if !line.trim().is_empty() {
+                let _ = editor.add_history_entry(line.as_str());
+            }
+
+            match self.handle(&line) {
+                Ok(ReplAction::Continue(Some(message))) => println!("{}", message),
+                Ok(ReplAction::Continue(None)) => {},
+                Ok(ReplAction::Save) => self.save(dir)?,
+                Ok(ReplAction::Quit) => break,
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        }
+
+        if let Err(e) = editor.save_history(&history_path) {
+            log::warn!("REPL history not saved to {:?}: {}", history_path, e);
+        }
+        self.save(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_repl_scripted_lines() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-01-01", false)?;
+        let mut repl = Repl::new(codexi, false);
+
+        let script = [
+            "debit 2025-01-03 12.50 coffee and cake",
+            "",
+            "credit 2025-01-05 40 Salary",
+            "debit 2025-01-06 abc oops",
+            "debit 2025-01-06",
+            "transfer 2025-01-06 10 x",
+            "rm 0",
+            "debit 2025-01-07 5 Bread",
+            "rm 3",
+        ];
+        assert_eq!(repl.handle("debit 2025-01-02 10 Bus")?, ReplAction::Continue(Some("debit 10.00 added, balance: 90.00.".to_string())));
+        repl.handle("rm 1")?;
+
+        let results: Vec<bool> = script.iter().map(|line| repl.handle(line).is_ok()).collect();
+        assert_eq!(results, vec![true, true, true, false, false, false, false, true, true], "The invalid lines fail, the loop goes on.");
+
+        let descriptions: Vec<&str> = repl.codexi.operations.iter().map(|op| op.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["INITIAL AMOUNT", "coffee and cake", "Salary"]);
+        assert_eq!(repl.codexi.resume()?.current_balance, Amount::from(127.5));
+        assert!(repl.is_dirty());
+
+        assert_eq!(repl.handle(":save")?, ReplAction::Save);
+        assert_eq!(repl.handle("exit")?, ReplAction::Quit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repl_rejects_non_positive_amounts() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-01-01", false)?;
+        let mut repl = Repl::new(codexi, false);
+
+        for line in ["credit 2025-01-02 -500 neg", "debit 2025-01-02 -20 neg", "debit 2025-01-02 0 zero"] {
+            assert!(repl.handle(line).is_err_and(|e| e.to_string().contains("positive amount")), "{} must fail.", line);
+        }
+        assert_eq!(repl.codexi.operations.len(), 1);
+        assert_eq!(repl.codexi.resume()?.current_balance, Amount::from(100.0));
+        assert!(!repl.is_dirty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repl_strict_balance_rolls_back() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-01-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-01-10", 90.0, "Rent")?;
+        let mut repl = Repl::new(codexi, true);
+
+        // Back-dated: the balance of 2025-01-10 would become negative
+        assert!(repl.handle("debit 2025-01-05 50 Late").is_err());
+        assert_eq!(repl.codexi.operations.len(), 2, "The failed change is rolled back.");
+        assert!(!repl.is_dirty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+// src/core/wallet/amount.rs
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use thiserror::Error;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+
+/// Error type for Amount
+#[derive(Debug, Error)]
+pub enum AmountError {
+    #[error("Invalid amount: '{0}'")]
+    Invalid(String),
+    #[error("Invalid amount: '{0}' has more than 2 decimals")]
+    TooPrecise(String),
+}
+/// Struct for an amount of money, stored as a whole number of cents
+/// The sums of amounts are exact (ex: 100 x 0.10 is 10.00), unlike the sums of f64.
+/// Serialized as cents in the binary data (codexi.dat, snapshots), as a decimal number
+/// in the text formats (csv, toml, json) so the exports keep their layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+/// Methods for Amount
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// Create an amount from a number of cents
+    /// ex: Amount::from_cents(1250); // 12.50
+    pub const fn from_cents(cents: i64) -> Self {
+        Amount(cents)
+    }
+    /// Get the number of cents
+    pub const fn cents(self) -> i64 {
+        self.0
+    }
+    /// Create an amount from a decimal value, rounded to the cent
+    /// ex: Amount::from_f64(12.505); // 12.51
+    pub fn from_f64(value: f64) -> Self {
+        Amount((value * 100.0).round() as i64)
+    }
+    /// Get the amount as a decimal value (for the ratios and the text formats)
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+    /// Get the absolute amount
+    pub fn abs(self) -> Self {
+        Amount(self.0.abs())
+    }
+    /// Check if the amount is zero
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+    /// Check if the amount is below zero
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+    /// Check if the amount is above zero
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+}
+/// Implement From<f64> for Amount (rounded to the cent)
+impl From<f64> for Amount {
+    fn from(value: f64) -> Self {
+        Amount::from_f64(value)
+    }
+}
+/// Implement FromStr for Amount: an exact decimal with at most 2 decimals (ex: 12.50, -3, .5)
+impl FromStr for Amount {
+    type Err = AmountError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let invalid = || AmountError::Invalid(s.to_string());
+
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (int_part, dec_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if (int_part.is_empty() && dec_part.is_empty())
+            || !int_part.chars().all(|c| c.is_ascii_digit())
+            || !dec_part.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        // Trailing zeros are harmless (ex: 12.500)
+        let dec_part = dec_part.trim_end_matches('0');
+        if dec_part.len() > 2 {
+            return Err(AmountError::TooPrecise(s.to_string()));
+        }
+
+        let units: i64 = if int_part.is_empty() { 0 } else { int_part.parse().map_err(|_| invalid())? };
+        let cents: i64 = format!("{:0<2}", dec_part).parse().map_err(|_| invalid())?;
+        let total = units.checked_mul(100).and_then(|u| u.checked_add(cents)).ok_or_else(invalid)?;
+        Ok(Amount(if negative { -total } else { total }))
+    }
+}
+/// Implement Display for Amount (ex: 1234.50, -0.05), the width and alignment are applied
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        f.pad(&format!("{}{}.{:02}", sign, cents / 100, cents % 100))
+    }
+}
+impl Add for Amount {
+    type Output = Amount;
+    fn add(self, rhs: Amount) -> Amount {
+        Amount(self.0 + rhs.0)
+    }
+}
+impl Sub for Amount {
+    type Output = Amount;
+    fn sub(self, rhs: Amount) -> Amount {
+        Amount(self.0 - rhs.0)
+    }
+}
+impl Neg for Amount {
+    type Output = Amount;
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+/// Multiply by a factor (ex: the sign of a flow)
+impl Mul<i64> for Amount {
+    type Output = Amount;
+    fn mul(self, rhs: i64) -> Amount {
+        Amount(self.0 * rhs)
+    }
+}
+impl AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        self.0 += rhs.0;
+    }
+}
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        self.0 -= rhs.0;
+    }
+}
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Amount {
+        iter.copied().sum()
+    }
+}
+/// Implement Serialize for Amount: cents in the binary formats, a decimal number in the text formats
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_f64(self.to_f64())
+        } else {
+            serializer.serialize_i64(self.0)
+        }
+    }
+}
+/// Implement Deserialize for Amount, the counterpart of Serialize
+/// The text formats accept a number (rounded to the cent) or a decimal string.
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(AmountVisitor)
+        } else {
+            i64::deserialize(deserializer).map(Amount::from_cents)
+        }
+    }
+}
+/// Visitor of the decimal amounts of the text formats
+struct AmountVisitor;
+
+impl Visitor<'_> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an amount (ex: 12.50)")
+    }
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+        if !value.is_finite() {
+            return Err(E::custom(format!("invalid amount {}", value)));
+        }
+        Ok(Amount::from_f64(value))
+    }
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+        value.checked_mul(100).map(Amount::from_cents).ok_or_else(|| E::custom(format!("amount {} is too large", value)))
+    }
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+        i64::try_from(value).map_err(|_| E::custom(format!("amount {} is too large", value)))
+            .and_then(|value| self.visit_i64(value))
+    }
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() -> anyhow::Result<()> {
+        assert_eq!("12.50".parse::<Amount>()?, Amount::from_cents(1250));
+        assert_eq!("12.5".parse::<Amount>()?, Amount::from_cents(1250));
+        assert_eq!(" 7 ".parse::<Amount>()?, Amount::from_cents(700));
+        assert_eq!("-0.05".parse::<Amount>()?, Amount::from_cents(-5));
+        assert_eq!(".5".parse::<Amount>()?, Amount::from_cents(50));
+        assert_eq!("3.100".parse::<Amount>()?, Amount::from_cents(310), "Trailing zeros are accepted.");
+        assert!(matches!("12.505".parse::<Amount>(), Err(AmountError::TooPrecise(_))));
+        for bad in ["", "-", ".", "1e3", "12,50", "abc", "1.2.3"] {
+            assert!(bad.parse::<Amount>().is_err(), "'{}' must be rejected.", bad);
+        }
+
+        assert_eq!(Amount::from_cents(1250).to_string(), "12.50");
+        assert_eq!(Amount::from_cents(-5).to_string(), "-0.05");
+        assert_eq!(format!("{:>8}|", Amount::from_cents(100)), "    1.00|");
+        assert_eq!(Amount::from_f64(0.1 + 0.2), Amount::from_cents(30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_cents_and_decimal() -> anyhow::Result<()> {
+        let amount = Amount::from_cents(1250);
+        assert_eq!(bincode::serialize(&amount)?, bincode::serialize(&1250i64)?, "Cents in the binary data.");
+        assert_eq!(bincode::deserialize::<Amount>(&bincode::serialize(&amount)?)?, amount);
+
+        assert_eq!(serde_json::to_string(&amount)?, "12.5");
+        assert_eq!(serde_json::from_str::<Amount>("12.5")?, amount);
+        assert_eq!(serde_json::from_str::<Amount>("12")?, Amount::from_cents(1200));
+        assert_eq!(serde_json::from_str::<Amount>("\"12.50\"")?, amount);
+        assert_eq!(serde_json::from_str::<Amount>("0.30000000000000004")?, Amount::from_cents(30), "An old f64 value is rounded to the cent.");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+// src/core/wallet/bank_import.rs
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::NaiveDate;
+
+use super::amount::Amount;
+use super::codexi::Codexi;
+use super::file_management::ImportSummary;
+use super::operation::Operation;
+use super::operation_flow::OperationFlow;
+use super::operation_kind::OperationKind;
+use super::regular_kind::RegularKind;
+
+/// Enum describing where the amount of a bank CSV row is read from
+#[derive(Debug, Clone)]
+pub enum BankAmountColumns {
+    /// One signed column: negative amounts are debits, positive amounts are credits
+    Signed(String),
+    /// Two columns: a value in the debit column is a debit, a value in the credit column a credit
+    Split { debit: String, credit: String },
+}
+/// Struct describing the columns of a bank CSV export
+#[derive(Debug, Clone)]
+pub struct BankCsvMapping {
+    pub date_col: String,
+    pub date_format: String,
+    pub desc_col: String,
+    pub amount: BankAmountColumns,
+    pub delimiter: u8,
+}
+/// Struct for a parsed bank CSV row
+struct BankRow {
+    line: u64,
+    date: NaiveDate,
+    flow: OperationFlow,
+    amount: Amount,
+    description: String,
+}
+
+/// Methods for importing bank CSV exports into the codexi
+impl Codexi {
+
+    /// Imports the operations of a bank CSV export into the codexi.
+    /// ex: codexi.import_bank_csv(Path::new("bank.csv"), &mapping, false);
+    /// The columns are found by their header name (see BankCsvMapping).
+    /// Invalid rows are collected as errors (with their line number) instead of aborting the import,
+    /// blank rows are skipped and the valid rows are added in date order through add_operation.
+    /// With `skip_existing`, a row whose canonical key (see Operation::dedup_key) is already in the codexi
+    /// is counted as existing instead of imported, so re-importing an overlapping export adds nothing twice.
+    /// Each operation of the codexi matches one row only: two identical rows with one in the codexi import one.
+    pub fn import_bank_csv(&mut self, file_path: &Path, mapping: &BankCsvMapping, skip_existing: bool) -> Result<ImportSummary> {
+
+        let file = fs::File::open(file_path)?;
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(mapping.delimiter)
+            .flexible(true)
+            .from_reader(file);
+
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| -> Result<usize> {
+            headers.iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| anyhow!("Column '{}' not found in {:?}", name, file_path))
+        };
+
+        let date_idx = column(&mapping.date_col)?;
+        let desc_idx = column(&mapping.desc_col)?;
+        let amount_idx = match &mapping.amount {
+            BankAmountColumns::Signed(amount) => (column(amount)?, None),
+            BankAmountColumns::Split { debit, credit } => (column(debit)?, Some(column(credit)?)),
+        };
+
+        let mut result = ImportSummary::default();
+        let mut rows = Vec::new();
+
+        for (count, record) in rdr.records().enumerate() {
+            result.log_progress(count);
+
+            // A malformed record (ex: invalid UTF-8) is reported like an invalid row
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    result.errors.push((e.position().map(|p| p.line()).unwrap_or(0), e.to_string()));
+                    continue;
+                }
+            };
+            let line = record.position().map(|p| p.line()).unwrap_or(0);
+            let field = |idx: usize| record.get(idx).unwrap_or("").trim();
+
+            if record.iter().all(|f| f.trim().is_empty()) {
+                result.skipped += 1;
+                continue;
+            }
+
+            let date = match NaiveDate::parse_from_str(field(date_idx), &mapping.date_format) {
+                Ok(d) => d,
+                Err(_) => {
+                    result.errors.push((line, format!("invalid date '{}'", field(date_idx))));
+                    continue;
+                }
+            };
+
+            let flow_amount = match amount_idx {
+                (signed_idx, None) => parse_signed_amount(field(signed_idx)),
+                (debit_idx, Some(credit_idx)) => parse_split_amount(field(debit_idx), field(credit_idx)),
+            };
+
+            match flow_amount {
+                Ok((flow, amount)) => rows.push(BankRow {
+                    line,
+                    date,
+                    flow,
+                    amount,
+                    description: field(desc_idx).to_string(),
+                }),
+                Err(e) => result.errors.push((line, e.to_string())),
+            }
+        }
+
+        // Credits first on a same day, so a debit is not refused for lack of funds
+        rows.sort_by_key(|r| (r.date, r.flow.is_debit()));
+
+        let mut existing: HashMap<String, usize> = HashMap::new();
+        if skip_existing {
+            for op in &self.operations {
+                *existing.entry(op.dedup_key()).or_default() += 1;
+            }
+        }
+
+        for row in rows {
+            if skip_existing {
+                let description = Operation::normalize_description(&row.description, self.description_style);
+                let key = Operation::new(
+                    OperationKind::Regular(RegularKind::Transaction),
+                    row.flow,
+                    &row.date.format("%Y-%m-%d").to_string(),
+                    row.amount,
+                    description,
+                )?.dedup_key();
+                if let Some(count) = existing.get_mut(&key)
+                    && *count > 0
+                {
+                    *count -= 1;
+                    result.existing += 1;
+                    continue;
+                }
+            }
+
+            match self.add_operation(
+                OperationKind::Regular(RegularKind::Transaction),
+                row.flow,
+                &row.date.format("%Y-%m-%d").to_string(),
+                row.amount,
+                &row.description,
+            ) {
+                Ok(()) => result.imported += 1,
+                Err(e) => result.errors.push((row.line, e.to_string())),
+            }
+        }
+
+        result.errors.sort_by_key(|(line, _)| *line);
+        log::info!("Import bank csv: {:?} loaded, {}", file_path, result.summary());
+        Ok(result)
+    }
+}
+
+/// Parse a bank amount, accepting a decimal comma (ex: "-1 234,56")
+fn parse_bank_amount(value: &str) -> Result<Amount> {
+    let mut cleaned: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if cleaned.contains(',') && !cleaned.contains('.') {
+        cleaned = cleaned.replace(',', ".");
+    } else {
+        cleaned = cleaned.replace(',', "");
+    }
+    cleaned.parse::<Amount>().map_err(|_| anyhow!("invalid amount '{}'", value))
+}
+
+/// Parse a signed amount column: the sign gives the flow
+fn parse_signed_amount(value: &str) -> Result<(OperationFlow, Amount)> {
+    let amount = parse_bank_amount(value)?;
+    if amount.is_zero() {
+        return Err(anyhow!("zero amount"));
+    }
+    Ok((OperationFlow::from_sign(amount.cents()), amount.abs()))
+}
+
+/// Parse the debit/credit columns: exactly one of them must be populated
+fn parse_split_amount(debit: &str, credit: &str) -> Result<(OperationFlow, Amount)> {
+    match (debit.is_empty(), credit.is_empty()) {
+        (false, true) => Ok((OperationFlow::Debit, parse_bank_amount(debit)?.abs())),
+        (true, false) => Ok((OperationFlow::Credit, parse_bank_amount(credit)?.abs())),
+        (false, false) => Err(anyhow!("both debit ('{}') and credit ('{}') are populated", debit, credit)),
+        (true, true) => Err(anyhow!("neither debit nor credit is populated")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::wallet::codexi::BalanceFilter;
+
+    #[test]
+    fn test_import_bank_csv_debit_credit_columns() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("bank.csv");
+        fs::write(&file_path, "\
+Date;Label;Debit;Credit
+01/11/2025;Salary;;1 500,00
+03/11/2025;Grocery;34,50;
+04/11/2025;Both;10,00;10,00
+05/11/2025;Neither;;
+06/11/2025;Rent;700,00;
+")?;
+
+        let mapping = BankCsvMapping {
+            date_col: "Date".to_string(),
+            date_format: "%d/%m/%Y".to_string(),
+            desc_col: "Label".to_string(),
+            amount: BankAmountColumns::Split { debit: "Debit".to_string(), credit: "Credit".to_string() },
+            delimiter: b';',
+        };
+
+        let mut codexi = Codexi::default();
+        let result = codexi.import_bank_csv(&file_path, &mapping, false)?;
+
+        assert_eq!(result.imported, 3, "Three rows are valid.");
+        assert_eq!(result.errors.len(), 2, "The rows with both or neither columns populated are errors.");
+        assert_eq!(result.errors[0].0, 4, "The error must carry the line number.");
+        assert_eq!(result.errors[1].0, 5, "The error must carry the line number.");
+
+        assert_eq!(codexi.operations[0].flow, OperationFlow::Credit);
+        assert_eq!(codexi.operations[0].amount, Amount::from(1500.0));
+        assert_eq!(codexi.operations[1].flow, OperationFlow::Debit);
+        assert_eq!(codexi.operations[1].amount, Amount::from(34.5));
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(765.50));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_bank_csv_malformed_record() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("bank.csv");
+        let mut content = b"Date;Label;Amount\n01/11/2025;Salary;1500\n02/11/2025;Caf".to_vec();
+        content.extend_from_slice(&[0xe9, 0xff]);
+        content.extend_from_slice(b";-3,50\n03/11/2025;Grocery;-34,50\n");
+        fs::write(&file_path, content)?;
+
+        let mapping = BankCsvMapping {
+            date_col: "Date".to_string(),
+            date_format: "%d/%m/%Y".to_string(),
+            desc_col: "Label".to_string(),
+            amount: BankAmountColumns::Signed("Amount".to_string()),
+            delimiter: b';',
+        };
+
+        let mut codexi = Codexi::default();
+        let result = codexi.import_bank_csv(&file_path, &mapping, false)?;
+
+        assert_eq!(result.imported, 2, "The rows after the malformed one are imported.");
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].0, 3, "The error must carry the line number.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_bank_csv_skip_existing() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let october = dir.path().join("october.csv");
+        let november = dir.path().join("november.csv");
+        fs::write(&october, "\
+Date,Description,Amount
+2025-10-30,Salary,1500.00
+2025-10-31,Coffee,-3.50
+2025-10-31,Coffee,-3.50
+")?;
+        // Overlaps the end of October
+        fs::write(&november, "\
+Date,Description,Amount
+2025-10-31,Coffee,-3.50
+2025-10-31,Coffee,-3.50
+2025-11-02,Grocery,-42.00
+")?;
+
+        let mapping = BankCsvMapping {
+            date_col: "Date".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            desc_col: "Description".to_string(),
+            amount: BankAmountColumns::Signed("Amount".to_string()),
+            delimiter: b',',
+        };
+
+        let mut codexi = Codexi::default();
+        assert_eq!(codexi.import_bank_csv(&october, &mapping, true)?.imported, 3, "The two coffees of the file are distinct.");
+
+        let result = codexi.import_bank_csv(&november, &mapping, true)?;
+        assert_eq!((result.imported, result.existing), (1, 2));
+
+        let result = codexi.import_bank_csv(&november, &mapping, true)?;
+        assert_eq!((result.imported, result.existing), (0, 3), "A second import adds nothing.");
+
+        assert_eq!(codexi.operations.len(), 4);
+        assert_eq!(codexi.dedupe(true)?, 1, "Only the two same-day coffees share a key.");
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(1451.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_signed_amount() -> Result<()> {
+        assert_eq!(parse_signed_amount("-12,50")?, (OperationFlow::Debit, Amount::from(12.5)));
+        assert_eq!(parse_signed_amount("1,234.56")?, (OperationFlow::Credit, Amount::from(1234.56)));
+        assert!(parse_signed_amount("0").is_err(), "A zero amount is not an operation.");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+// src/core/wallet/batch.rs
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+use chrono::NaiveDate;
+
+use super::amount::Amount;
+use super::codexi::Codexi;
+use super::file_management::ImportSummary;
+use super::operation_flow::OperationFlow;
+use super::operation_kind::OperationKind;
+use super::regular_kind::RegularKind;
+
+/// Struct for a parsed line of a .codexi batch file
+/// ex: 2025-11-06 -12.50 Lunch #food @Bistro
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchLine {
+    pub date: NaiveDate,
+    pub flow: OperationFlow,
+    pub amount: Amount,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub payee: Option<String>,
+}
+/// Methods for BatchLine
+impl BatchLine {
+
+    /// Parse a line of a batch file: `DATE SIGNED_AMOUNT [words] [#tag] [@payee]`
+    /// The sign gives the flow (+ credit, - debit), the words the description.
+    /// Returns None for a blank line or a comment (a line starting with '#').
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let mut words = line.split_whitespace();
+
+        let date_str = words.next().unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+            .map_err(|_| anyhow!("invalid date '{}' (expected YYYY-MM-DD)", date_str))?;
+
+        let amount_str = words.next().ok_or_else(|| anyhow!("missing amount after the date"))?;
+        let flow = match amount_str.chars().next() {
+            Some('+') => OperationFlow::Credit,
+            Some('-') => OperationFlow::Debit,
+            _ => return Err(anyhow!("the amount '{}' must start with + (credit) or - (debit)", amount_str)),
+        };
+        let amount = amount_str[1..].parse::<Amount>()
+            .ok()
+            .filter(|a| a.is_positive())
+            .ok_or_else(|| anyhow!("invalid amount '{}'", amount_str))?;
+
+        let mut text = Vec::new();
+        let mut tags = Vec::new();
+        let mut payee = None;
+        for word in words {
+            if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                tags.push(tag.to_string());
+            } else if let Some(name) = word.strip_prefix('@').filter(|n| !n.is_empty()) {
+                if payee.replace(name.to_string()).is_some() {
+                    return Err(anyhow!("only one @payee is allowed per line"));
+                }
+            } else {
+                text.push(word);
+            }
+        }
+
+        Ok(Some(BatchLine { date, flow, amount, description: text.join(" "), tags, payee }))
+    }
+    /// Get the kind of the operation: the #tag is the category (transaction without tag)
+    pub fn kind(&self) -> Result<OperationKind> {
+        match self.tags.as_slice() {
+            [] => Ok(OperationKind::Regular(RegularKind::Transaction)),
+            [tag] => match OperationKind::try_from_str(tag)? {
+                OperationKind::System(_) => Err(anyhow!("#{} is a system kind", tag)),
+                kind => Ok(kind),
+            },
+            _ => Err(anyhow!("only one #tag is allowed per line (the tag is the category)")),
+        }
+    }
+    /// Get the description of the operation, the payee comes first
+    /// ex: "Bistro - Lunch"
+    pub fn full_description(&self) -> String {
+        match (&self.payee, self.description.is_empty()) {
+            (Some(payee), true) => payee.clone(),
+            (Some(payee), false) => format!("{} - {}", payee, self.description),
+            (None, true) => "no description".to_string(),
+            (None, false) => self.description.clone(),
+        }
+    }
+}
+
+/// Methods for applying a batch file to the codexi
+impl Codexi {
+
+    /// Applies a .codexi batch file, one operation per line (see BatchLine::parse).
+    /// ex: codexi.apply_batch(Path::new("november.codexi"));
+    /// The operations are added through add_operation, in date order (credits first on a same day).
+    /// The batch is atomic: when a line fails (parse or integrity check) nothing is added and
+    /// the errors are returned with their line number in the ImportSummary.
+    pub fn apply_batch(&mut self, file_path: &Path) -> Result<ImportSummary> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| anyhow!("Cannot read {:?}: {}", file_path, e))?;
+
+        let mut result = ImportSummary::default();
+        let mut lines = Vec::new();
+
+        for (idx, raw) in content.lines().enumerate() {
+            let line = idx as u64 + 1;
+            let batch_line = match BatchLine::parse(raw) {
+                Ok(Some(b)) => b,
+                Ok(None) => {
+                    result.skipped += 1;
+                    continue;
+                }
+                Err(e) => {
+                    result.errors.push((line, e.to_string()));
+                    continue;
+                }
+            };
+            match batch_line.kind() {
+                Ok(kind) => lines.push((line, batch_line, kind)),
+                Err(e) => result.errors.push((line, e.to_string())),
+            }
+        }
+
+        if !result.errors.is_empty() {
+            log::info!("Apply batch: {:?} not applied, {}", file_path, result.summary());
+            return Ok(result);
+        }
+
+        lines.sort_by_key(|(_, b, _)| (b.date, b.flow.is_debit()));
+
+        let mut staged = self.clone();
+        for (line, batch_line, kind) in lines {
+            match staged.add_operation(
+                kind,
+                batch_line.flow,
+                &batch_line.date.format("%Y-%m-%d").to_string(),
+                batch_line.amount,
+                &batch_line.full_description(),
+            ) {
+                Ok(()) => result.imported += 1,
+                Err(e) => result.errors.push((line, e.to_string())),
+            }
+        }
+
+        if !result.errors.is_empty() {
+            result.errors.sort_by_key(|(line, _)| *line);
+            result.imported = 0;
+            log::info!("Apply batch: {:?} not applied, {}", file_path, result.summary());
+            return Ok(result);
+        }
+
+        *self = staged;
+        log::info!("Apply batch: {:?} applied, {}", file_path, result.summary());
+        Ok(result)
+    }
+    /// Adds the records read from a stream (ex: stdin), one `flow,date,amount,description` per line.
+    /// ex: codexi.add_records("debit,2025-11-06,12.50,Lunch\n".as_bytes(), false);
+    /// Unlike apply_batch, a failed line is only reported (with its line number) and the others are added,
+    /// in the order of the input. With `strict_balance`, a line making the running balance negative fails.
+    /// Blank lines and comments (starting with '#') are skipped.
+    pub fn add_records(&mut self, input: impl BufRead, strict_balance: bool) -> Result<ImportSummary> {
+        let mut result = ImportSummary::default();
+
+        for (idx, raw) in input.lines().enumerate() {
+            let line = idx as u64 + 1;
+            let raw = raw?;
+            let record = raw.trim();
+            if record.is_empty() || record.starts_with('#') {
+                result.skipped += 1;
+                continue;
+            }
+            match self.add_record(record, strict_balance) {
+                Ok(()) => result.imported += 1,
+                Err(e) => result.errors.push((line, e.to_string())),
+            }
+            result.log_progress(line as usize);
+        }
+
+        log::info!("Batch from the input: {}", result.summary());
+        Ok(result)
+    }
+    /// Adds one record `flow,date,amount,description` (the description may hold commas)
+    fn add_record(&mut self, record: &str, strict_balance: bool) -> Result<()> {
+        let [flow, date, amount, description] = record.splitn(4, ',').map(str::trim).collect::<Vec<_>>()[..] else {
+            return Err(anyhow!("expected 'flow,date,amount,description'"));
+        };
+        let flow = match OperationFlow::try_from_str(flow)? {
+            OperationFlow::None => return Err(anyhow!("the flow is 'debit' or 'credit'")),
+            flow => flow,
+        };
+        let amount = amount.parse::<Amount>()
+            .ok()
+            .filter(|a| a.is_positive())
+            .ok_or_else(|| anyhow!("invalid amount '{}'", amount))?;
+
+        let previous = strict_balance.then(|| self.clone());
+        self.add_operation(OperationKind::Regular(RegularKind::Transaction), flow, date, amount, description)?;
+        if let Some(previous) = previous
+            && let Err(e) = self.check_running_balance()
+        {
+            *self = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::wallet::codexi::BalanceFilter;
+
+    #[test]
+    fn test_batch_line_sign_tag_and_payee() -> Result<()> {
+        let credit = BatchLine::parse("2025-11-05 +100 Salary")?.expect("a credit line");
+        assert_eq!(credit.flow, OperationFlow::Credit);
+        assert_eq!(credit.amount, Amount::from(100.0));
+        assert_eq!(credit.kind()?, OperationKind::Regular(RegularKind::Transaction));
+        assert_eq!(credit.full_description(), "Salary");
+
+        let debit = BatchLine::parse("2025-11-06 -12.50 Lunch #food @Bistro")?.expect("a debit line");
+        assert_eq!(debit.flow, OperationFlow::Debit);
+        assert_eq!(debit.amount, Amount::from(12.50));
+        assert_eq!(debit.tags, vec!["food".to_string()]);
+        assert_eq!(debit.payee.as_deref(), Some("Bistro"));
+        assert_eq!(debit.kind()?, OperationKind::Regular(RegularKind::Custom("food".to_string())));
+        assert_eq!(debit.full_description(), "Bistro - Lunch");
+
+        assert_eq!(BatchLine::parse("  ")?, None, "A blank line is skipped.");
+        assert_eq!(BatchLine::parse("# November")?, None, "A comment is skipped.");
+        assert!(BatchLine::parse("2025-11-07 -5 #init")?.expect("a line").kind().is_err(), "A tag cannot be a system kind.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_records_reports_each_line() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-01-01", false)?;
+        let input = "\
+# migration
+credit,2025-01-05,40,Salary, January
+debit,2025-01-06,12.50,Lunch
+
+transfer,2025-01-07,5,Unknown flow
+debit,2025-01-07,abc,Bad amount
+debit,2025-01-08,1000,Too much
+debit,2025-01-09
+debit,2025-01-10,2.50,Coffee
+";
+        let result = codexi.add_records(input.as_bytes(), false)?;
+
+        assert_eq!(result.imported, 3);
+        assert_eq!(result.skipped, 2, "The comment and the blank line.");
+        let failed: Vec<u64> = result.errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(failed, vec![5, 6, 7, 8]);
+        assert!(result.errors[1].1.contains("invalid amount 'abc'"));
+        assert_eq!(codexi.operations.len(), 4, "The valid lines are added, the others do not abort the batch.");
+        assert_eq!(codexi.operations[1].description, "Salary, January");
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(125.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_batch_is_atomic() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("november.codexi");
+        fs::write(&file_path, "\
+# November
+2025-11-05 +100 Salary
+2025-11-06 -12.50 Lunch #food
+2025-11-07 12 Coffee
+")?;
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(50.0, "2025-11-01", false)?;
+        let before = codexi.operations.clone();
+
+        let result = codexi.apply_batch(&file_path)?;
+        assert_eq!(result.errors.len(), 1, "The unsigned amount is an error.");
+        assert_eq!(result.errors[0].0, 4, "The error must carry the line number.");
+        assert_eq!(codexi.operations, before, "Nothing is added when a line fails.");
+
+        fs::write(&file_path, "2025-11-05 +100 Salary\n2025-11-06 -12.50 Lunch #food\n")?;
+        let result = codexi.apply_batch(&file_path)?;
+        assert_eq!(result.imported, 2);
+        assert_eq!(codexi.operations.len(), 3);
+
+        Ok(())
+    }
+}
@@ -3,121 +3,581 @@
 use anyhow::{Result, anyhow};
 use std::fs;
 use std::mem;
-
-use std::cmp::Ordering;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+use std::collections::BTreeMap;
+
+use std::cmp::{Ordering, Reverse};
+use std::ops::Range;
+use thiserror::Error;
 use serde::{Serialize, Deserialize};
-use chrono::{NaiveDate, Datelike};
+use chrono::{NaiveDate, Datelike, Local, Weekday};
+use regex::RegexBuilder;
 
+use super::amount::Amount;
 use super::operation_flow::OperationFlow;
 use super::operation_kind::OperationKind;
 use super::system_kind::SystemKind;
 use super::regular_kind::RegularKind;
 use super::operation::Operation;
+use super::operation::AdjustmentInfo;
+use super::operation::DescriptionStyle;
+use super::schema::FieldSchema;
 use crate::core::helpers::calculate_new_balance;
-use crate::core::helpers::parse_flexible_date_range;
+use crate::core::helpers::date_range_bounds;
 use crate::core::helpers::get_archive_path;
 use crate::core::helpers::round_to_2_dec;
-
+use crate::core::helpers::previous_month;
+use crate::core::helpers::month_bounds;
+use crate::core::helpers::write_atomic;
+use crate::core::helpers::JsonStyle;
+
+/// Error type for the integrity checks of the codexi
+/// The fields keep the context of the conflict, see helpers::pretty_error for the suggestions.
+#[derive(Debug, Error)]
+pub enum CodexiError {
+    #[error("Date conflict with period closure.")]
+    ClosedPeriod { date: NaiveDate, close_date: NaiveDate },
+    #[error("Date conflict with system anchor.")]
+    BeforeAnchor { date: NaiveDate, anchor_date: NaiveDate },
+    #[error("Insufficient funds.")]
+    InsufficientFunds { balance: Amount, amount: Amount },
+}
 /// Struct for resume result
-#[derive(Debug, Clone)]
+/// Serialized as is by `report resume --json` and `--format json` (dates as YYYY-MM-DD or null)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResumeResult {
     pub current_nb_transaction: usize,
     pub current_nb_init: usize,
     pub current_nb_adjust: usize,
     pub current_nb_close: usize,
     pub current_nb_op: usize,
-    pub current_balance: f64,
-    pub latest_transaction_date: String,
-    pub latest_init_date: String,
-    pub latest_adjust_date: String,
-    pub latest_close_date: String,
+    pub current_balance: Amount,
+    /// Sum of the signed deviations of the adjustments (positive: balance raised)
+    pub adjustment_total: Amount,
+    pub latest_transaction_date: Option<NaiveDate>,
+    pub latest_init_date: Option<NaiveDate>,
+    pub latest_adjust_date: Option<NaiveDate>,
+    pub latest_close_date: Option<NaiveDate>,
+}
+/// Struct for the filters of balance, running_balance and balance_by_payee
+/// ex: BalanceFilter { month: Some("2025-11".into()), ..Default::default() }
+/// The default filters nothing.
+#[derive(Debug, Clone, Default)]
+pub struct BalanceFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub day: Option<String>,
+    pub month: Option<String>,
+    pub year: Option<String>,
+    pub amount_min: Option<Amount>,
+    pub amount_max: Option<Amount>,
+    /// The system operations stay in the total but not in the credit/debit
+    pub exclude_system: bool,
+    /// These kinds stay in the total but not in the credit/debit
+    pub exclude_kinds: Vec<OperationKind>,
 }
 /// Struct for balance result
-#[derive(Debug, Clone)]
+/// Serialized as is by `report balance` with `--format json`
+#[derive(Debug, Clone, Serialize)]
 pub struct BalanceResult {
-    pub credit: f64,
-    pub debit: f64,
-    pub total: f64,
+    pub credit: Amount,
+    pub debit: Amount,
+    pub total: Amount,
+    /// Net of the regular operations only, set when the system operations are excluded
+    pub activity: Option<Amount>,
+    /// Number of operations matching the filters
+    pub count: usize,
+    /// Average spend over the calendar span of the filters, set on request (see average_spend)
+    pub average: Option<AverageSpend>,
+}
+/// Struct for the average spend (total debit) over a calendar span
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AverageSpend {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// Number of calendar days of the span (bounds included)
+    pub days: i64,
+    /// Number of months of the span, a partial month counts for its share of days
+    pub months: f64,
+    pub per_day: Amount,
+    pub per_month: Amount,
+}
+/// Struct for balance comparison result (period over previous period)
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceCompareResult {
+    pub period: String,
+    pub current: BalanceResult,
+    pub previous_period: String,
+    pub previous: BalanceResult,
+}
+/// Struct for the balance of one month
+#[derive(Debug, Clone)]
+pub struct MonthlyBalance {
+    pub month: String,
+    pub balance: BalanceResult,
+}
+/// Struct for the progress toward a savings goal
+#[derive(Debug, Clone)]
+pub struct GoalResult {
+    pub balance: Amount,
+    pub target: Amount,
+    /// Amount still to save (0 when the goal is reached)
+    pub remaining: Amount,
+    pub percent: f64,
+    /// Average net of the regular operations per month, None without regular operation
+    pub monthly_average: Option<Amount>,
+    /// Estimated months to reach the goal, None when the monthly average is not positive
+    pub months_to_goal: Option<u32>,
+}
+/// Struct for one period close of the history
+#[derive(Debug, Clone)]
+pub struct CloseEntry {
+    pub date: NaiveDate,
+    /// Number of archived operations, None when the archive file is missing
+    pub archived: Option<usize>,
+    pub carry_forward: Amount,
+    /// Change of the carry-forward since the previous close (effective net of the period)
+    pub delta: Option<Amount>,
+}
+/// Struct for the preview of a period close (nothing is changed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosePreview {
+    pub date: NaiveDate,
+    /// Number of operations that would be archived
+    pub archived: usize,
+    /// Balance carried forward by the close
+    pub carry_forward: Amount,
+}
+/// Struct for the activity statistics (operations per day)
+#[derive(Debug, Clone, Default)]
+pub struct ActivityResult {
+    /// Number of regular operations in the range
+    pub count: usize,
+    /// Number of days with at least one operation
+    pub active_days: usize,
+    /// Day with the most operations and its number of operations
+    pub busiest_day: Option<(NaiveDate, usize)>,
+    /// Day with the highest total debit and this total
+    pub highest_spend_day: Option<(NaiveDate, Amount)>,
+    pub average_per_day: f64,
+    pub median_per_day: f64,
+}
+/// Struct for the discretionary spend (see Codexi::discretionary for the policy)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiscretionaryResult {
+    /// Debits of the transactions and custom categories
+    pub spend: Amount,
+    /// Credits of the refunds
+    pub refunds: Amount,
+    /// Discretionary spend: spend - refunds
+    pub total: Amount,
+    /// Number of operations counted
+    pub count: usize,
+    /// Number of regular operations ignored by the policy (fees, transfers, credits)
+    pub ignored: usize,
+}
+/// Struct for the spend of a payee (see Codexi::balance_by_payee)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PayeeTotal {
+    pub payee: String,
+    /// Number of debits
+    pub count: usize,
+    /// Total of the debits
+    pub total: Amount,
+}
+/// Struct for the spend per payee, sorted by total descending
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PayeeBreakdown {
+    pub payees: Vec<PayeeTotal>,
+    /// The payees beyond the top or below the threshold, merged (count 0 when none)
+    pub other: PayeeTotal,
+    /// Total of the debits of the period
+    pub total: Amount,
+}
+/// Name of the group of the operations without category (see Codexi::balance_by_category)
+pub const UNCATEGORIZED: &str = "uncategorized";
+/// Struct for the totals of a category (see Codexi::balance_by_category)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryTotal {
+    pub category: String,
+    pub credit: Amount,
+    pub debit: Amount,
+    /// Number of operations of the category
+    pub count: usize,
+}
+/// Struct for the distribution of the amounts of one flow (see Codexi::stats)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmountStats {
+    pub count: usize,
+    pub mean: Amount,
+    /// Middle amount, the mean of the two middle ones for an even count
+    pub median: Amount,
+    pub min: Amount,
+    pub max: Amount,
+}
+/// Struct for the stats report: the credits and the debits apart (None without operation)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsResult {
+    pub credit: Option<AmountStats>,
+    pub debit: Option<AmountStats>,
+    /// The operation with the largest amount (the first one on a tie)
+    pub largest: Option<Operation>,
+}
+/// Struct for a gap between two consecutive operation dates (see Codexi::gaps)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapEntry {
+    /// Date of the last operation before the gap
+    pub start: NaiveDate,
+    /// Date of the first operation after the gap
+    pub end: NaiveDate,
+    /// Days from start to end
+    pub days: i64,
 }
 /// Struct for search item
-#[derive(Clone)]
+/// Serialized by `search --format json` as the operation fields plus the index and the running balance
+#[derive(Clone, Serialize)]
 pub struct SearchItem<'a> {
     pub index: i32,
+    #[serde(flatten)]
     pub op: &'a Operation,
-    pub balance: f64,
+    pub balance: Amount,
+}
+/// Enum for the order of the search results
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchSort {
+    /// Chronological order
+    #[default]
+    Date,
+    /// Smallest amount first, then by date
+    Amount,
+    /// Largest amount first, then by date
+    DescAmount,
+}
+/// Enum for the fields scanned by the text filter of the search
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchField {
+    /// The description only
+    #[default]
+    Desc,
+    /// The category only (the uncategorized operations never match)
+    Category,
+    /// The description or the category
+    All,
+}
+/// Struct for the filters of search
+/// ex: SearchFilter { text: vec!["rent".into()], flagged: true, ..Default::default() }
+/// The default keeps every operation in chronological order.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Any of the terms matches, the empty ones are ignored
+    pub text: Vec<String>,
+    /// The terms are case-insensitive regular expressions instead of substrings
+    pub regex: bool,
+    /// Fields the terms are searched in, any of them matches
+    pub text_in: SearchField,
+    pub kind: Option<String>,
+    /// Some(true) keeps only the system operations, Some(false) only the regular ones
+    pub system: Option<bool>,
+    pub flow: Option<String>,
+    pub day: Option<String>,
+    /// Keeps the operations of a day of the week (ex: the Saturday spending)
+    pub weekday: Option<Weekday>,
+    pub amount_min: Option<Amount>,
+    pub amount_max: Option<Amount>,
+    /// Keeps only the starred operations (see set_flag)
+    pub flagged: bool,
+    /// Order of the results, the balance stays the chronological running balance
+    pub sort: SearchSort,
+    /// Keeps the N most recent results, or the N first ones with an amount sort
+    pub latest: Option<usize>,
+}
+/// Struct for the fields changed by edit_operation, None keeps the field as is
+/// ex: OperationEdit { amount: Some(Amount::from(12.5)), append: Some("paid back"), ..Default::default() }
+#[derive(Debug, Clone, Copy)]
+pub struct OperationEdit<'a> {
+    pub date: Option<&'a str>,
+    pub amount: Option<Amount>,
+    pub kind: Option<&'a str>,
+    pub flow: Option<&'a str>,
+    /// Replaces the description
+    pub description: Option<&'a str>,
+    /// Joined after the description with the separator
+    pub append: Option<&'a str>,
+    /// Joined before the description with the separator
+    pub prepend: Option<&'a str>,
+    pub separator: &'a str,
+}
+/// The separator defaults to a space, as edit --separator
+impl Default for OperationEdit<'_> {
+    fn default() -> Self {
+        OperationEdit { date: None, amount: None, kind: None, flow: None, description: None, append: None, prepend: None, separator: " " }
+    }
+}
+/// Struct for a page of the search results (see search --page and --page-size)
+/// The total is set by the search, once the results are filtered and sorted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchPage {
+    /// Page number, from 1
+    pub number: usize,
+    /// Operations per page
+    pub size: usize,
+    /// Number of operations found, all pages included
+    pub total: usize,
+}
+/// Methods for SearchPage
+impl SearchPage {
+
+    /// ex: SearchPage::new(2, 20)? is the operations 21 to 40
+    pub fn new(number: usize, size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(anyhow!("The page size must be at least 1."));
+        }
+        Ok(SearchPage { number, size, total: 0 })
+    }
+    /// Number of pages (0 without operation)
+    pub fn count(&self) -> usize {
+        self.total.div_ceil(self.size)
+    }
+    /// Positions of the page in the results, empty when the page is out of range
+    pub fn range(&self) -> Range<usize> {
+        let start = match self.number.checked_sub(1) {
+            Some(n) => n.saturating_mul(self.size).min(self.total),
+            None => self.total,
+        };
+        start..start.saturating_add(self.size).min(self.total)
+    }
+}
+/// Enum for the policy applied to operations dated after today
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FutureDatePolicy {
+    /// Reject the operation (a typo in the year is easy, and it would skew the recurring rules and forecasts)
+    #[default]
+    Reject,
+    /// Accept the operation (see --allow-future)
+    Allow,
 }
 /// Struct representing the codexi
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Codexi {
+    /// Name of the ledger shown in the view headers (ex: "Joint Account")
+    #[serde(default)]
+    pub title: Option<String>,
     pub operations: Vec<Operation>,
+    /// Policy for the future dates (runtime setting, not stored)
+    #[serde(skip)]
+    pub future_dates: FutureDatePolicy,
+    /// Normalization of the descriptions of the new operations (runtime setting, not stored)
+    #[serde(skip)]
+    pub description_style: DescriptionStyle,
+    /// Totals of all the operations (runtime cache, not stored), computed on the first use
+    /// and kept up to date by the changes of the operations. None when it must be computed again.
+    #[serde(skip)]
+    pub(super) cached_totals: Cell<Option<Totals>>,
+}
+/// Struct for the totals of all the operations, the balance without filter
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct Totals {
+    credit: Amount,
+    debit: Amount,
+    count: usize,
+}
+/// Methods for Totals
+impl Totals {
+
+    fn of(operations: &[Operation]) -> Self {
+        let mut totals = Totals::default();
+        operations.iter().for_each(|op| totals.add(op));
+        totals
+    }
+    fn add(&mut self, op: &Operation) {
+        match op.flow {
+            OperationFlow::Credit => self.credit += op.amount,
+            OperationFlow::Debit => self.debit += op.amount,
+            OperationFlow::None => {},
+        }
+        self.count += 1;
+    }
+    fn remove(&mut self, op: &Operation) {
+        match op.flow {
+            OperationFlow::Credit => self.credit -= op.amount,
+            OperationFlow::Debit => self.debit -= op.amount,
+            OperationFlow::None => {},
+        }
+        self.count -= 1;
+    }
+    fn total(&self) -> Amount {
+        self.credit - self.debit
+    }
 }
 /// Methods for codexi
 impl Codexi {
 
+    /// Fields of the codexi, for the schema (keep in sync with the struct, runtime settings excluded)
+    pub const FIELDS: &'static [FieldSchema] = &[
+        FieldSchema { name: "title", type_name: "string", optional: true, description: "Name of the ledger" },
+        FieldSchema { name: "operations", type_name: "array of operation", optional: false, description: "Operations of the ledger, in date order" },
+    ];
+
+    /// Sets the title of the codexi, an empty title removes it.
+    /// ex: codexi.set_title("Joint Account");
+    pub fn set_title(&mut self, title: &str) {
+        let title = title.trim();
+        self.title = (!title.is_empty()).then(|| title.to_string());
+        log::info!("codexi title set to {:?}", self.title);
+    }
+
     /// This function adds a new operation to the codexi while ensuring data integrity.
     /// ex: codexi.add_operation(...);
     /// It checks for date conflicts with existing system operations (Init, Close, Adjust)
     /// and ensures that debit operations do not exceed the current balance.
+    /// A date after today is rejected, unless the future_dates policy allows it (see --allow-future).
+    /// The description is normalized according to the description_style setting.
     pub fn add_operation(&mut self,
         kind:OperationKind,
         flow: OperationFlow,
         date: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
         description: &str,
     ) -> Result<()>
     {
-        let new_op_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
-
-        let latest_close_date = self.operations.iter()
-            .filter(|op| matches!(op.kind, OperationKind::System(SystemKind::Close)))
-            .map(|op| op.date)
-            .max();
+        self.add_operation_with_category(kind, flow, date, amount, description, None)
+    }
 
-        let latest_non_strict_date = self.operations.iter()
-            .filter(|op| matches!(op.kind, OperationKind::System(SystemKind::Init) | OperationKind::System(SystemKind::Adjust)))
-            .map(|op| op.date)
-            .max();
+    /// Same as add_operation, with a category for the category report (blank: none).
+    /// ex: codexi.add_operation_with_category(..., Some("groceries"));
+    pub fn add_operation_with_category(&mut self,
+        kind:OperationKind,
+        flow: OperationFlow,
+        date: &str,
+        amount: impl Into<Amount>,
+        description: &str,
+        category: Option<&str>,
+    ) -> Result<()>
+    {
+        let description = Operation::normalize_description(description, self.description_style);
+        let mut op = Operation::new(kind, flow, date, amount, description)?;
+        op.category = category.map(str::trim).filter(|c| !c.is_empty()).map(str::to_string);
+        self.insert_operation(op)
+    }
 
+    /// Same as add_operation, returns the total of the codexi once the operation is added.
+    /// ex: let balance = codexi.apply_operation(...)?;
+    /// The totals of the codexi are kept up to date, the operations are not scanned again.
+    pub fn apply_operation(&mut self,
+        kind: OperationKind,
+        flow: OperationFlow,
+        date: &str,
+        amount: impl Into<Amount>,
+        description: &str,
+    ) -> Result<Amount>
+    {
+        self.add_operation(kind, flow, date, amount, description)?;
+        Ok(self.totals().total())
+    }
 
-        if let Some(close_date) = latest_close_date {
-            if new_op_date <= close_date {
-                log::error!(
-                    "Operation date ({}) cannot be on or before the last period close date ({}).",
-                    new_op_date, close_date
-                );
-                return Err(anyhow::anyhow!("Date conflict with period closure."));
+    /// Totals of all the operations, from the cache (computed on the first use).
+    fn totals(&self) -> Totals {
+        match self.cached_totals.get() {
+            Some(totals) => totals,
+            None => {
+                let totals = Totals::of(&self.operations);
+                self.cached_totals.set(Some(totals));
+                totals
             }
         }
+    }
 
-        if let Some(anchor_date) = latest_non_strict_date {
-            if new_op_date < anchor_date {
-                log::error!(
-                    "Operation date ({}) cannot be before the latest system anchor date ({}).",
-                    new_op_date, anchor_date
-                );
-                return Err(anyhow::anyhow!("Date conflict with system anchor."));
-            }
+    /// Updates the cached totals (when they are computed) for a change of the operations.
+    fn update_totals<'a>(&self, added: impl IntoIterator<Item = &'a Operation>, removed: impl IntoIterator<Item = &'a Operation>) {
+        if let Some(mut totals) = self.cached_totals.get() {
+            removed.into_iter().for_each(|op| totals.remove(op));
+            added.into_iter().for_each(|op| totals.add(op));
+            self.cached_totals.set(Some(totals));
         }
+    }
+
+    /// Forgets the cached totals, after a change of several operations (ex: an import).
+    pub(super) fn reset_totals(&self) {
+        self.cached_totals.set(None);
+    }
 
-        if flow == OperationFlow::Debit {
-            let current_balance = self.balance(None, None, None, None, None)?.total;
+    /// Inserts a built operation, with the integrity checks of add_operation.
+    fn insert_operation(&mut self, op: Operation) -> Result<()> {
+        self.check_new_date(op.date)?;
 
-            if current_balance < amount {
+        if op.flow == OperationFlow::Debit {
+            let current_balance = self.totals().total();
+
+            if current_balance < op.amount {
                 log::error!("Debit operation cannot be added. Insufficient funds: Current balance is {} but debit amount is {}.",
                     current_balance,
-                    amount
+                    op.amount
                 );
-                return Err(anyhow!("Date conflict with system anchor."));
+                return Err(CodexiError::InsufficientFunds { balance: current_balance, amount: op.amount }.into());
             }
         }
 
-        let op = Operation::new(kind, flow, date, amount, description)?;
-        self.operations.push(op.clone());
-        self.operations.sort_by_key(|o| o.date);
+        self.update_totals([&op], []);
+        // After the operations of the same day, as a stable sort by date would put it:
+        // the order of the existing ones (ex: the kind order of close_period) is kept.
+        let position = self.operations.partition_point(|o| o.date <= op.date);
+        self.operations.insert(position, op.clone());
         log::info!("Operation added : {}", op);
         Ok(())
     }
 
+    /// Checks the date of a new (or moved) regular operation: the future_dates policy,
+    /// not on or before the latest close and not before the latest init/adjust anchor.
+    pub(super) fn check_new_date(&self, new_op_date: NaiveDate) -> Result<()> {
+        let today = Local::now().date_naive();
+        if new_op_date > today {
+            match self.future_dates {
+                FutureDatePolicy::Reject => {
+                    log::error!("Operation date ({}) cannot be after today ({}).", new_op_date, today);
+                    return Err(anyhow!("Date {} is after today ({}), use --allow-future to accept it.", new_op_date, today));
+                }
+                FutureDatePolicy::Allow => log::info!("Operation date ({}) is in the future (today is {}).", new_op_date, today),
+            }
+        }
+
+        let latest_close_date = self.operations.iter()
+            .filter(|op| matches!(op.kind, OperationKind::System(SystemKind::Close)))
+            .map(|op| op.date)
+            .max();
+
+        let latest_non_strict_date = self.operations.iter()
+            .filter(|op| matches!(op.kind, OperationKind::System(SystemKind::Init) | OperationKind::System(SystemKind::Adjust)))
+            .map(|op| op.date)
+            .max();
+
+
+        if let Some(close_date) = latest_close_date
+            && new_op_date <= close_date
+        {
+            log::error!(
+                "Operation date ({}) cannot be on or before the last period close date ({}).",
+                new_op_date, close_date
+            );
+            return Err(CodexiError::ClosedPeriod { date: new_op_date, close_date }.into());
+        }
+
+        if let Some(anchor_date) = latest_non_strict_date
+            && new_op_date < anchor_date
+        {
+            log::error!(
+                "Operation date ({}) cannot be before the latest system anchor date ({}).",
+                new_op_date, anchor_date
+            );
+            return Err(CodexiError::BeforeAnchor { date: new_op_date, anchor_date }.into());
+        }
+
+        Ok(())
+    }
+
     /// This function removes an operation at the specified index.
     /// ex: codexi.delete_operation(3);
     /// It checks if the operation is a system operation (Init, Close, Adjust) and prevents deletion if so.
@@ -128,7 +588,7 @@ impl Codexi {
             return Err(anyhow::anyhow!("Operation index {} is out of bounds.", index));
         }
 
-        let op_kind = self.operations[index].kind;
+        let op_kind = &self.operations[index].kind;
 
         if matches!(
             op_kind,
@@ -142,34 +602,285 @@ impl Codexi {
             ));
         }
 
-        self.operations.remove(index);
+        let removed = self.operations.remove(index);
+        self.update_totals([], [&removed]);
         log::info!("Operation #{} successfully removed.", index);
 
         Ok(())
     }
 
+    /// Change the kind (and optionally the flow) of an operation in place.
+    /// ex: codexi.recategorize(3, "fee", Some("debit"));
+    /// Protected system entries cannot be changed and a regular operation cannot become a system one.
+    /// When the flow flips to debit, the final balance must stay positive (as for a new debit).
+    pub fn recategorize(&mut self, index: usize, kind: &str, flow: Option<&str>) -> Result<()> {
+        let Some(op) = self.operations.get(index) else {
+            return Err(anyhow!("Operation index {} is out of bounds.", index));
+        };
+        if op.kind.is_system() {
+            return Err(anyhow!("Operation #{} cannot be changed: it is a protected system entry.", index));
+        }
+
+        let new_kind = OperationKind::try_from_str(kind)?;
+        if new_kind.is_system() {
+            return Err(anyhow!("Operation #{} cannot become a system entry ({}).", index, new_kind.as_str()));
+        }
+        let new_flow = match flow {
+            Some(f) => OperationFlow::try_from_str(f)?,
+            None => op.flow,
+        };
+        if new_flow.is_none() {
+            return Err(anyhow!("A regular operation must be a debit or a credit."));
+        }
+
+        let previous = self.operations[index].clone();
+        self.operations[index].kind = new_kind;
+        self.operations[index].flow = new_flow;
+        self.update_totals([&self.operations[index]], [&previous]);
+
+        if new_flow != previous.flow && new_flow.is_debit() {
+            let balance = self.totals().total();
+            if balance.is_negative() {
+                self.update_totals([&previous], [&self.operations[index]]);
+                self.operations[index] = previous;
+                return Err(anyhow!("Operation #{} cannot become a debit. Insufficient funds: the balance would be {}.", index, balance));
+            }
+        }
+
+        log::info!("Operation #{} recategorized: {}", index, self.operations[index]);
+        Ok(())
+    }
+
+    /// Star (or unstar) an operation for a follow-up.
+    /// ex: codexi.set_flag(3, true);
+    pub fn set_flag(&mut self, index: usize, flagged: bool) -> Result<()> {
+        let Some(op) = self.operations.get_mut(index) else {
+            return Err(anyhow!("Operation index {} is out of bounds.", index));
+        };
+
+        op.flagged = flagged;
+        log::info!("Operation #{} {}: {}", index, if flagged { "flagged" } else { "unflagged" }, op);
+        Ok(())
+    }
+
+    /// Edit an operation in place, only the given fields change.
+    /// ex: codexi.edit_operation(3, &OperationEdit { amount: Some(Amount::from_cents(1250)), append: Some("paid back"), ..Default::default() });
+    /// The description is replaced by `description` if given, then `append`/`prepend` are
+    /// joined to it with the separator. A new date is checked as for add_operation (closes, anchors)
+    /// and the operations are sorted again. Of a protected system entry only the amount can be edited.
+    /// The running balance must stay positive over the whole codexi, else nothing is changed.
+    pub fn edit_operation(&mut self, index: usize, edit: &OperationEdit) -> Result<()> {
+        let OperationEdit { date, amount, kind, flow, description, append, prepend, separator } = *edit;
+        let Some(original) = self.operations.get(index).cloned() else {
+            return Err(anyhow!("Operation index {} is out of bounds.", index));
+        };
+        let mut edited = original.clone();
+
+        if description.is_some() || append.is_some() || prepend.is_some() {
+            let mut new_description = description.unwrap_or(&original.description).trim().to_string();
+            if let Some(text) = append.map(str::trim).filter(|t| !t.is_empty()) {
+                new_description = format!("{}{}{}", new_description, separator, text);
+            }
+            if let Some(text) = prepend.map(str::trim).filter(|t| !t.is_empty()) {
+                new_description = format!("{}{}{}", text, separator, new_description);
+            }
+            if new_description.trim().is_empty() {
+                return Err(anyhow!("The description of operation #{} cannot be empty.", index));
+            }
+            edited.description = new_description;
+        }
+        if let Some(date) = date {
+            edited.date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+        }
+        if let Some(amount) = amount {
+            if amount.is_negative() {
+                return Err(anyhow!("Invalid amount {} for operation #{}.", amount, index));
+            }
+            edited.amount = amount;
+        }
+        if let Some(kind) = kind {
+            edited.kind = OperationKind::try_from_str(kind)?;
+        }
+        if let Some(flow) = flow {
+            edited.flow = OperationFlow::try_from_str(flow)?;
+        }
+
+        if original.kind.is_system() {
+            if edited.kind != original.kind {
+                return Err(anyhow!("The kind of operation #{} cannot be changed: it is a protected system entry ({}).", index, original.kind.as_str()));
+            }
+            if edited.date != original.date || edited.flow != original.flow || edited.description != original.description {
+                return Err(anyhow!("Operation #{} is a protected system entry: only its amount can be edited.", index));
+            }
+            // The adjustment details follow the new amount
+            if let Some(info) = edited.adjustment.as_mut() {
+                info.deviation = edited.amount * edited.flow.to_sign();
+                info.physical_balance = info.previous_balance + info.deviation;
+            }
+        } else {
+            if edited.kind.is_system() {
+                return Err(anyhow!("Operation #{} cannot become a system entry ({}).", index, edited.kind.as_str()));
+            }
+            if edited.flow.is_none() {
+                return Err(anyhow!("A regular operation must be a debit or a credit."));
+            }
+        }
+
+        // A regular operation is never an anchor, its date is checked against the others
+        if edited.date != original.date {
+            self.check_new_date(edited.date)?;
+        }
+
+        let backup = self.operations.clone();
+        let moved = edited.date != original.date;
+        self.update_totals([&edited], [&original]);
+        self.operations[index] = edited.clone();
+        if moved {
+            self.operations.sort_by_key(|o| o.date);
+        }
+        if let Err(e) = self.check_running_balance() {
+            self.update_totals([&original], [&edited]);
+            self.operations = backup;
+            return Err(anyhow!("Operation #{} cannot be edited: {}", index, e));
+        }
+
+        let new_index = self.operations.iter().position(|op| op.id == original.id).unwrap_or(index);
+        log::info!("Operation #{} edited (now #{}): {}", index, new_index, self.operations[new_index]);
+        Ok(())
+    }
+
+    /// Find the index of an operation from its id.
+    /// ex: codexi.index_of_id("3f2a9c1e");
+    /// The full id or a unique prefix of it (ex: the short id) is accepted.
+    pub fn index_of_id(&self, id: &str) -> Result<usize> {
+        let prefix = id.trim().to_lowercase().replace('-', "");
+        if prefix.is_empty() {
+            return Err(anyhow!("An operation id is required."));
+        }
+
+        let matches: Vec<usize> = self.operations.iter()
+            .enumerate()
+            .filter(|(_, op)| op.id.simple().to_string().starts_with(&prefix))
+            .map(|(index, _)| index)
+            .collect();
+
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(anyhow!("No operation with the id '{}'.", id)),
+            _ => Err(anyhow!("The id '{}' matches {} operations, give more characters.", id, matches.len())),
+        }
+    }
+
+    /// This function removes several operations at once (ex: the result of a search).
+    /// ex: codexi.delete_operations(&[2, 5, 7], None, Some(10));
+    /// Protected system entries (Init, Close, Adjust) are skipped and not counted.
+    /// The guards protect against a too broad filter: if the number of removed operations
+    /// differs from `expect_removed` or exceeds `max_removed`, the removal is rolled back.
+    /// Returns the number of removed operations.
+    pub fn delete_operations(
+        &mut self,
+        indices: &[usize],
+        expect_removed: Option<usize>,
+        max_removed: Option<usize>,
+    ) -> Result<usize>
+    {
+        let backup = self.operations.clone();
+
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        let mut removed = 0;
+        // Remove from the end so the remaining indices stay valid
+        for &index in sorted.iter().rev() {
+            if index >= self.operations.len() {
+                self.operations = backup;
+                return Err(anyhow!("Operation index {} is out of bounds.", index));
+            }
+            if self.operations[index].kind.is_system() {
+                log::warn!("Operation #{} skipped: it is a protected system entry.", index);
+                continue;
+            }
+            self.operations.remove(index);
+            removed += 1;
+        }
+
+        if let Some(expected) = expect_removed
+            && removed != expected
+        {
+            self.operations = backup;
+            log::error!("Removal aborted: {} operations matched but {} were expected.", removed, expected);
+            return Err(anyhow!("Removal aborted: {} operations matched, expected {}.", removed, expected));
+        }
+
+        if let Some(max) = max_removed
+            && removed > max
+        {
+            self.operations = backup;
+            log::error!("Removal aborted: {} operations matched, more than the maximum of {}.", removed, max);
+            return Err(anyhow!("Removal aborted: {} operations matched, maximum is {}.", removed, max));
+        }
+
+        self.update_totals([], sorted.iter().filter_map(|&index| backup.get(index)).filter(|op| !op.kind.is_system()));
+        log::info!("{} operations successfully removed.", removed);
+        Ok(removed)
+    }
+
+    /// This function collapses the duplicated operations (same date, flow, kind, amount and description).
+    /// ex: codexi.dedupe(false);
+    /// The first operation of each group is kept, protected system entries are never removed.
+    /// With `dry_run`, nothing is removed.
+    /// Returns the number of duplicates (removed or to remove).
+    pub fn dedupe(&mut self, dry_run: bool) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let duplicates: Vec<bool> = self.operations
+            .iter()
+            .map(|op| !op.kind.is_system() && !seen.insert(op.dedup_key()))
+            .collect();
+        let count = duplicates.iter().filter(|d| **d).count();
+
+        if dry_run || count == 0 {
+            log::info!("{} duplicated operations found.", count);
+            return Ok(count);
+        }
+
+        self.update_totals([], self.operations.iter().zip(&duplicates).filter(|(_, duplicate)| **duplicate).map(|(op, _)| op));
+        let mut flags = duplicates.into_iter();
+        self.operations.retain(|_| !flags.next().unwrap_or(false));
+
+        log::info!("{} duplicated operations removed.", count);
+        Ok(count)
+    }
+
     /// Sets the initial balance of the codexi.
-    /// ex: codexi.initialize(1000.0, "2024-07-01");
+    /// ex: codexi.initialize(1000.0, "2024-07-01", false);
     /// This function creates an initial operation representing the starting balance.
     /// It should only be called when the codexi is empty.
+    /// With `if_empty`, a non-empty codexi is left unchanged instead of returning an error.
     pub fn initialize(
         &mut self,
-        amount: f64,
+        amount: impl Into<Amount>,
         date_str: &str,
+        if_empty: bool,
     ) -> Result<()>
     {
+        let amount = amount.into();
         if !self.operations.is_empty() {
+            if if_empty {
+                log::info!("The codexi is not empty, initialization skipped.");
+                return Ok(());
+            }
             return Err(anyhow::anyhow!("The codexi is not empty. Cannot set initial balance."));
         }
 
-        let op_flow = OperationFlow::from_sign(amount);
-        let description = format!("INITIAL AMOUNT");
+        let op_flow = OperationFlow::from_sign(amount.cents());
+        let description = "INITIAL AMOUNT".to_string();
 
         // 3. Créer l'opération
         self.add_operation(
             OperationKind::System(SystemKind::Init) ,
             op_flow,
-            &date_str,
+            date_str,
             amount.abs(), // Utiliser la valeur absolue
             &description,
         )?;
@@ -184,39 +895,46 @@ impl Codexi {
     /// ex: codexi.adjust_balance(950.0, "2024-07-15");
     pub fn adjust_balance(
         &mut self,
-        physical_balance: f64,
+        physical_balance: impl Into<Amount>,
         date_str: &str,
     ) -> Result<()>
     {
+        let physical_balance = physical_balance.into();
 
-        if physical_balance < 0.0 {
+        if physical_balance.is_negative() {
             log::warn!("Negative physical balance not allow.");
             return Ok(());
         }
 
-        let current_balance = self.balance(None, None, None, None, None)?.total;
+        let current_balance = self.balance(&BalanceFilter::default())?.total;
 
         let difference = physical_balance - current_balance;
 
-        if difference.abs() < 0.001 {
+        if difference.is_zero() {
             log::info!("No adjustment needed. Theoretical balance ({}) matches physical balance ({}).",
                     current_balance, physical_balance);
             return Ok(());
         }
 
-        let adjustment_flow = OperationFlow::from_sign(difference);
+        let adjustment_flow = OperationFlow::from_sign(difference.cents());
         let adjustment_amount = difference.abs();
 
         let description = format!("ADJUSTMENT: Deviation of {} to reach physical balance {}",
                                 adjustment_amount, physical_balance);
 
-        self.add_operation(
-            OperationKind::System(SystemKind::Adjust),
+        let mut op = Operation::new_system_operation(
+            SystemKind::Adjust,
             adjustment_flow,
-            &date_str,
+            date_str,
             adjustment_amount,
-            &description,
+            description,
         )?;
+        op.adjustment = Some(AdjustmentInfo {
+            previous_balance: current_balance,
+            physical_balance,
+            deviation: difference,
+        });
+        self.insert_operation(op)?;
 
         log::warn!("ADJUSTMENT MADE: Added a {} of {} to correct the balance.",
                 adjustment_flow,
@@ -226,84 +944,106 @@ impl Codexi {
         Ok(())
     }
 
+    /// Preview of a period close at a date, nothing is changed (see close_period).
+    /// ex: codexi.close_preview("2025-11-30");
+    /// The carry-forward restarts at each Init/Close anchor and adds the other operations up to the date.
+    /// Returns None when there is nothing to close (only Init/Close anchors up to the date).
+    pub fn close_preview(&self, close_date_str: &str) -> Result<Option<ClosePreview>> {
+        let close_date = NaiveDate::parse_from_str(close_date_str, "%Y-%m-%d")?;
+
+        let has_activity = self.operations.iter().any(|op| op.date <= close_date && !matches!(op.kind,
+            OperationKind::System(SystemKind::Init) |
+            OperationKind::System(SystemKind::Close)));
+        if !has_activity {
+            return Ok(None);
+        }
+
+        let mut carry_forward = Amount::ZERO;
+        let mut archived = 0;
+        for op in self.operations.iter().filter(|op| op.date <= close_date) {
+            archived += 1;
+            match op.kind {
+                OperationKind::System(SystemKind::Init) | OperationKind::System(SystemKind::Close) => {
+                    match op.flow {
+                        OperationFlow::Credit => carry_forward = op.amount,
+                        OperationFlow::Debit => carry_forward = -op.amount,
+                        OperationFlow::None => {},
+                    }
+                }
+                OperationKind::System(SystemKind::Adjust) |
+                OperationKind::Regular(_) => {
+                    match op.flow {
+                        OperationFlow::Credit => carry_forward += op.amount,
+                        OperationFlow::Debit => carry_forward -= op.amount,
+                        OperationFlow::None => {},
+                    }
+                }
+            }
+        }
+
+        Ok(Some(ClosePreview { date: close_date, archived, carry_forward }))
+    }
+
     /// This function closes the current accounting period by archiving all operations
     /// up to the specified closing date and creating a new "Carried Forward Solde" operation.
     /// ex: codexi.close_period("2024-07-31", vec!["End of July".to_string()]);
     /// It saves the archived operations to a file and updates the codexi accordingly.
     /// The description_parts are concatenated to describe the closing operation.
+    /// With `keep_open_copy`, the operations to archive are first exported to this path (csv or toml,
+    /// see export_operations), nothing is closed if the export fails.
+    /// There is nothing to close when no operation other than an anchor (Init, Close) is dated
+    /// on or before the closing date: the codexi is left untouched, no archive nor anchor is created.
+    /// Returns true if the period was closed.
     pub fn close_period(
         &mut self,
         close_date_str: &str,
         description_parts: Vec<String>,
-    ) -> Result<()>
+        keep_open_copy: Option<&Path>,
+    ) -> Result<bool>
     {
-        let close_date = NaiveDate::parse_from_str(close_date_str, "%Y-%m-%d")?;
+        // If there's nothing to close, we stop.
+        let Some(preview) = self.close_preview(close_date_str)? else {
+            log::info!("No transactions (Adjust/Others) found to archive on or before {}.", close_date_str);
+            return Ok(false);
+        };
+        let close_date = preview.date;
+
+        if let Some(copy_path) = keep_open_copy {
+            let to_archive: Vec<Operation> = self.operations.iter()
+                .filter(|op| op.date <= close_date)
+                .cloned()
+                .collect();
+            Self::export_operations(&to_archive, copy_path, JsonStyle::Pretty)?;
+        }
 
-        let mut current_closing_balance: f64 = 0.0;
         let mut archived_operations = Vec::new();
 
         let original_operations = mem::take(&mut self.operations);
+        let totals = self.cached_totals.take();
 
         for op in original_operations.into_iter() {
-            let op_date = op.date;
-
-            if op_date <= close_date {
-
-                match op.kind {
-                    OperationKind::System(SystemKind::Init) | OperationKind::System(SystemKind::Close) => {
-                        archived_operations.push(op.clone());
-
-                        match op.flow {
-                            OperationFlow::Credit => current_closing_balance = op.amount,
-                            OperationFlow::Debit => current_closing_balance = -op.amount,
-                            OperationFlow::None => {},
-                        }
-                    }
-                    OperationKind::System(SystemKind::Adjust) |
-                    OperationKind::Regular(RegularKind::Transaction) |
-                    OperationKind::Regular(RegularKind::Fee) |
-                    OperationKind::Regular(RegularKind::Transfer) |
-                    OperationKind::Regular(RegularKind::Refund) => {
-                        match op.flow {
-                            OperationFlow::Credit => current_closing_balance += op.amount,
-                            OperationFlow::Debit => current_closing_balance -= op.amount,
-                            OperationFlow::None => {},
-                        }
-                        archived_operations.push(op);
-                    }
-                }
+            if op.date <= close_date {
+                archived_operations.push(op);
             } else {
                 self.operations.push(op);
             }
         }
 
-        // If there's nothing to close, we stop.
-        if archived_operations.is_empty() && self.operations.iter().all(|op| !matches!(op.kind,
-            OperationKind::System(SystemKind::Init) |
-            OperationKind::System(SystemKind::Close)))
-        {
-            // Management logic if the codexi is empty or contains only previous anchors.
-            // If there are no transactions to archive, nothing is done.
-            log::info!("No transactions (Adjust/Others) found to archive on or before {}.", close_date_str);
-            return Ok(());
-        }
-
         // --- PART 1: ARCHIVE MANAGEMENT ---
 
-        // Save the archive if there are transactions to archive.
-        if !archived_operations.is_empty() {
-            let archive_path = get_archive_path(close_date_str)?;
-            let encoded_archive = bincode::serialize(&archived_operations)?;
-            fs::write(&archive_path, encoded_archive)?;
-            log::info!("Archived {} operations to {:?}", archived_operations.len(), archive_path);
-        }
+        // Save the archive (never empty, there is at least one transaction to archive).
+        let archive_path = get_archive_path(close_date_str)?;
+        let archive = Codexi { operations: archived_operations, ..Default::default() };
+        let encoded_archive = archive.to_bytes()?;
+        write_atomic(&archive_path, encoded_archive)?;
+        log::info!("Archived {} operations to {:?}", archive.operations.len(), archive_path);
 
         // --- PART 2: CREATION OF THE NEW ANCHOR ---
 
-        let net_solde = current_closing_balance;
+        let net_solde = preview.carry_forward;
 
         // 1. Create the new Carry Forward Balance operation
-        let new_flow = OperationFlow::from_sign(net_solde);
+        let new_flow = OperationFlow::from_sign(net_solde.cents());
         let new_amount = net_solde.abs();
         let description = format!("SOLDE REPORTÉ : {} {}", new_amount, description_parts.join(" "));
 
@@ -317,6 +1057,8 @@ impl Codexi {
 
         // 2. Add the new anchor to the vector.
         // This new anchor replaces all old anchors and transactions up to close_date.
+        self.cached_totals.set(totals);
+        self.update_totals([&new_op], &archive.operations);
         self.operations.push(new_op);
 
         // 3. Sort the final vector (so that the new anchor is in the correct position)
@@ -333,61 +1075,293 @@ impl Codexi {
 
         log::warn!("PERIOD CLOSED: All transactions up to {} archived and replaced by single Close entry.", close_date_str);
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// This function closes several past months in sequence, one archive per month.
+    /// ex: codexi.close_batch("2025-01", "2025-03", vec!["Catch up".to_string()]);
+    /// The months are processed chronologically so that each close carries forward the previous one.
+    /// If a month fails, the archives written by the batch are removed and the codexi is left untouched.
+    /// Returns the list of the closing dates (the months with nothing to close are skipped).
+    pub fn close_batch(
+        &mut self,
+        from_month: &str,
+        to_month: &str,
+        description_parts: Vec<String>,
+    ) -> Result<Vec<String>>
+    {
+        let (mut month_start, _) = month_bounds(from_month)?;
+        let (_, last_month_end) = month_bounds(to_month)?;
+
+        if month_start > last_month_end {
+            return Err(anyhow!("The start month ({}) is after the end month ({}).", from_month, to_month));
+        }
+
+        let mut batch = self.clone();
+        let mut closed_dates = Vec::new();
+        let mut written_archives = Vec::new();
+
+        while month_start <= last_month_end {
+            let month = month_start.format("%Y-%m").to_string();
+            let (_, month_end) = month_bounds(&month)?;
+            let close_date_str = month_end.format("%Y-%m-%d").to_string();
+
+            let archive_path = get_archive_path(&close_date_str)?;
+            let archive_existed = archive_path.exists();
+
+            let mut description = description_parts.clone();
+            description.push(month.clone());
+
+            match batch.close_period(&close_date_str, description, None) {
+                Ok(true) => {
+                    if !archive_existed && archive_path.exists() {
+                        written_archives.push(archive_path);
+                    }
+                    closed_dates.push(close_date_str);
+                }
+                Ok(false) => {},
+                Err(e) => {
+                    log::error!("Batch close aborted on {}: {}", month, e);
+                    for path in &written_archives {
+                        let _ = fs::remove_file(path);
+                    }
+                    return Err(e);
+                }
+            }
+
+            month_start = month_end
+                .succ_opt()
+                .ok_or_else(|| anyhow!("Error computing the next month"))?;
+        }
+
+        *self = batch;
+        log::warn!("BATCH CLOSE: {} months closed from {} to {}.", closed_dates.len(), from_month, to_month);
+
+        Ok(closed_dates)
+    }
+
+    /// This function reopens a closed period: the operations of its archive are merged back into the codexi.
+    /// ex: codexi.reopen_period("codexi_2025-11-30.cld");
+    /// The close date of the archive (from its filename) must match a Close anchor of the codexi,
+    /// so only the latest close can be reopened. The anchor is replaced by the archived operations.
+    /// The archive file is not deleted here, the caller removes it once the codexi is saved.
+    /// Returns the path of the archive file.
+    pub fn reopen_period(&mut self, filename: &str) -> Result<PathBuf> {
+        let close_date = filename
+            .strip_prefix("codexi_")
+            .and_then(|rest| rest.strip_suffix(".cld"))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .ok_or_else(|| anyhow!("Invalid archive filename '{}' (expected codexi_YYYY-MM-DD.cld).", filename))?;
+
+        let anchor_index = self.operations.iter()
+            .position(|op| op.kind == OperationKind::System(SystemKind::Close) && op.date == close_date)
+            .ok_or_else(|| anyhow!("No Close anchor on {} in the codexi, only the latest close can be reopened.", close_date))?;
+
+        let archive = Self::load_archive(filename)?;
+        if let Some(op) = archive.operations.iter().find(|op| op.date > close_date) {
+            return Err(anyhow!("The archive {} holds an operation after its close date ({} {}).", filename, op.date, op.description));
+        }
+
+        self.operations.remove(anchor_index);
+        self.operations.extend(archive.operations);
+        self.reset_totals();
+        // Stable sort: the archived operations keep their order, before the ones of the open period
+        self.operations.sort_by_key(|o| o.date);
+
+        log::warn!("PERIOD REOPENED: the operations up to {} are back in the codexi.", close_date);
+        get_archive_path(&close_date.format("%Y-%m-%d").to_string())
     }
 
     /// Get the operations with balance
-    pub fn get_operations_with_balance(&self) -> Vec<(&Operation, f64)> {
-        let mut cur_bal = 0.0;
+    pub fn get_operations_with_balance(&self) -> Vec<(&Operation, Amount)> {
+        let mut cur_bal = Amount::ZERO;
         let mut out = Vec::new();
 
         for op in &self.operations {
-            cur_bal = calculate_new_balance(cur_bal, op).unwrap_or(0.0);
+            cur_bal = calculate_new_balance(cur_bal, op).unwrap_or_default();
             out.push((op, cur_bal));
         }
 
         out
     }
 
-    /// Calculates the total of credits, debits and the final balance,
-    /// with several date filters (from/to/day/month/year).
-    /// Returns a BalanceResult struct.
-    pub fn balance(
-        &self,
-        from: Option<String>,
-        to: Option<String>,
-        day: Option<String>,
-        month: Option<String>,
-        year: Option<String>,
-    ) -> Result<BalanceResult> {
+    /// Checks that the running balance never goes negative over the whole timeline.
+    /// ex: codexi.check_running_balance()?;
+    /// Unlike the debit check of add_operation (which only looks at the current balance),
+    /// this also catches back-dated operations pushing an intermediate balance below zero.
+    /// It returns an error naming the first offending operation.
+    pub fn check_running_balance(&self) -> Result<()> {
+        for (idx, (op, bal)) in self.get_operations_with_balance().into_iter().enumerate() {
+            if bal.is_negative() {
+                log::error!(
+                    "The running balance becomes negative ({}) at operation #{} ({}).",
+                    bal, idx, op
+                );
+                return Err(anyhow!(
+                    "Negative running balance at operation #{} ({} {}).",
+                    idx, op.date, op.description
+                ));
+            }
+        }
 
-        // Cumulated value
-        let mut credit: f64 = 0.0;
-        let mut debit: f64 = 0.0;
-        let mut total: f64 = 0.0;
+        Ok(())
+    }
 
-        // Parsing from/to
-        let start_date = from
-            .as_deref()
-            .map(|d| parse_flexible_date_range(d, true))
-            .transpose()?;
+    /// Check the invariants of the codexi (ex: the content of an import file) without changing it.
+    /// ex: let problems = codexi.validate();
+    /// The amounts must not be negative, a regular operation must be a debit or a credit,
+    /// there is at most one Init with no operation before it, and no operation on or before
+    /// the latest Close (except the Close itself).
+    /// Returns the list of problems (empty when the codexi is valid).
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (idx, op) in self.operations.iter().enumerate() {
+            if op.amount.is_negative() {
+                problems.push(format!("Operation #{} [{}]: invalid amount {}.", idx, op.to_csv_row(), op.amount));
+            }
+            if op.kind.is_regular() && op.flow.is_none() {
+                problems.push(format!("Operation #{} [{}]: a regular operation must be a debit or a credit.", idx, op.to_csv_row()));
+            }
+        }
+
+        let inits: Vec<(usize, &Operation)> = self.operations.iter()
+            .enumerate()
+            .filter(|(_, op)| op.kind == OperationKind::System(SystemKind::Init))
+            .collect();
+        if inits.len() > 1 {
+            problems.push(format!("{} Init operations found, at most one is expected.", inits.len()));
+        }
+        if let Some((_, init)) = inits.first() {
+            for (idx, op) in self.operations.iter().enumerate().filter(|(_, op)| op.date < init.date) {
+                problems.push(format!("Operation #{} [{}] is before the Init of {}.", idx, op.to_csv_row(), init.date));
+            }
+        }
+
+        let latest_close = self.operations.iter()
+            .enumerate()
+            .filter(|(_, op)| op.kind == OperationKind::System(SystemKind::Close))
+            .max_by_key(|(_, op)| op.date);
+        if let Some((close_idx, close)) = latest_close {
+            for (idx, op) in self.operations.iter().enumerate() {
+                if idx != close_idx && op.date <= close.date {
+                    problems.push(format!("Operation #{} [{}] is in the period closed on {}.", idx, op.to_csv_row(), close.date));
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Check the whole codexi as loaded (ex: after a manual edit of an export), without changing it.
+    /// ex: let problems = codexi.verify();
+    /// On top of the invariants of validate(): the operations are in date order and the running
+    /// balance never becomes negative (each drop below zero is reported).
+    /// Returns the list of problems (empty when the codexi is sound).
+    pub fn verify(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (idx, pair) in self.operations.windows(2).enumerate() {
+            if pair[1].date < pair[0].date {
+                problems.push(format!("Operation #{} [{}] is out of date order, after {}.", idx + 1, pair[1].to_csv_row(), pair[0].date));
+            }
+        }
+
+        problems.extend(self.validate());
+
+        let mut negative = false;
+        for (idx, (op, bal)) in self.get_operations_with_balance().into_iter().enumerate() {
+            if bal.is_negative() && !negative {
+                problems.push(format!("Operation #{} [{}] makes the running balance negative ({}).", idx, op.to_csv_row(), bal));
+            }
+            negative = bal.is_negative();
+        }
+
+        problems
+    }
+
+    /// Calculates the total of credits, debits and the final balance,
+    /// with several date filters (from/to/day/month/year) and an amount range (amount_min/amount_max,
+    /// ex: to leave a large outlier out of the totals).
+    /// With `exclude_system`, the system operations (Init, Adjust, Close) are left out of the
+    /// credit/debit totals, the balance stays the true net and the activity net is also returned.
+    /// The same goes for the operations of `exclude_kinds` (ex: the transfers between own accounts).
+    /// Without any filter, the totals kept by the codexi are returned without a scan of the operations.
+    /// Returns a BalanceResult struct.
+    pub fn balance(&self, filter: &BalanceFilter) -> Result<BalanceResult> {
+        let exclude_system = filter.exclude_system;
+        let exclude_kinds = &filter.exclude_kinds;
+
+        // Without filter, the totals kept by the codexi (no scan)
+        if filter.from.is_none() && filter.to.is_none() && filter.day.is_none() && filter.month.is_none() && filter.year.is_none()
+            && filter.amount_min.is_none() && filter.amount_max.is_none() && !exclude_system && exclude_kinds.is_empty()
+        {
+            let totals = self.totals();
+            return Ok(BalanceResult { credit: totals.credit, debit: totals.debit, total: totals.total(), activity: None, count: totals.count, average: None });
+        }
+
+        // Cumulated value
+        let mut credit = Amount::ZERO;
+        let mut debit = Amount::ZERO;
+        let mut total = Amount::ZERO;
+        let mut count: usize = 0;
+
+        for (_, op) in self.balance_operations(filter)? {
+
+            // --- Cumulate the true net
+            total += op.amount * op.flow.to_sign();
+            count += 1;
 
-        let end_date = to
-            .as_deref()
-            .map(|d| parse_flexible_date_range(d, false))
-            .transpose()?;
+            // --- Cumulate CREDIT / DEBIT
+            if (exclude_system && op.kind.is_system()) || exclude_kinds.contains(&op.kind) {
+                continue;
+            }
+            match op.flow {
+                OperationFlow::Credit => credit += op.amount,
+                OperationFlow::Debit  => debit  += op.amount,
+                OperationFlow::None   => {},
+            }
+        }
+
+        let activity = (exclude_system || !exclude_kinds.is_empty()).then(|| credit - debit);
+
+        Ok(BalanceResult{ credit, debit, total, activity, count, average: None })
+    }
+
+    /// Running balance of the operations matching the balance filters, in date order:
+    /// each item carries the cumulative net of the filtered operations up to it (a bank-statement view).
+    /// ex: codexi.running_balance(&BalanceFilter { month: Some("2025-11".into()), ..Default::default() });
+    /// The last balance is the total of balance() for the same filters.
+    pub fn running_balance(&self, filter: &BalanceFilter) -> Result<Vec<SearchItem<'_>>> {
+        let mut balance = Amount::ZERO;
+        let items = self.balance_operations(filter)?
+            .into_iter()
+            .map(|(index, op)| {
+                balance += op.amount * op.flow.to_sign();
+                SearchItem { index: index as i32, op, balance }
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// Operations matching the filters of balance() (from/to/day/month/year and the amount range), with their index.
+    /// An invalid day or year matches nothing, an invalid month filters nothing.
+    fn balance_operations(&self, filter: &BalanceFilter) -> Result<Vec<(usize, &Operation)>> {
+
+        // Parsing from/to
+        let (start_date, end_date) = date_range_bounds(filter.from.as_deref(), filter.to.as_deref())?;
 
         // Expected format : "YYYY-MM-DD"
-        let filter_day: Option<NaiveDate> = match day.as_deref() {
+        let filter_day: Option<NaiveDate> = match filter.day.as_deref() {
             Some(dstr) => match NaiveDate::parse_from_str(dstr, "%Y-%m-%d") {
                 Ok(d) => Some(d),
-                Err(_) => return Ok(BalanceResult{credit: 0.0, debit: 0.9, total: 0.0}), // jour invalide = aucun match
+                Err(_) => return Ok(Vec::new()), // jour invalide = aucun match
             },
             None => None,
         };
 
         // Expected format : "YYYY-MM"
-        let filter_month: Option<(i32, u32)> = if let Some(m) = month.as_deref() {
+        let filter_month: Option<(i32, u32)> = if let Some(m) = filter.month.as_deref() {
             let parts: Vec<&str> = m.split('-').collect();
             if parts.len() == 2 {
                 if let (Ok(y), Ok(mo)) = (parts[0].parse::<i32>(), parts[1].parse::<u32>()) {
@@ -403,99 +1377,517 @@ impl Codexi {
         };
 
         // Expected format : "YYYY"
-        let filter_year: Option<i32> = match year.as_deref() {
+        let filter_year: Option<i32> = match filter.year.as_deref() {
             Some(ystr) => match ystr.parse::<i32>() {
                 Ok(v) => Some(v),
-                Err(_) => return Ok(BalanceResult{credit: 0.0, debit: 0.9, total: 0.0}), // année invalide = aucun match
+                Err(_) => return Ok(Vec::new()), // année invalide = aucun match
             },
             None => None,
         };
 
-        for op in self.operations.iter() {
+        let mut matched = Vec::new();
+        for (index, op) in self.operations.iter().enumerate() {
 
             // --- Filter FROM
-            if let Some(s_date) = start_date {
-                if op.date < s_date {
-                    continue;
-                }
+            if let Some(s_date) = start_date
+                && op.date < s_date
+            {
+                continue;
             }
 
             // --- Filter TO
-            if let Some(e_date) = end_date {
-                if op.date > e_date {
-                    continue;
-                }
+            if let Some(e_date) = end_date
+                && op.date > e_date
+            {
+                continue;
             }
 
             // --- Filter EXACT DAY
-            if let Some(d) = filter_day {
-                if op.date != d {
-                    continue;
-                }
+            if let Some(d) = filter_day
+                && op.date != d
+            {
+                continue;
             }
 
             // --- Filter MONTH
-            if let Some((y, m)) = filter_month {
-                if op.date.year() != y || op.date.month() != m {
-                    continue;
-                }
+            if let Some((y, m)) = filter_month
+                && (op.date.year() != y || op.date.month() != m)
+            {
+                continue;
             }
 
             // --- Filter YEAR
-            if let Some(y) = filter_year {
-                if op.date.year() != y {
+            if let Some(y) = filter_year
+                && op.date.year() != y
+            {
+                continue;
+            }
+
+            // --- Filter AMOUNT
+            if filter.amount_min.is_some_and(|min| op.amount < min) || filter.amount_max.is_some_and(|max| op.amount > max) {
+                continue;
+            }
+
+            matched.push((index, op));
+        }
+        Ok(matched)
+    }
+
+    /// Average spend per day and per month of a debit total, over the calendar span of the
+    /// balance filters (from/to/day/month/year), so the days without operation count too.
+    /// ex: codexi.average_spend(balance.debit, Some("2025-01"), Some("2025-03"), None, None, None);
+    /// An open bound is the date of the first (or last) operation.
+    /// Returns None when the span is empty (no operation, or bounds that do not overlap).
+    pub fn average_spend(
+        &self,
+        debit: Amount,
+        from: Option<&str>,
+        to: Option<&str>,
+        day: Option<&str>,
+        month: Option<&str>,
+        year: Option<&str>,
+    ) -> Result<Option<AverageSpend>> {
+        let (mut start, mut end) = date_range_bounds(from, to)?;
+        let mut restrict = |lower: NaiveDate, upper: NaiveDate| {
+            start = Some(start.map_or(lower, |s| s.max(lower)));
+            end = Some(end.map_or(upper, |e| e.min(upper)));
+        };
+
+        if let Some(d) = day {
+            let d = NaiveDate::parse_from_str(d, "%Y-%m-%d")?;
+            restrict(d, d);
+        }
+        if let Some(m) = month {
+            let (first, last) = month_bounds(m)?;
+            restrict(first, last);
+        }
+        if let Some(y) = year {
+            let y: i32 = y.parse().map_err(|_| anyhow!("Invalid year '{}'", y))?;
+            let first = NaiveDate::from_ymd_opt(y, 1, 1).ok_or_else(|| anyhow!("Invalid year {}", y))?;
+            let last = NaiveDate::from_ymd_opt(y, 12, 31).ok_or_else(|| anyhow!("Invalid year {}", y))?;
+            restrict(first, last);
+        }
+
+        let (Some(start), Some(end)) = (
+            start.or_else(|| self.operations.iter().map(|op| op.date).min()),
+            end.or_else(|| self.operations.iter().map(|op| op.date).max()),
+        ) else {
+            return Ok(None);
+        };
+        if end < start {
+            return Ok(None);
+        }
+
+        // Each calendar month of the span counts for the share of its days covered
+        let mut months = 0.0;
+        let mut month_start = start;
+        while month_start <= end {
+            let (first, last) = month_bounds(&month_start.format("%Y-%m").to_string())?;
+            let covered = (last.min(end) - month_start).num_days() + 1;
+            months += covered as f64 / ((last - first).num_days() + 1) as f64;
+            month_start = last.succ_opt().ok_or_else(|| anyhow!("Error computing the next month"))?;
+        }
+
+        let days = (end - start).num_days() + 1;
+        Ok(Some(AverageSpend {
+            start,
+            end,
+            days,
+            months,
+            per_day: Amount::from_f64(debit.to_f64() / days as f64),
+            per_month: Amount::from_f64(debit.to_f64() / months),
+        }))
+    }
+
+    /// Compares the balance of a month with the one of the previous month.
+    /// ex: codexi.balance_compare("2025-11");
+    /// Returns a BalanceCompareResult struct.
+    pub fn balance_compare(&self, month: &str) -> Result<BalanceCompareResult> {
+        let previous_period = previous_month(month)?;
+
+        let current = self.balance(&BalanceFilter { month: Some(month.to_string()), ..Default::default() })?;
+        let previous = self.balance(&BalanceFilter { month: Some(previous_period.clone()), ..Default::default() })?;
+
+        Ok(BalanceCompareResult {
+            period: month.to_string(),
+            current,
+            previous_period,
+            previous,
+        })
+    }
+
+    /// Calculates the balance of each month, from the `from` month to the `to` month (YYYY-MM).
+    /// ex: codexi.monthly_balances(Some("2025-01".to_string()), None);
+    /// Without bounds, the months of the first and of the last operation are used.
+    /// Returns a vector of MonthlyBalance, one per month (empty months included).
+    pub fn monthly_balances(&self, from: Option<String>, to: Option<String>) -> Result<Vec<MonthlyBalance>> {
+        let (Some(first), Some(last)) = (
+            self.operations.iter().map(|op| op.date).min(),
+            self.operations.iter().map(|op| op.date).max(),
+        ) else {
+            return Ok(Vec::new());
+        };
+
+        let from_month = from.unwrap_or_else(|| first.format("%Y-%m").to_string());
+        let to_month = to.unwrap_or_else(|| last.format("%Y-%m").to_string());
+        let (mut month_start, _) = month_bounds(&from_month)?;
+        let (_, last_month_end) = month_bounds(&to_month)?;
+
+        let mut months = Vec::new();
+        while month_start <= last_month_end {
+            let month = month_start.format("%Y-%m").to_string();
+            let (_, month_end) = month_bounds(&month)?;
+            let balance = self.balance(&BalanceFilter { month: Some(month.clone()), ..Default::default() })?;
+            months.push(MonthlyBalance { month, balance });
+
+            month_start = month_end
+                .succ_opt()
+                .ok_or_else(|| anyhow!("Error computing the next month"))?;
+        }
+
+        Ok(months)
+    }
+
+    /// Average net of the regular operations per month, from the month of the first operation to the
+    /// month of the last one (empty months included).
+    /// ex: codexi.monthly_average_net();
+    /// Returns None when there is no regular operation.
+    pub fn monthly_average_net(&self) -> Result<Option<Amount>> {
+        if !self.operations.iter().any(|op| op.kind.is_regular()) {
+            return Ok(None);
+        }
+
+        let months = self.monthly_balances(None, None)?;
+        let mut total = Amount::ZERO;
+        for m in &months {
+            total += self.balance(&BalanceFilter { month: Some(m.month.clone()), exclude_system: true, ..Default::default() })?.activity.unwrap_or_default();
+        }
+
+        Ok(Some(Amount::from_f64(total.to_f64() / months.len() as f64)))
+    }
+
+    /// Progress of the balance toward a savings goal
+    /// ex: codexi.savings_goal(Amount::from_cents(500000));
+    /// The time to goal is estimated from the monthly average net (see monthly_average_net).
+    pub fn savings_goal(&self, target: Amount) -> Result<GoalResult> {
+        if !target.is_positive() {
+            return Err(anyhow!("The target must be a positive amount."));
+        }
+
+        let balance = self.balance(&BalanceFilter::default())?.total;
+        let remaining = (target - balance).max(Amount::ZERO);
+        let monthly_average = self.monthly_average_net()?;
+        let months_to_goal = match monthly_average {
+            _ if remaining.is_zero() => Some(0),
+            Some(average) if average.is_positive() => Some((remaining.cents() as u64).div_ceil(average.cents() as u64) as u32),
+            _ => None,
+        };
+
+        Ok(GoalResult {
+            balance,
+            target,
+            remaining,
+            percent: round_to_2_dec(balance.to_f64() / target.to_f64() * 100.0),
+            monthly_average,
+            months_to_goal,
+        })
+    }
+
+    /// History of the period closes, oldest first
+    /// ex: codexi.close_history();
+    /// Each archive (codexi_YYYY-MM-DD.cld) gives a close date, its number of operations and
+    /// its carry-forward balance. A live Close anchor without archive is listed too.
+    pub fn close_history(&self) -> Result<Vec<CloseEntry>> {
+        let mut entries: Vec<CloseEntry> = Vec::new();
+
+        for file_name in Self::list_archives()? {
+            let Some(date) = file_name
+                .strip_prefix("codexi_")
+                .and_then(|rest| rest.strip_suffix(".cld"))
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+
+            let archive = Self::load_archive(&file_name)?;
+            let mut carry_forward = Amount::ZERO;
+            for op in &archive.operations {
+                if matches!(op.kind, OperationKind::System(SystemKind::Init) | OperationKind::System(SystemKind::Close)) {
+                    carry_forward = Amount::ZERO;
+                }
+                carry_forward = calculate_new_balance(carry_forward, op)?;
+            }
+
+            entries.push(CloseEntry {
+                date,
+                archived: Some(archive.operations.len()),
+                carry_forward,
+                delta: None,
+            });
+        }
+
+        for op in self.operations.iter().filter(|op| op.kind == OperationKind::System(SystemKind::Close)) {
+            let carry_forward = calculate_new_balance(Amount::ZERO, op)?;
+            match entries.iter_mut().find(|e| e.date == op.date) {
+                Some(entry) => entry.carry_forward = carry_forward,
+                None => entries.push(CloseEntry { date: op.date, archived: None, carry_forward, delta: None }),
+            }
+        }
+
+        entries.sort_by_key(|e| e.date);
+        for i in 1..entries.len() {
+            entries[i].delta = Some(entries[i].carry_forward - entries[i - 1].carry_forward);
+        }
+
+        Ok(entries)
+    }
+
+    /// Statistics of the operations per day, between the from and to dates
+    /// ex: codexi.activity(Some("2025-11".to_string()), None);
+    /// Only the regular operations count, the average and median are taken over the active days.
+    /// A range without operation gives a count of 0 and no busiest/highest-spend day.
+    pub fn activity(&self, from: Option<String>, to: Option<String>) -> Result<ActivityResult> {
+        let (start_date, end_date) = date_range_bounds(from.as_deref(), to.as_deref())?;
+
+        // date -> (number of operations, total debit)
+        let mut days: BTreeMap<NaiveDate, (usize, Amount)> = BTreeMap::new();
+        for op in self.operations.iter().filter(|op| !op.kind.is_system()) {
+            if start_date.is_some_and(|d| op.date < d) || end_date.is_some_and(|d| op.date > d) {
+                continue;
+            }
+            let day = days.entry(op.date).or_insert((0, Amount::ZERO));
+            day.0 += 1;
+            if op.flow.is_debit() {
+                day.1 += op.amount;
+            }
+        }
+
+        if days.is_empty() {
+            return Ok(ActivityResult::default());
+        }
+
+        // On a tie, the earliest day is kept
+        let mut busiest_day: Option<(NaiveDate, usize)> = None;
+        let mut highest_spend_day: Option<(NaiveDate, Amount)> = None;
+        for (date, (count, spend)) in &days {
+            if busiest_day.is_none_or(|(_, best)| *count > best) {
+                busiest_day = Some((*date, *count));
+            }
+            if spend.is_positive() && highest_spend_day.is_none_or(|(_, best)| *spend > best) {
+                highest_spend_day = Some((*date, *spend));
+            }
+        }
+
+        let mut counts: Vec<usize> = days.values().map(|(count, _)| *count).collect();
+        counts.sort_unstable();
+        let count: usize = counts.iter().sum();
+        let middle = counts.len() / 2;
+        let median_per_day = if counts.len().is_multiple_of(2) {
+            (counts[middle - 1] + counts[middle]) as f64 / 2.0
+        } else {
+            counts[middle] as f64
+        };
+
+        Ok(ActivityResult {
+            count,
+            active_days: days.len(),
+            busiest_day,
+            highest_spend_day,
+            average_per_day: round_to_2_dec(count as f64 / days.len() as f64),
+            median_per_day,
+        })
+    }
+
+    /// Discretionary spend between the from and to dates
+    /// ex: codexi.discretionary(Some("2025-11".to_string()), None);
+    /// Policy (fixed): the debits of the transactions and of the custom categories are spend,
+    /// the credits of the refunds are subtracted. The fees, the transfers, the other credits
+    /// and the system operations are ignored.
+    pub fn discretionary(&self, from: Option<String>, to: Option<String>) -> Result<DiscretionaryResult> {
+        let (start_date, end_date) = date_range_bounds(from.as_deref(), to.as_deref())?;
+
+        let mut result = DiscretionaryResult::default();
+        for op in self.operations.iter().filter(|op| !op.kind.is_system()) {
+            if start_date.is_some_and(|d| op.date < d) || end_date.is_some_and(|d| op.date > d) {
+                continue;
+            }
+            match (&op.kind, op.flow) {
+                (OperationKind::Regular(RegularKind::Transaction | RegularKind::Custom(_)), OperationFlow::Debit) => result.spend += op.amount,
+                (OperationKind::Regular(RegularKind::Refund), OperationFlow::Credit) => result.refunds += op.amount,
+                _ => {
+                    result.ignored += 1;
                     continue;
                 }
             }
+            result.count += 1;
+        }
 
-            // --- Cumulate CREDIT / DEBIT
+        result.total = result.spend - result.refunds;
+        Ok(result)
+    }
+
+    /// Credit and debit per category between the from and to dates (see Operation::category)
+    /// ex: codexi.balance_by_category(Some("2025-11".to_string()), None);
+    /// The categories are grouped case-insensitively (the first spelling met is kept) and sorted by name,
+    /// the operations without category are grouped last under uncategorized. The system operations are ignored.
+    pub fn balance_by_category(&self, from: Option<String>, to: Option<String>) -> Result<Vec<CategoryTotal>> {
+        let (start_date, end_date) = date_range_bounds(from.as_deref(), to.as_deref())?;
+
+        let mut groups: BTreeMap<String, CategoryTotal> = BTreeMap::new();
+        let mut uncategorized = CategoryTotal { category: UNCATEGORIZED.to_string(), ..Default::default() };
+        for op in self.operations.iter().filter(|op| !op.kind.is_system()) {
+            if start_date.is_some_and(|d| op.date < d) || end_date.is_some_and(|d| op.date > d) {
+                continue;
+            }
+            let entry = match op.category.as_deref() {
+                Some(category) => groups.entry(category.to_lowercase()).or_insert_with(|| CategoryTotal {
+                    category: category.to_string(),
+                    ..Default::default()
+                }),
+                None => &mut uncategorized,
+            };
             match op.flow {
-                OperationFlow::Credit => credit += op.amount,
-                OperationFlow::Debit  => debit  += op.amount,
-                OperationFlow::None   => {},
+                OperationFlow::Credit => entry.credit += op.amount,
+                OperationFlow::Debit => entry.debit += op.amount,
+                OperationFlow::None => {},
+            }
+            entry.count += 1;
+        }
+
+        let mut categories: Vec<CategoryTotal> = groups.into_values().collect();
+        if uncategorized.count > 0 {
+            categories.push(uncategorized);
+        }
+        Ok(categories)
+    }
+
+    /// Distribution of the amounts between the from and to dates: count, mean, median, min and max
+    /// of the credits and of the debits, and the largest operation.
+    /// ex: codexi.stats(Some("2025".to_string()), None);
+    /// The system operations (init, adjust, close) are ignored.
+    pub fn stats(&self, from: Option<String>, to: Option<String>) -> Result<StatsResult> {
+        let operations: Vec<&Operation> = self.balance_operations(&BalanceFilter { from, to, ..Default::default() })?
+            .into_iter()
+            .map(|(_, op)| op)
+            .filter(|op| !op.kind.is_system())
+            .collect();
+
+        let flow_stats = |flow: OperationFlow| -> Option<AmountStats> {
+            let mut amounts: Vec<Amount> = operations.iter().filter(|op| op.flow == flow).map(|op| op.amount).collect();
+            amounts.sort();
+            let count = amounts.len();
+            let middle = count / 2;
+            let median = match count {
+                0 => return None,
+                _ if count % 2 == 1 => amounts[middle],
+                _ => Amount::from_f64((amounts[middle - 1].to_f64() + amounts[middle].to_f64()) / 2.0),
+            };
+            Some(AmountStats {
+                count,
+                mean: Amount::from_f64(amounts.iter().sum::<Amount>().to_f64() / count as f64),
+                median,
+                min: amounts[0],
+                max: amounts[count - 1],
+            })
+        };
+
+        Ok(StatsResult {
+            credit: flow_stats(OperationFlow::Credit),
+            debit: flow_stats(OperationFlow::Debit),
+            largest: operations.iter().rev().max_by_key(|op| op.amount).map(|op| (*op).clone()),
+        })
+    }
+
+    /// Spend per payee over a period: the debits of the regular operations, grouped by payee (see Operation::payee).
+    /// ex: codexi.balance_by_payee(&BalanceFilter { month: Some("2025-11".into()), ..Default::default() }, Some(10), None);
+    /// The period is a day, a month or a year of the filter when given, else the from/to range;
+    /// the amount range and the exclusions of the filter are not used.
+    /// The payees are sorted by total descending, the ones beyond `top` or with a total below `threshold`
+    /// are merged into the Other bucket.
+    pub fn balance_by_payee(&self, filter: &BalanceFilter, top: Option<usize>, threshold: Option<Amount>) -> Result<PayeeBreakdown> {
+        let (start_date, end_date) = match filter.day.as_deref().or(filter.month.as_deref()).or(filter.year.as_deref()) {
+            Some(period) => date_range_bounds(Some(period), Some(period))?,
+            None => date_range_bounds(filter.from.as_deref(), filter.to.as_deref())?,
+        };
+
+        // Grouped case-insensitively, the first spelling met is kept
+        let mut groups: BTreeMap<String, PayeeTotal> = BTreeMap::new();
+        for op in self.operations.iter().filter(|op| !op.kind.is_system() && op.flow.is_debit()) {
+            if start_date.is_some_and(|d| op.date < d) || end_date.is_some_and(|d| op.date > d) {
+                continue;
             }
+            let entry = groups.entry(op.payee().to_lowercase()).or_insert_with(|| PayeeTotal {
+                payee: op.payee().to_string(),
+                ..Default::default()
+            });
+            entry.count += 1;
+            entry.total += op.amount;
+        }
+
+        let mut payees: Vec<PayeeTotal> = groups.into_values().collect();
+        payees.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.payee.cmp(&b.payee)));
 
-            total = credit - debit;
+        let mut result = PayeeBreakdown {
+            total: payees.iter().map(|p| p.total).sum(),
+            other: PayeeTotal { payee: "Other".to_string(), ..Default::default() },
+            ..Default::default()
+        };
+        for (rank, payee) in payees.into_iter().enumerate() {
+            if top.is_some_and(|n| rank >= n) || threshold.is_some_and(|t| payee.total < t) {
+                result.other.count += payee.count;
+                result.other.total += payee.total;
+            } else {
+                result.payees.push(payee);
+            }
         }
 
-        credit = round_to_2_dec(credit);
-        debit = round_to_2_dec(debit);
-        total = round_to_2_dec(total);
+        Ok(result)
+    }
 
-        Ok(BalanceResult{ credit, debit, total })
+    /// Gaps in the records: every interval between two consecutive operation dates longer than `max_gap` days.
+    /// ex: codexi.gaps(30);
+    /// Only the regular operations are considered, an anchor (init, adjust, close) is not a record of activity.
+    pub fn gaps(&self, max_gap: i64) -> Vec<GapEntry> {
+        let mut dates: Vec<NaiveDate> = self.operations.iter()
+            .filter(|op| !op.kind.is_system())
+            .map(|op| op.date)
+            .collect();
+        dates.sort();
+        dates.dedup();
+
+        dates.windows(2)
+            .map(|w| GapEntry { start: w[0], end: w[1], days: (w[1] - w[0]).num_days() })
+            .filter(|gap| gap.days > max_gap)
+            .collect()
     }
 
     /// Search
+    /// Keeps the operations matching every filter (see SearchFilter), `page` cuts the results.
     /// Returns a vector of SearchItem
-    pub fn search(
-        &self,
-        from: Option<String>,
-        to: Option<String>,
-        text: Option<String>,
-        kind: Option<String>,
-        flow: Option<String>,
-        day: Option<String>,
-        amount_min: Option<f64>,
-        amount_max: Option<f64>,
-        latest: Option<usize>,
-    ) -> Result<Vec<SearchItem<'_>>> {
+    pub fn search(&self, filter: &SearchFilter, page: Option<&mut SearchPage>) -> Result<Vec<SearchItem<'_>>> {
+        let SearchFilter { from, to, text, kind, flow, day, .. } = filter;
+        let &SearchFilter { regex, text_in, system, weekday, amount_min, amount_max, flagged, sort, latest, .. } = filter;
 
         let ops_map = self.get_operations_with_balance();
 
-        let start_date = from
-            .as_deref()
-            .map(|d| parse_flexible_date_range(d, true))
-            .transpose()?;
-
-        let end_date = to
-            .as_deref()
-            .map(|d| parse_flexible_date_range(d, false))
-            .transpose()?;
-
-        let text_lc = text.as_ref().map(|t| t.to_lowercase());
+        let (start_date, end_date) = date_range_bounds(from.as_deref(), to.as_deref())?;
+
+        // Any of the terms matches, the empty ones are ignored
+        let terms: Vec<&str> = text.iter().map(String::as_str).filter(|t| !t.is_empty()).collect();
+        let text_lc: Vec<String> = if regex { Vec::new() } else { terms.iter().map(|t| t.to_lowercase()).collect() };
+        let text_re = if regex {
+            terms.iter()
+                .map(|pattern| RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| anyhow!("Invalid regex '{}': {}", pattern, e)))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
 
         let o_flow_filter = match flow {
-            Some(ref s) => match OperationFlow::try_from(s.as_str()) {
+            Some(s) => match OperationFlow::try_from(s.as_str()) {
                 Ok(v) => Some(v),
                 Err(_) => return Ok(Vec::new()),
             },
@@ -503,7 +1895,7 @@ impl Codexi {
         };
 
         let o_kind_filter = match kind {
-            Some(ref s) => match OperationKind::try_from(s.as_str()) {
+            Some(s) => match OperationKind::try_from(s.as_str()) {
                 Ok(v) => Some(v),
                 Err(_) => return Ok(Vec::new()),
             },
@@ -522,53 +1914,84 @@ impl Codexi {
 
         for (idx, &(op, bal)) in ops_map.iter().enumerate() {
             // from
-            if let Some(s_date) = start_date {
-                if op.date < s_date {
-                    continue;
-                }
+            if let Some(s_date) = start_date
+                && op.date < s_date
+            {
+                continue;
             }
 
             // to
-            if let Some(e_date) = end_date {
-                if op.date > e_date {
-                    continue;
-                }
+            if let Some(e_date) = end_date
+                && op.date > e_date
+            {
+                continue;
             }
 
-            if let Some(ref needle) = text_lc {
-                if !op.description.to_lowercase().contains(needle) {
+            if !text_lc.is_empty() || !text_re.is_empty() {
+                // Any term in any of the selected fields
+                let mut fields: Vec<&str> = Vec::with_capacity(2);
+                if text_in != SearchField::Category {
+                    fields.push(&op.description);
+                }
+                if text_in != SearchField::Desc
+                    && let Some(category) = op.category.as_deref()
+                {
+                    fields.push(category);
+                }
+                let found = fields.iter().any(|field| {
+                    let field_lc = field.to_lowercase();
+                    text_lc.iter().any(|needle| field_lc.contains(needle)) || text_re.iter().any(|re| re.is_match(field))
+                });
+                if !found {
                     continue;
                 }
             }
 
-            if let Some(f_op) = o_flow_filter {
-                if op.flow != f_op {
-                    continue;
-                }
+            if let Some(f_op) = o_flow_filter
+                && op.flow != f_op
+            {
+                continue;
             }
 
-            if let Some(k_op) = o_kind_filter {
-                if op.kind != k_op {
-                    continue;
-                }
+            if let Some(ref k_op) = o_kind_filter
+                && !op.kind.matches(k_op)
+            {
+                continue;
             }
 
-            if let Some(d) = day_parsed {
-                if op.date != d {
-                    continue;
-                }
+            // system only (true) / regular only (false)
+            if let Some(is_system) = system
+                && op.kind.is_system() != is_system
+            {
+                continue;
             }
 
-            if let Some(min) = amount_min {
-                if op.amount < min {
-                    continue;
-                }
+            if let Some(d) = day_parsed
+                && op.date != d
+            {
+                continue;
             }
 
-            if let Some(max) = amount_max {
-                if op.amount > max {
-                    continue;
-                }
+            if let Some(wd) = weekday
+                && op.date.weekday() != wd
+            {
+                continue;
+            }
+
+            if let Some(min) = amount_min
+                && op.amount < min
+            {
+                continue;
+            }
+
+            if let Some(max) = amount_max
+                && op.amount > max
+            {
+                continue;
+            }
+
+            if flagged && !op.flagged {
+                continue;
             }
 
             matched.push(SearchItem {
@@ -578,17 +2001,31 @@ impl Codexi {
             });
         }
 
-        let result = if let Some(n) = latest {
+        // Stable sorts: the equal amounts stay in date order
+        match sort {
+            SearchSort::Date => {},
+            SearchSort::Amount => matched.sort_by_key(|item| item.op.amount),
+            SearchSort::DescAmount => matched.sort_by_key(|item| Reverse(item.op.amount)),
+        }
+
+        let mut result = if let Some(n) = latest {
             if matched.len() <= n {
                 matched
-            } else {
+            } else if sort == SearchSort::Date {
                 let start = matched.len().saturating_sub(n);
                 matched[start..].to_vec()
+            } else {
+                matched[..n].to_vec()
             }
         } else {
             matched
         };
 
+        if let Some(page) = page {
+            page.total = result.len();
+            result = result.drain(page.range()).collect();
+        }
+
         Ok(result)
     }
     /// Resume
@@ -598,33 +2035,38 @@ impl Codexi {
         let mut nb_init: usize = 0;
         let mut nb_adjust: usize = 0;
         let mut nb_close: usize = 0;
-        let mut latest_transaction_date = String::from("__________");
-        let mut latest_init_date = String::from("__________");
-        let mut latest_adjust_date = String::from("__________");
-        let mut latest_close_date = String::from("__________");
+        let mut latest_transaction_date = None;
+        let mut latest_init_date = None;
+        let mut latest_adjust_date = None;
+        let mut latest_close_date = None;
+        let mut adjustment_total = Amount::ZERO;
 
         for op in &self.operations {
             match op.kind {
                 OperationKind::Regular(RegularKind::Transaction) => {
                     nb_transaction += 1;
-                    latest_transaction_date = op.date.format("%Y-%m-%d").to_string();
+                    latest_transaction_date = Some(op.date);
                 }
                 OperationKind::System(SystemKind::Init) => {
                     nb_init += 1;
-                    latest_init_date = op.date.format("%Y-%m-%d").to_string();
+                    latest_init_date = Some(op.date);
                 }
                 OperationKind::System(SystemKind::Adjust) => {
                     nb_adjust += 1;
-                    latest_adjust_date = op.date.format("%Y-%m-%d").to_string();
+                    latest_adjust_date = Some(op.date);
+                    adjustment_total += match op.adjustment {
+                        Some(info) => info.deviation,
+                        None => op.amount * op.flow.to_sign(),
+                    };
                 }
                 OperationKind::System(SystemKind::Close) => {
                     nb_close += 1;
-                    latest_close_date = op.date.format("%Y-%m-%d").to_string();
+                    latest_close_date = Some(op.date);
                 }
                 _ => { /* Ignore other types of operations */ }
             }
         }
-        let current_balance = self.balance(None, None, None, None, None)?.total;
+        let current_balance = self.balance(&BalanceFilter::default())?.total;
         let nb_op = nb_transaction + nb_init + nb_adjust + nb_close;
 
         Ok(ResumeResult {
@@ -634,6 +2076,7 @@ impl Codexi {
             current_nb_close: nb_close,
             current_nb_op: nb_op,
             current_balance,
+            adjustment_total,
             latest_transaction_date,
             latest_init_date,
             latest_adjust_date,
@@ -647,6 +2090,7 @@ impl Codexi {
 mod tests {
 
     use super::*;
+    use crate::core::helpers::set_test_data_dir;
 
     fn setup_empty_codexi() -> Codexi {
         // init
@@ -663,7 +2107,7 @@ mod tests {
             OperationFlow::Credit,
             "2025-11-05".to_string().as_str(),
             100.0,
-            format!("Atm").as_str(),
+            "Atm",
         ).unwrap();
 
         // #1 Credit (2025-10-08) : 50.00
@@ -672,7 +2116,7 @@ mod tests {
             OperationFlow::Credit,
             "2025-10-08".to_string().as_str(),
             50.0,
-            format!("Atm").as_str(),
+            "Atm",
         ).unwrap();
 
         // #7 Debit (2025-12-05) : 25.50
@@ -681,7 +2125,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-12-05".to_string().as_str(),
             25.50,
-            format!("Minimarket").as_str(),
+            "Minimarket",
         ).unwrap();
 
         // #0 Debit (2025-10-04) : 14.20
@@ -690,7 +2134,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-10-04".to_string().as_str(),
             14.20,
-            format!("Book").as_str(),
+            "Book",
         ).unwrap();
 
         // #2 Debit (2025-10-21) : 44.80
@@ -699,7 +2143,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-10-21".to_string().as_str(),
             44.80,
-            format!("Post office").as_str(),
+            "Post office",
         ).unwrap();
 
         // #9 Credit (2025-12-15) : 150.00
@@ -708,7 +2152,7 @@ mod tests {
             OperationFlow::Credit,
             "2025-12-15".to_string().as_str(),
             150.0,
-            format!("Atm").as_str(),
+            "Atm",
         ).unwrap();
 
         // #5 Debit (2025-11-12) : 15.70
@@ -717,7 +2161,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-11-12".to_string().as_str(),
             15.70,
-            format!("Bakery").as_str(),
+            "Bakery",
         ).unwrap();
 
         // #3 Debit (2025-10-21) : 11.00
@@ -726,7 +2170,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-10-21".to_string().as_str(),
             11.00,
-            format!("Fruits").as_str(),
+            "Fruits",
         ).unwrap();
 
         // #8 Credit (2025-12-10) : 10.00
@@ -735,7 +2179,7 @@ mod tests {
             OperationFlow::Credit,
             "2025-12-10".to_string().as_str(),
             10.0,
-            format!("Refund").as_str(),
+            "Refund",
         ).unwrap();
 
         // #6 Debit (2025-11-20) : 23.60
@@ -744,7 +2188,7 @@ mod tests {
             OperationFlow::Debit,
             "2025-11-20".to_string().as_str(),
             23.60,
-            format!("Newspapers").as_str(),
+            "Newspapers",
         ).unwrap();
 
         cb
@@ -756,8 +2200,8 @@ mod tests {
 
         assert_eq!(codexi.operations.len(), 0, "The default codexi should have 0 operations.");
 
-        let balance_result = codexi.balance(None, None, None, None, None)?;
-        assert_eq!(balance_result.total, 0.0, "The balance of an empty codexi must be 0.0.");
+        let balance_result = codexi.balance(&BalanceFilter::default())?;
+        assert_eq!(balance_result.total, Amount::ZERO, "The balance of an empty codexi must be 0.0.");
 
         Ok(())
     }
@@ -767,16 +2211,16 @@ mod tests {
     fn test_full_account_balance() -> Result<()> {
         let codexi = setup_codexi_with_data();
 
-        let balance_result = codexi.balance(None, None, None, None, None)?;
+        let balance_result = codexi.balance(&BalanceFilter::default())?;
 
         // ASSERT: Verification of expected results
         // Expected total balance: 310.00 - 134.80 = 175.20
         // Expected total credit: 100.00 + 50.00 + 150.00 + 10.00 = 310.00
         // Expected total debit: 25.50 + 14.20 + 44.80 + 15.70 + 11.00 + 23.60 = 134.80
 
-        assert_eq!(balance_result.credit, 310.00, "The total credits are incorrect");
-        assert_eq!(balance_result.debit, 134.80, "The total debits are incorrect.");
-        assert_eq!(balance_result.total, 175.20, "The final account balance is incorrect.");
+        assert_eq!(balance_result.credit, Amount::from(310.00), "The total credits are incorrect");
+        assert_eq!(balance_result.debit, Amount::from(134.80), "The total debits are incorrect.");
+        assert_eq!(balance_result.total, Amount::from(175.20), "The final account balance is incorrect.");
 
         Ok(())
     }
@@ -786,15 +2230,16 @@ mod tests {
     fn test_balance_with_range_filter() -> Result<()> {
         let codexi = setup_codexi_with_data();
 
-        let balance_result = codexi.balance(
-            Some("2025-12-04".to_string()), // --from (start_date)
-            Some("2025-12-06".to_string()), // --to (end_date)
-            None, None, None
-        )?;
+        let balance_result = codexi.balance(&BalanceFilter {
+            from: Some("2025-12-04".to_string()), // --from (start_date)
+            to: Some("2025-12-06".to_string()), // --to (end_date)
+            ..Default::default()
+        })?;
 
-        assert_eq!(balance_result.credit, 0.00, "The total filtered credit must be 0.0.");
-        assert_eq!(balance_result.debit, 25.50, "The total debits are incorrect.");
-        assert_eq!(balance_result.total, -25.50, "The balance filtered by date range is incorrect.");
+        assert_eq!(balance_result.credit, Amount::ZERO, "The total filtered credit must be 0.0.");
+        assert_eq!(balance_result.debit, Amount::from(25.50), "The total debits are incorrect.");
+        assert_eq!(balance_result.total, Amount::from(-25.50), "The balance filtered by date range is incorrect.");
+        assert_eq!(balance_result.count, 1, "One operation is in the range.");
 
         Ok(())
     }
@@ -803,17 +2248,15 @@ mod tests {
     fn test_balance_with_day_filter_no_operations() -> Result<()> {
         let codexi = setup_codexi_with_data();
 
-        let balance_result = codexi.balance(
-            None,
-            None,
-            Some("2025-12-06".to_string()), // --day
-            None,
-            None,
-        )?;
+        let balance_result = codexi.balance(&BalanceFilter {
+            day: Some("2025-12-06".to_string()), // --day
+            ..Default::default()
+        })?;
 
-        assert_eq!(balance_result.credit, 0.00, "The total filtered credit must be 0.0.");
-        assert_eq!(balance_result.debit, 0.00, "The total filtered debit must be 0.0.");
-        assert_eq!(balance_result.total, 0.00, "The balance filtered by date range is incorrect.");
+        assert_eq!(balance_result.credit, Amount::ZERO, "The total filtered credit must be 0.0.");
+        assert_eq!(balance_result.debit, Amount::ZERO, "The total filtered debit must be 0.0.");
+        assert_eq!(balance_result.total, Amount::ZERO, "The balance filtered by date range is incorrect.");
+        assert_eq!(balance_result.count, 0, "No operation on this day.");
 
         Ok(())
     }
@@ -822,17 +2265,1294 @@ mod tests {
     fn test_balance_with_filter_month() -> Result<()> {
         let codexi = setup_codexi_with_data();
 
-        let balance_result = codexi.balance(
-            None,
-            None,
-            None,
-            Some("2025-11".to_string()), // --month
-            None,
-        )?;
+        let balance_result = codexi.balance(&BalanceFilter {
+            month: Some("2025-11".to_string()), // --month
+            ..Default::default()
+        })?;
+
+        assert_eq!(balance_result.credit, Amount::from(100.00), "The total credits are incorrect.");
+        assert_eq!(balance_result.debit, Amount::from(39.30), "The total debits are incorrect");
+        assert_eq!(balance_result.total, Amount::from(60.70), "The balance filtered by date range is incorrect.");
+        assert_eq!(balance_result.count, 3, "Three operations in the month.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_running_balance_back_dated_debit() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-01-01", 100.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-03-01", 90.0, "Rent")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-04-01", 200.0, "Salary")?;
+        assert!(codexi.check_running_balance().is_ok(), "The running balance should never be negative.");
+
+        // Current balance is 210.00, so the debit check of add_operation lets it through...
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-02-01", 150.0, "Laptop")?;
+
+        // ...but the running balance drops to -50.00 on 2025-02-01.
+        let err = codexi.check_running_balance().unwrap_err();
+        assert!(err.to_string().contains("#1"), "The first offending operation must be named: {}", err);
+        assert!(err.to_string().contains("Laptop"), "The first offending operation must be named: {}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_compare_previous_month() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+
+        let compare = codexi.balance_compare("2025-12")?;
+
+        assert_eq!(compare.previous_period, "2025-11", "The previous period is incorrect.");
+        assert_eq!(compare.current.total, Amount::from(134.50), "The current period balance is incorrect.");
+        assert_eq!(compare.previous.total, Amount::from(60.70), "The previous period balance is incorrect.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_compare_first_period() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+
+        let compare = codexi.balance_compare("2025-10")?;
+
+        assert_eq!(compare.previous_period, "2025-09", "The previous period is incorrect.");
+        assert_eq!(compare.previous.credit, Amount::ZERO, "There is no data before the first period.");
+        assert_eq!(compare.previous.debit, Amount::ZERO, "There is no data before the first period.");
+        assert_eq!(compare.previous.total, Amount::ZERO, "There is no data before the first period.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen_period_restores_ledger() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+        let before = codexi.operations.clone();
+
+        assert!(codexi.close_period("2025-11-30", vec![], None)?);
+        assert!(codexi.reopen_period("codexi_2025-10-31.cld").is_err(), "There is no close on 2025-10-31.");
+
+        let archive_path = codexi.reopen_period("codexi_2025-11-30.cld")?;
+        assert_eq!(codexi.operations, before, "The ledger must be back to its state before the close.");
+        assert_eq!(archive_path, get_archive_path("2025-11-30")?);
+
+        // The anchor is gone, the archive can not be merged twice
+        assert!(codexi.reopen_period("codexi_2025-11-30.cld").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_batch_three_months() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+
+        let closed = codexi.close_batch("2025-10", "2025-12", Vec::new())?;
+
+        assert_eq!(closed, vec!["2025-10-31", "2025-11-30", "2025-12-31"], "One close per month is expected.");
+        assert_eq!(codexi.operations.len(), 1, "Only the last carry-forward should remain.");
+        assert_eq!(codexi.operations[0].kind, OperationKind::System(SystemKind::Close));
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(175.20), "The chained carry-forward is incorrect.");
+
+        for date in &closed {
+            assert!(get_archive_path(date)?.exists(), "The archive of {} is missing.", date);
+        }
+
+        // The November archive starts with the October carry-forward (-20.00)
+        let november = Codexi::load_archive("codexi_2025-11-30.cld")?;
+        assert_eq!(november.operations[0].kind, OperationKind::System(SystemKind::Close));
+        assert_eq!(november.operations[0].amount, Amount::from(20.0));
+        assert_eq!(november.operations[0].flow, OperationFlow::Debit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_operations_max_removed_aborted() -> Result<()> {
+        let mut codexi = setup_codexi_with_data();
+
+        // Over-broad filter: every credit of the codexi
+        let indices: Vec<usize> = codexi.search(&SearchFilter { flow: Some("credit".to_string()), ..Default::default() }, None)?
+            .iter()
+            .map(|item| item.index as usize)
+            .collect();
+        assert_eq!(indices.len(), 4);
+
+        let result = codexi.delete_operations(&indices, None, Some(2));
+
+        assert!(result.is_err(), "The removal must be aborted above --max-removed.");
+        assert_eq!(codexi.operations.len(), 10, "The codexi must be left untouched.");
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(175.20));
+
+        let removed = codexi.delete_operations(&indices, Some(4), Some(4))?;
+        assert_eq!(removed, 4);
+        assert_eq!(codexi.operations.len(), 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_empty_codexi_has_no_latest_dates() -> Result<()> {
+        let codexi = setup_empty_codexi();
 
-        assert_eq!(balance_result.credit, 100.00, "The total credits are incorrect.");
-        assert_eq!(balance_result.debit, 39.30, "The total debits are incorrect");
-        assert_eq!(balance_result.total, 60.70, "The balance filtered by date range is incorrect.");
+        let resume = codexi.resume()?;
+
+        assert_eq!(resume.current_nb_op, 0);
+        assert_eq!(resume.latest_transaction_date, None, "No transaction date expected.");
+        assert_eq!(resume.latest_init_date, None, "No init date expected.");
+        assert_eq!(resume.latest_adjust_date, None, "No adjustment date expected.");
+        assert_eq!(resume.latest_close_date, None, "No closing date expected.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_initialize_if_empty_is_idempotent() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+
+        codexi.initialize(500.0, "2025-11-01", true)?;
+        assert_eq!(codexi.operations.len(), 1, "The first init must add the initial amount.");
+
+        let before = codexi.operations.clone();
+        codexi.initialize(900.0, "2025-12-01", true)?;
+        assert_eq!(codexi.operations, before, "A second init with if_empty must leave the codexi unchanged.");
+
+        assert!(codexi.initialize(900.0, "2025-12-01", false).is_err(), "Without if_empty, init must still fail.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_exclude_system() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 200.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-04", 50.0, "Grocery")?;
+        codexi.adjust_balance(1100.0, "2025-11-05")?;
+
+        let gross = codexi.balance(&BalanceFilter::default())?;
+        let activity = codexi.balance(&BalanceFilter { exclude_system: true, ..Default::default() })?;
+
+        assert_eq!(gross.credit, Amount::from(1200.0), "The gross credits include the init.");
+        assert_eq!(gross.debit, Amount::from(100.0), "The gross debits include the adjustment.");
+        assert_eq!(gross.total, Amount::from(1100.0));
+        assert_eq!(gross.activity, None);
+
+        assert_eq!(activity.credit, Amount::from(200.0), "Only the regular credits are expected.");
+        assert_eq!(activity.debit, Amount::from(50.0), "Only the regular debits are expected.");
+        assert_eq!(activity.total, Amount::from(1100.0), "The balance must stay the true net.");
+        assert_eq!(activity.activity, Some(Amount::from(150.0)), "The activity net is the regular operations only.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_exclude_kind() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 200.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transfer), OperationFlow::Debit, "2025-11-04", 300.0, "To savings")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 50.0, "Grocery")?;
+
+        let all = codexi.balance(&BalanceFilter { month: Some("2025-11".to_string()), exclude_system: true, ..Default::default() })?;
+        let without_transfers = codexi.balance(&BalanceFilter {
+            month: Some("2025-11".to_string()),
+            exclude_system: true,
+            exclude_kinds: vec![OperationKind::Regular(RegularKind::Transfer)],
+            ..Default::default()
+        })?;
+
+        assert_eq!(all.activity, Some(Amount::from(-150.0)));
+        assert_eq!(without_transfers.debit, Amount::from(50.0), "The transfer must not count as spending.");
+        assert_eq!(without_transfers.activity, Some(Amount::from(150.0)), "The net without the transfers differs.");
+        assert_eq!(without_transfers.total, all.total, "The balance stays the true net.");
+
+        let kind_only = codexi.balance(&BalanceFilter { exclude_kinds: vec![OperationKind::Regular(RegularKind::Transfer)], ..Default::default() })?;
+        assert_eq!(kind_only.credit, Amount::from(1200.0), "Without --exclude-system the init is still counted.");
+        assert_eq!(kind_only.activity, Some(Amount::from(1150.0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_activity_per_day() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 10.0, "Coffee")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 5.0, "Bread")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 200.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-07", 80.0, "Shoes")?;
+
+        let activity = codexi.activity(Some("2025-11".to_string()), None)?;
+        assert_eq!(activity.count, 4, "The init is not activity.");
+        assert_eq!(activity.active_days, 2);
+        assert_eq!(activity.busiest_day, Some((NaiveDate::from_ymd_opt(2025, 11, 3).unwrap(), 3)));
+        assert_eq!(activity.highest_spend_day, Some((NaiveDate::from_ymd_opt(2025, 11, 7).unwrap(), Amount::from(80.0))));
+        assert_eq!(activity.average_per_day, 2.0);
+        assert_eq!(activity.median_per_day, 2.0);
+
+        let empty = codexi.activity(Some("2025-12-01".to_string()), Some("2025-12-31".to_string()))?;
+        assert_eq!(empty.active_days, 0);
+        assert_eq!(empty.busiest_day, None);
+        assert_eq!(empty.average_per_day, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_savings_goal() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-09-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-09-05", 300.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-10-05", 300.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 300.0, "Rent")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-06", 600.0, "Bonus")?;
+
+        let goal = codexi.savings_goal(Amount::from(2500.0))?;
+        assert_eq!(goal.balance, Amount::from(1900.0));
+        assert_eq!(goal.remaining, Amount::from(600.0));
+        assert_eq!(goal.percent, 76.0);
+        assert_eq!(goal.monthly_average, Some(Amount::from(300.0)), "The init is not part of the monthly trend.");
+        assert_eq!(goal.months_to_goal, Some(2));
+
+        let reached = codexi.savings_goal(Amount::from(1500.0))?;
+        assert_eq!(reached.remaining, Amount::ZERO);
+        assert_eq!(reached.months_to_goal, Some(0));
+
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-07", 1500.0, "Car")?;
+        let negative = codexi.savings_goal(Amount::from(2500.0))?;
+        assert_eq!(negative.months_to_goal, None, "A negative trend never reaches the goal.");
+
+        assert!(codexi.savings_goal(Amount::ZERO).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_amount_range_excludes_outlier() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(500.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 5000.0, "Car sale")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-04", 40.0, "Grocery")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 60.0, "Fuel")?;
+
+        let month = || Some("2025-11".to_string());
+        let all = codexi.balance(&BalanceFilter { month: month(), exclude_system: true, ..Default::default() })?;
+        let typical = codexi.balance(&BalanceFilter { month: month(), amount_max: Some(Amount::from(1000.0)), exclude_system: true, ..Default::default() })?;
+
+        assert_eq!(all.credit, Amount::from(5000.0));
+        assert_eq!(typical.credit, Amount::ZERO, "The outlier above --a-max is left out.");
+        assert_eq!(typical.debit, Amount::from(100.0));
+        assert_eq!(typical.total, Amount::from(400.0), "The total only includes the operations in the range.");
+        assert_eq!(typical.count, 3);
+
+        let large = codexi.balance(&BalanceFilter { month: month(), amount_min: Some(Amount::from(50.0)), ..Default::default() })?;
+        assert_eq!(large.count, 3, "Init, Car sale and Fuel are at least 50.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_regex() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+        let search = |text: &str, regex: bool| codexi.search(&SearchFilter { text: vec![text.to_string()], regex, ..Default::default() }, None);
+
+        let atm = search("^atm$", true)?;
+        assert_eq!(atm.len(), 3, "Anchored and case-insensitive.");
+        assert!(atm.iter().all(|item| item.op.description == "Atm"));
+        assert_eq!(search("^a", true)?.len(), 3, "Only the descriptions starting with 'a'.");
+
+        let alternation = search("book|fruits", true)?;
+        let descriptions: Vec<&str> = alternation.iter().map(|item| item.op.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["Book", "Fruits"]);
+
+        // Without --regex, the text is a plain substring
+        assert_eq!(search("^atm", false)?.len(), 0);
+        assert_eq!(search("TM", false)?.len(), 3);
+        assert_eq!(search("book|fruits", false)?.len(), 0);
+
+        let err = search("(atm", true).err().expect("An invalid regex is an error, not an empty result.");
+        assert!(err.to_string().contains("Invalid regex '(atm'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_text_any_term() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+        let search = |terms: &[&str], regex: bool| codexi.search(&SearchFilter { text: terms.iter().map(|t| t.to_string()).collect(), regex, ..Default::default() }, None);
+        let descriptions = |items: Vec<SearchItem>| items.iter().map(|item| item.op.description.clone()).collect::<Vec<_>>();
+
+        assert_eq!(descriptions(search(&["book", "FRUITS"], false)?), vec!["Book", "Fruits"], "Any of the terms, case-insensitive.");
+        assert_eq!(descriptions(search(&["book", "nothing"], false)?), descriptions(search(&["book"], false)?));
+        assert_eq!(descriptions(search(&["^boo", "^fru"], true)?), vec!["Book", "Fruits"]);
+
+        let all = search(&[], false)?.len();
+        assert_eq!(all, codexi.operations.len(), "No term: no text filter.");
+        assert_eq!(search(&[""], false)?.len(), all, "An empty term is ignored.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_text_in_category() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        let transaction = || OperationKind::Regular(RegularKind::Transaction);
+        codexi.add_operation(transaction(), OperationFlow::Credit, "2025-11-01", 200.0, "Salary")?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-11-03", 25.5, "Bakery", Some("Groceries"))?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-11-05", 8.0, "Groceries shop", Some("leisure"))?;
+        codexi.add_operation(transaction(), OperationFlow::Debit, "2025-11-07", 4.0, "Coffee")?;
+        let search = |text: &str, regex: bool, text_in: SearchField| {
+            codexi.search(&SearchFilter { text: vec![text.to_string()], regex, text_in, ..Default::default() }, None)
+                .map(|items| items.iter().map(|item| item.op.description.clone()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(search("bakery", false, SearchField::Desc)?, ["Bakery"]);
+        assert!(search("groc", false, SearchField::Desc)?.contains(&"Groceries shop".to_string()), "The default scans the description.");
+        assert_eq!(search("leisure", false, SearchField::Desc)?, Vec::<String>::new(), "The term is only in a category.");
+        assert_eq!(search("leisure", false, SearchField::Category)?, ["Groceries shop"]);
+        assert_eq!(search("groc", false, SearchField::Category)?, ["Bakery"], "Case-insensitive, the description is not scanned.");
+        assert_eq!(search("groc", false, SearchField::All)?, ["Bakery", "Groceries shop"], "Either field matches.");
+        assert_eq!(search("^leis", true, SearchField::All)?, ["Groceries shop"], "With --regex too.");
+        assert_eq!(search("coffee", false, SearchField::Category)?, Vec::<String>::new(), "An uncategorized operation never matches.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_by_category() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        let transaction = || OperationKind::Regular(RegularKind::Transaction);
+        codexi.initialize(1000.0, "2025-10-01", false)?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-10-05", 40.0, "Market", Some("Groceries"))?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-11-03", 25.5, "Bakery", Some("groceries "))?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Credit, "2025-11-04", 5.5, "Bakery refund", Some("GROCERIES"))?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-11-10", 12.0, "Cinema", Some("leisure"))?;
+        codexi.add_operation_with_category(transaction(), OperationFlow::Debit, "2025-11-12", 9.0, "Misc", Some("  "))?;
+        codexi.add_operation(transaction(), OperationFlow::Credit, "2025-11-30", 1500.0, "Salary")?;
+
+        let all = codexi.balance_by_category(None, None)?;
+        let names: Vec<&str> = all.iter().map(|c| c.category.as_str()).collect();
+        assert_eq!(names, vec!["Groceries", "leisure", UNCATEGORIZED], "Case-insensitive groups, uncategorized last, no Init.");
+        assert_eq!(all[0].debit, Amount::from(65.5));
+        assert_eq!(all[0].credit, Amount::from(5.5));
+        assert_eq!(all[0].count, 3);
+        assert_eq!(all[2].count, 2, "A blank category is no category.");
+        assert_eq!(all[2].credit, Amount::from(1500.0));
+
+        let november = codexi.balance_by_category(Some("2025-11".to_string()), Some("2025-11-10".to_string()))?;
+        assert_eq!(november.len(), 2, "Only the operations in the range are grouped.");
+        assert_eq!(november[0].category, "groceries", "The first spelling in the range is kept.");
+        assert_eq!(november[0].debit, Amount::from(25.5));
+        assert_eq!(november[0].count, 2);
+        assert_eq!(november[1].category, "leisure");
+
+        assert!(codexi.balance_by_category(Some("2026-01".to_string()), None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_running_balance_matches_balance() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+        let month = || Some("2025-11".to_string());
+
+        let all = codexi.running_balance(&BalanceFilter::default())?;
+        assert_eq!(all.len(), codexi.operations.len());
+        assert_eq!(all.last().map(|item| item.balance), Some(codexi.balance(&BalanceFilter::default())?.total));
+
+        let november = codexi.running_balance(&BalanceFilter { month: month(), ..Default::default() })?;
+        let balance = codexi.balance(&BalanceFilter { month: month(), ..Default::default() })?;
+        assert_eq!(november.len(), balance.count);
+        assert_eq!(november.last().map(|item| item.balance), Some(balance.total), "The last running value is the total of the period.");
+        assert!(november.iter().all(|item| item.op.date.month() == 11));
+        assert_eq!(november[0].balance, november[0].op.amount * november[0].op.flow.to_sign(), "The running balance starts with the period.");
+        assert!(november.iter().all(|item| codexi.operations[item.index as usize] == *item.op), "The index points into the codexi.");
+
+        let large = codexi.running_balance(&BalanceFilter { from: Some("2025-10".to_string()), to: Some("2025-12".to_string()), amount_min: Some(Amount::from(40.0)), ..Default::default() })?;
+        let balance = codexi.balance(&BalanceFilter { from: Some("2025-10".to_string()), to: Some("2025-12".to_string()), amount_min: Some(Amount::from(40.0)), ..Default::default() })?;
+        assert_eq!(large.last().map(|item| item.balance), Some(balance.total));
+
+        assert!(codexi.running_balance(&BalanceFilter { day: Some("not-a-day".to_string()), ..Default::default() })?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_sort_desc_amount() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+        let search = |sort: SearchSort, latest: Option<usize>| codexi.search(&SearchFilter { sort, latest, ..Default::default() }, None);
+
+        let by_date = search(SearchSort::Date, None)?;
+        let sorted = search(SearchSort::DescAmount, None)?;
+        let max = by_date.iter().map(|item| item.op.amount).max().unwrap();
+
+        assert_eq!(sorted.len(), by_date.len());
+        assert_eq!(sorted[0].op.amount, max, "The largest transaction must come first.");
+        for pair in sorted.windows(2) {
+            assert!(pair[0].op.amount > pair[1].op.amount || (pair[0].op.amount == pair[1].op.amount && pair[0].index < pair[1].index),
+                "Decreasing amounts, equal amounts in date order.");
+        }
+        for item in &sorted {
+            let chrono = by_date.iter().find(|c| c.index == item.index).unwrap();
+            assert_eq!(item.balance, chrono.balance, "The balance of #{} must stay the chronological one.", item.index);
+        }
+
+        // With an amount sort, --latest keeps the first results
+        let top = search(SearchSort::DescAmount, Some(2))?;
+        assert_eq!(top.iter().map(|i| i.index).collect::<Vec<_>>(), sorted[..2].iter().map(|i| i.index).collect::<Vec<_>>());
+        let smallest = search(SearchSort::Amount, Some(1))?;
+        assert_eq!(smallest[0].op.amount, by_date.iter().map(|item| item.op.amount).min().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_page() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+        let all = codexi.search(&SearchFilter::default(), None)?;
+        let indices = |items: &[SearchItem]| items.iter().map(|i| i.index).collect::<Vec<_>>();
+        let total = all.len();
+        let size = 4;
+
+        let mut first = SearchPage::new(1, size)?;
+        let page = codexi.search(&SearchFilter::default(), Some(&mut first))?;
+        assert_eq!(first.total, total, "The total counts all the pages.");
+        assert_eq!(first.count(), total.div_ceil(size));
+        assert_eq!(indices(&page), indices(&all[..size]));
+
+        let mut last = SearchPage::new(first.count(), size)?;
+        let page = codexi.search(&SearchFilter::default(), Some(&mut last))?;
+        assert_eq!(indices(&page), indices(&all[(first.count() - 1) * size..]), "The last page holds the rest.");
+
+        for number in [0, first.count() + 1] {
+            let mut out = SearchPage::new(number, size)?;
+            let page = codexi.search(&SearchFilter::default(), Some(&mut out))?;
+            assert!(page.is_empty(), "Page {} is out of range.", number);
+        }
+        assert!(SearchPage::new(1, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_page_count() -> Result<()> {
+        let page = |number: usize, size: usize, total: usize| SearchPage { number, size, total };
+
+        assert_eq!(page(1, 20, 0).count(), 0);
+        assert_eq!(page(1, 20, 20).count(), 1);
+        assert_eq!(page(1, 20, 21).count(), 2);
+        assert_eq!(page(5, 20, 93).count(), 5);
+        assert_eq!(page(2, 20, 93).range(), 20..40);
+        assert_eq!(page(5, 20, 93).range(), 80..93);
+        assert!(page(6, 20, 93).range().is_empty());
+        assert_eq!(Codexi::page_footer(&page(2, 20, 93)), "Page 2/5 (showing 21–40 of 93)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+
+        let stats = codexi.stats(None, None)?;
+        let credit = stats.credit.expect("credit stats");
+        assert_eq!(credit.count, 4);
+        assert_eq!(credit.median, Amount::from(75.0), "Even count: the mean of 50.00 and 100.00.");
+        assert_eq!(credit.mean, Amount::from(77.5));
+        assert_eq!((credit.min, credit.max), (Amount::from(10.0), Amount::from(150.0)));
+
+        let debit = stats.debit.expect("debit stats");
+        assert_eq!(debit.count, 6);
+        assert_eq!(debit.median, Amount::from(19.65), "The mean of 15.70 and 23.60.");
+        assert_eq!(debit.mean, Amount::from(22.47), "134.80 / 6, rounded to the cent.");
+        assert_eq!(debit.max, Amount::from(44.80));
+
+        let largest = stats.largest.expect("largest operation");
+        assert_eq!((largest.date.to_string().as_str(), largest.amount, largest.description.as_str()), ("2025-12-15", Amount::from(150.0), "Atm"));
+
+        // October: 3 debits, the median is the middle one
+        let october = codexi.stats(Some("2025-10-01".to_string()), Some("2025-10-31".to_string()))?;
+        assert_eq!(october.debit.map(|d| d.median), Some(Amount::from(14.20)));
+        assert!(codexi.stats(Some("2026-01".to_string()), None)?.largest.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_operation_returns_balance() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+        let full_total = |c: &Codexi| c.balance(&BalanceFilter::default()).map(|b| b.total);
+
+        let total = codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-12-20", 200.0, "Bonus")?;
+        assert_eq!(total, full_total(&codexi)?);
+        let total = codexi.apply_operation(OperationKind::Regular(RegularKind::Fee), OperationFlow::Debit, "2025-12-21", 12.35, "Bank fee")?;
+        assert_eq!(total, full_total(&codexi)?);
+
+        // The cache follows the other changes
+        codexi.delete_operation(codexi.operations.len() - 1)?;
+        let total = codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-22", 5.0, "Coffee")?;
+        assert_eq!(total, full_total(&codexi)?, "After a removal.");
+
+        let last = codexi.operations.len() - 1;
+        codexi.recategorize(last, "refund", Some("credit"))?;
+        let total = codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-23", 1.0, "Bread")?;
+        assert_eq!(total, full_total(&codexi)?, "After a change of flow.");
+
+        assert!(codexi.close_period("2025-12-23", vec![], None)?);
+        let total = codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-12-24", 30.0, "Gift")?;
+        assert_eq!(total, full_total(&codexi)?, "After a close.");
+
+        assert!(codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-01", 1.0, "Closed").is_err());
+        assert_eq!(codexi.apply_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-25", 0.0, "Nothing")?, total);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_totals_bulk_add() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2000-01-01", false)?;
+        let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+
+        for i in 0..10_000i64 {
+            let date = (start + chrono::Duration::days(i / 4)).format("%Y-%m-%d").to_string();
+            let (flow, cents) = if i % 3 == 0 { (OperationFlow::Debit, 1_000 + i % 700) } else { (OperationFlow::Credit, 1_500 + i % 900) };
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), flow, &date, Amount::from_cents(cents), "Bulk")?;
+        }
+        let cached = codexi.balance(&BalanceFilter::default())?;
+        let scanned = codexi.balance(&BalanceFilter { from: Some("2000-01-01".to_string()), ..Default::default() })?;
+        assert_eq!(codexi.cached_totals.get(), Some(Totals::of(&codexi.operations)), "The cache must equal a fresh full scan.");
+        assert_eq!((cached.credit, cached.debit, cached.total, cached.count), (scanned.credit, scanned.debit, scanned.total, scanned.count));
+        assert_eq!(cached.count, 10_001);
+
+        // The loaded codexi computes its totals again
+        codexi.save(data_dir.path())?;
+        let loaded = Codexi::load(data_dir.path())?;
+        assert_eq!(loaded.cached_totals.get(), None);
+        assert_eq!(loaded.balance(&BalanceFilter::default())?.total, cached.total);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_keeps_sorted_order() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(10_000.0, "2025-01-01", false)?;
+        let mut reference = codexi.operations.clone();
+
+        // Shuffled dates, several operations a day
+        let n = 97;
+        for i in 0..n {
+            let day = (i * 31 % n) % 28 + 1;
+            let flow = if i % 4 == 0 { OperationFlow::Debit } else { OperationFlow::Credit };
+            let date = format!("2025-02-{:02}", day);
+            let description = format!("Op {}", i);
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), flow, &date, 10.0, &description)?;
+
+            // The former behavior: push and stable sort by date
+            reference.push(Operation::new(OperationKind::Regular(RegularKind::Transaction), flow, &date, 10.0, description)?);
+            reference.sort_by_key(|o| o.date);
+        }
+        codexi.adjust_balance(20_000.0, "2025-02-28")?;
+        reference.push(codexi.operations.iter().find(|op| op.kind == OperationKind::System(SystemKind::Adjust)).unwrap().clone());
+        reference.sort_by_key(|o| o.date);
+
+        let order = |ops: &[Operation]| ops.iter().map(|op| (op.date, op.description.clone())).collect::<Vec<_>>();
+        assert_eq!(order(&codexi.operations), order(&reference));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_reports_each_violation() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-01-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-01-05", 40.0, "Grocery")?;
+        assert!(codexi.verify().is_empty());
+
+        let op = |kind: OperationKind, flow: OperationFlow, date: &str, amount: f64, description: &str| Operation::new(kind, flow, date, amount, description);
+        let transaction = || OperationKind::Regular(RegularKind::Transaction);
+        let mut negative = op(transaction(), OperationFlow::Credit, "2025-03-05", 1.0, "Negative")?;
+        negative.amount = Amount::from(-1.0);
+        let broken = Codexi { operations: vec![
+            op(OperationKind::System(SystemKind::Init), OperationFlow::Credit, "2025-01-01", 100.0, "INITIAL AMOUNT")?,
+            op(transaction(), OperationFlow::Debit, "2025-02-10", 150.0, "Too much")?,
+            op(transaction(), OperationFlow::Credit, "2025-02-01", 200.0, "Late entry")?,
+            op(OperationKind::System(SystemKind::Close), OperationFlow::Credit, "2025-02-28", 150.0, "SOLDE REPORTÉ")?,
+            op(transaction(), OperationFlow::Credit, "2025-02-20", 10.0, "In the closed period")?,
+            negative,
+            op(OperationKind::System(SystemKind::Init), OperationFlow::Credit, "2025-03-10", 5.0, "INITIAL AMOUNT")?,
+        ], ..Default::default() };
+
+        let problems = broken.verify();
+        let has = |text: &str| problems.iter().any(|p| p.contains(text));
+        assert!(has("#2 [") && has("out of date order"), "{:?}", problems);
+        assert!(has("invalid amount -1.00"));
+        assert!(has("2 Init operations found"));
+        assert!(has("in the period closed on 2025-02-28"));
+        assert!(has("#1 [") && has("makes the running balance negative (-50.00)"));
+        assert!(Codexi::default().verify().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_by_weekday() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+
+        // 2025-10-08, 2025-11-05, 2025-11-12 and 2025-12-10 are Wednesdays
+        let wednesdays = codexi.search(&SearchFilter { weekday: Some(Weekday::Wed), ..Default::default() }, None)?;
+        assert_eq!(wednesdays.len(), 4);
+        assert!(wednesdays.iter().all(|item| item.op.date.weekday() == Weekday::Wed));
+
+        let wednesday_debits = codexi.search(&SearchFilter { flow: Some("debit".to_string()), weekday: Some(Weekday::Wed), ..Default::default() }, None)?;
+        assert_eq!(wednesday_debits.len(), 1, "The weekday composes with the flow.");
+        assert_eq!(wednesday_debits[0].op.description, "Bakery");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_average_spend_over_the_calendar_span() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-10", 150.0, "Grocery")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-20", 150.0, "Fuel")?;
+
+        let average = codexi.average_spend(Amount::from(300.0), None, None, None, Some("2025-11"), None)?.expect("a span");
+        assert_eq!(average.days, 30, "The days without operation count.");
+        assert_eq!(average.months, 1.0);
+        assert_eq!(average.per_day, Amount::from(10.0));
+        assert_eq!(average.per_month, Amount::from(300.0));
+
+        let two_months = codexi.average_spend(Amount::from(300.0), Some("2025-11"), Some("2025-12"), None, None, None)?.expect("a span");
+        assert_eq!(two_months.days, 61);
+        assert_eq!(two_months.per_month, Amount::from(150.0));
+
+        let single_day = codexi.average_spend(Amount::from(150.0), None, None, Some("2025-11-10"), None, None)?.expect("a span");
+        assert_eq!(single_day.days, 1);
+        assert_eq!(single_day.per_day, Amount::from(150.0));
+
+        let open = codexi.average_spend(Amount::from(300.0), None, None, None, None, None)?.expect("a span");
+        assert_eq!((open.start, open.end), (NaiveDate::from_ymd_opt(2025, 11, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 11, 20).unwrap()));
+
+        assert_eq!(codexi.average_spend(Amount::ZERO, Some("2025-12-01"), Some("2025-11-01"), None, None, None)?, None);
+        assert_eq!(setup_empty_codexi().average_spend(Amount::ZERO, None, None, None, None, None)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_discretionary_spend_policy() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 60.0, "Restaurant")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Custom("Leisure".to_string())), OperationFlow::Debit, "2025-11-04", 40.0, "Cinema")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Refund), OperationFlow::Credit, "2025-11-05", 15.0, "Shoes returned")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Fee), OperationFlow::Debit, "2025-11-06", 5.0, "Bank fee")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transfer), OperationFlow::Debit, "2025-11-07", 200.0, "Savings")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-08", 500.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-01", 30.0, "Books")?;
+
+        let result = codexi.discretionary(Some("2025-11".to_string()), Some("2025-11".to_string()))?;
+        assert_eq!(result.spend, Amount::from(100.0), "Transactions and custom categories are spend.");
+        assert_eq!(result.refunds, Amount::from(15.0));
+        assert_eq!(result.total, Amount::from(85.0));
+        assert_eq!(result.count, 3);
+        assert_eq!(result.ignored, 3, "The fee, the transfer and the salary are ignored.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_preview_matches_balance_at_date() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 120.5, "Groceries")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-20", 300.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-02", 50.0, "Books")?;
+        let before = codexi.operations.clone();
+
+        let preview = codexi.close_preview("2025-11-30")?.expect("a period to close");
+        let balance_at = codexi.balance(&BalanceFilter { to: Some("2025-11-30".to_string()), ..Default::default() })?;
+        assert_eq!(preview.carry_forward, balance_at.total, "The carry-forward is the balance at the close date.");
+        assert_eq!(preview.archived, 3);
+        assert_eq!(codexi.operations, before, "The preview must not change the codexi.");
+
+        assert_eq!(codexi.close_preview("2025-11-01")?, None, "Only the init: nothing to close.");
+
+        codexi.close_period("2025-11-30", vec![], None)?;
+        let close = codexi.operations.iter().find(|op| op.kind.is_system()).expect("the close anchor");
+        assert_eq!(close.amount, preview.carry_forward, "The close carries the previewed balance.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_flagged() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 49.99, "Unknown charge")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-04", 12.0, "Lunch")?;
+
+        codexi.set_flag(1, true)?;
+        codexi.set_flag(2, true)?;
+        codexi.set_flag(2, false)?;
+
+        let flagged = codexi.search(&SearchFilter { flagged: true, ..Default::default() }, None)?;
+        assert_eq!(flagged.len(), 1, "Only the starred operation is found.");
+        assert_eq!(flagged[0].op.description, "Unknown charge");
+
+        let all = codexi.search(&SearchFilter::default(), None)?;
+        assert_eq!(all.len(), 3);
+        assert!(codexi.set_flag(9, true).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_json_round_trip() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 50.0, "Lunch")?;
+
+        let resume = codexi.resume()?;
+        let json = serde_json::to_string_pretty(&resume)?;
+        assert!(json.contains("\"latest_transaction_date\": \"2025-11-05\""));
+        assert!(json.contains("\"latest_close_date\": null"));
+
+        let back: ResumeResult = serde_json::from_str(&json)?;
+        assert_eq!(back, resume);
+        assert_eq!(back.current_balance, Amount::from(950.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adjust_balance_zero_difference_creates_nothing() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(0.1, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 0.2, "Refund")?;
+
+        // 0.1 + 0.2 is not exactly 0.3, the residue must not become an adjustment
+        codexi.adjust_balance(0.3, "2025-11-03")?;
+        codexi.adjust_balance(0.304, "2025-11-03")?;
+        assert_eq!(codexi.operations.len(), 2, "No adjustment expected under the cent.");
+        assert!(codexi.operations.iter().all(|op| !op.flow.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_period_zero_carry_forward() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(0.1, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 0.2, "Refund")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 0.3, "Coffee")?;
+
+        assert!(codexi.close_period("2025-11-30", Vec::new(), None)?);
+
+        // The anchor is kept to mark the close, but it moves nothing
+        assert_eq!(codexi.operations.len(), 1);
+        let anchor = &codexi.operations[0];
+        assert_eq!(anchor.kind, OperationKind::System(SystemKind::Close));
+        assert_eq!(anchor.flow, OperationFlow::None, "A residue must not give a Credit or a Debit.");
+        assert_eq!(anchor.amount, Amount::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balance_by_payee() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-10-01", false)?;
+        let ops = [
+            (OperationFlow::Debit, "2025-10-20", 30.0, "Bistro - Lunch"),
+            (OperationFlow::Debit, "2025-11-02", 80.0, "Market - Weekly grocery"),
+            (OperationFlow::Debit, "2025-11-09", 60.0, "market - Grocery"),
+            (OperationFlow::Debit, "2025-11-10", 25.5, "Bistro - Dinner"),
+            (OperationFlow::Debit, "2025-11-12", 12.0, "Bistro"),
+            (OperationFlow::Debit, "2025-11-15", 9.99, "Streaming - Monthly plan"),
+            (OperationFlow::Debit, "2025-11-20", 4.5, "Bakery"),
+            (OperationFlow::Credit, "2025-11-25", 500.0, "Employer - Salary"),
+        ];
+        for (flow, date, amount, description) in ops {
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), flow, date, amount, description)?;
+        }
+
+        let november = codexi.balance_by_payee(&BalanceFilter { month: Some("2025-11".to_string()), ..Default::default() }, None, None)?;
+        let names: Vec<&str> = november.payees.iter().map(|p| p.payee.as_str()).collect();
+        assert_eq!(names, ["Market", "Bistro", "Streaming", "Bakery"], "Sorted by total, the credits and October excluded.");
+        assert_eq!((november.payees[0].count, november.payees[0].total), (2, Amount::from(140.0)), "The payee is grouped case-insensitively.");
+        assert_eq!((november.payees[1].count, november.payees[1].total), (2, Amount::from(37.5)));
+        assert_eq!(november.other.count, 0);
+        assert_eq!(november.total, Amount::from(191.99));
+
+        let top = codexi.balance_by_payee(&BalanceFilter { month: Some("2025-11".to_string()), ..Default::default() }, Some(2), None)?;
+        assert_eq!(top.payees.len(), 2);
+        assert_eq!((top.other.count, top.other.total), (2, Amount::from(14.49)), "Beyond the top 2 goes to Other.");
+
+        let threshold = codexi.balance_by_payee(&BalanceFilter { from: Some("2025-10-01".to_string()), to: Some("2025-11-30".to_string()), ..Default::default() }, None, Some(Amount::from(10.0)))?;
+        assert_eq!(threshold.payees[1].total, Amount::from(67.5), "The range includes the October lunch.");
+        assert_eq!((threshold.other.count, threshold.other.total), (2, Amount::from(14.49)), "Below the threshold goes to Other.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaps_over_threshold() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(1000.0, "2025-01-01", false)?;
+        for date in ["2025-01-05", "2025-01-12", "2025-01-19", "2025-03-10", "2025-03-17", "2025-04-20"] {
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, date, 10.0, "Grocery")?;
+        }
+        // An anchor in the middle of the gap is not a record
+        codexi.adjust_balance(500.0, "2025-02-15")?;
+
+        let gaps = codexi.gaps(30);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!((gaps[0].start.to_string(), gaps[0].end.to_string(), gaps[0].days), ("2025-01-19".to_string(), "2025-03-10".to_string(), 50));
+        assert_eq!(gaps[1].days, 34);
+
+        assert!(codexi.gaps(50).is_empty(), "A gap of exactly the threshold is not reported.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_credit_found_by_kind() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 20.0, "Gift")?;
+        // As `codexi credit -k fee ...` does
+        codexi.add_operation(OperationKind::Regular(RegularKind::try_from_str("fee")?), OperationFlow::Credit, "2025-11-03", 4.5, "Fee reversal")?;
+
+        assert_eq!(codexi.operations[2].kind, OperationKind::Regular(RegularKind::Fee));
+
+        let fees = codexi.search(&SearchFilter { kind: Some("fee".to_string()), ..Default::default() }, None)?;
+        assert_eq!(fees.len(), 1, "Only the fee is found by --kind fee.");
+        assert_eq!(fees[0].op.flow, OperationFlow::Credit);
+        assert_eq!(fees[0].op.description, "Fee reversal");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hundred_dimes_sum_exactly() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(0.0, "2025-11-01", false)?;
+        for _ in 0..100 {
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 0.10, "Dime")?;
+        }
+
+        let balance = codexi.balance(&BalanceFilter::default())?;
+        assert_eq!(balance.total, Amount::from_cents(1000), "100 x 0.10 must be exactly 10.00.");
+        assert_eq!(balance.credit.to_string(), "10.00");
+
+        // Spent back to zero: no residue left for an adjustment or a close
+        for _ in 0..100 {
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 0.10, "Dime")?;
+        }
+        assert!(codexi.balance(&BalanceFilter::default())?.total.is_zero());
+        codexi.adjust_balance(0.0, "2025-11-04")?;
+        assert!(!codexi.operations.iter().any(|op| op.kind == OperationKind::System(SystemKind::Adjust)));
+        assert_eq!(codexi.close_preview("2025-11-30")?.map(|p| p.carry_forward), Some(Amount::ZERO));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_period_empty_codexi() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_empty_codexi();
+
+        assert!(!codexi.close_period("2025-11-30", Vec::new(), None)?, "An empty codexi has nothing to close.");
+        assert!(codexi.operations.is_empty(), "No anchor must be created.");
+        assert!(!get_archive_path("2025-11-30")?.exists(), "No archive must be written.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_period_only_prior_close_anchor() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+        codexi.close_period("2025-12-31", Vec::new(), None)?;
+        let before = codexi.operations.clone();
+        assert_eq!(before.len(), 1);
+
+        assert!(!codexi.close_period("2026-01-31", Vec::new(), None)?, "A lone Close anchor has nothing to close.");
+        assert_eq!(codexi.operations, before, "The prior anchor must be kept untouched.");
+        assert!(!get_archive_path("2026-01-31")?.exists(), "No archive must be written.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_period_with_operations() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+
+        assert!(codexi.close_period("2025-11-30", Vec::new(), None)?);
+
+        let anchors: Vec<_> = codexi.operations.iter()
+            .filter(|op| op.kind == OperationKind::System(SystemKind::Close))
+            .collect();
+        assert_eq!(anchors.len(), 1, "Exactly one Close anchor is expected.");
+        assert_eq!(anchors[0].date, NaiveDate::from_ymd_opt(2025, 11, 30).unwrap());
+        assert!(codexi.operations.iter().all(|op| op.date >= anchors[0].date), "Closed operations must be archived.");
+        assert!(get_archive_path("2025-11-30")?.exists(), "The archive is missing.");
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(175.20), "The close must keep the balance.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_period_keep_open_copy() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+
+        let copy_dir = tempfile::tempdir()?;
+        assert!(codexi.close_period("2025-11-30", Vec::new(), Some(&copy_dir.path().join("codexi.toml")))?);
+
+        let archive = Codexi::load_archive("codexi_2025-11-30.cld")?;
+        let copy = Codexi::import_toml(copy_dir.path())?;
+        let keys = |ops: &[Operation]| ops.iter().map(Operation::dedup_key).collect::<Vec<_>>();
+        assert_eq!(keys(&copy.operations), keys(&archive.operations), "The copy must hold exactly the archived operations.");
+
+        // A bad copy path leaves the codexi open
+        let before = codexi.operations.clone();
+        assert!(codexi.close_period("2025-12-31", Vec::new(), Some(&copy_dir.path().join("copy.txt"))).is_err());
+        assert_eq!(codexi.operations, before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monthly_balances() -> Result<()> {
+        let codexi = setup_codexi_with_data();
+
+        let months = codexi.monthly_balances(None, None)?;
+
+        let nets: Vec<(&str, Amount)> = months.iter().map(|m| (m.month.as_str(), m.balance.total)).collect();
+        assert_eq!(nets, vec![("2025-10", Amount::from(-20.00)), ("2025-11", Amount::from(60.70)), ("2025-12", Amount::from(134.50))]);
+
+        let months = codexi.monthly_balances(Some("2025-12".to_string()), Some("2026-01".to_string()))?;
+        assert_eq!(months.len(), 2, "Empty months are included.");
+        assert_eq!(months[1].balance.total, Amount::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_category_creation_and_filter() -> Result<()> {
+        let mut codexi = setup_codexi_with_data();
+        let salary = RegularKind::try_from_str("Salary")?;
+        assert_eq!(salary, RegularKind::Custom("Salary".to_string()), "An unknown kind is a custom category.");
+        assert_eq!(RegularKind::try_from_str("fee")?, RegularKind::Fee, "The known kinds are unchanged.");
+
+        codexi.add_operation(OperationKind::Regular(salary), OperationFlow::Credit, "2025-12-20", 1500.0, "December pay")?;
+
+        let results = codexi.search(&SearchFilter { kind: Some("salary".to_string()), ..Default::default() }, None)?;
+        assert_eq!(results.len(), 1, "The custom category must be found whatever the case.");
+        assert_eq!(results[0].op.kind.as_str(), "Salary", "The category is displayed as entered.");
+
+        let transactions = codexi.search(&SearchFilter { kind: Some("transaction".to_string()), ..Default::default() }, None)?;
+        assert_eq!(transactions.len(), 10, "A custom category is not a transaction.");
+
+        // The category travels through the data file and the csv export
+        let decoded = Codexi::from_bytes(&codexi.to_bytes()?)?;
+        assert_eq!(decoded.operations, codexi.operations);
+
+        let dir = tempfile::tempdir()?;
+        codexi.export_csv(dir.path(), true, false)?;
+        let (imported, summary) = Codexi::import_csv(dir.path(), false)?;
+        assert!(summary.errors.is_empty());
+        assert!(imported.operations.iter().any(|op| op.kind == OperationKind::Regular(RegularKind::Custom("Salary".to_string()))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedupe_three_identical_credits() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        for _ in 0..3 {
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-05", 50.0, "Salary")?;
+        }
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-05", 50.0, "Bonus")?;
+
+        assert_eq!(codexi.dedupe(true)?, 2, "Two duplicates expected.");
+        assert_eq!(codexi.operations.len(), 5, "The dry run must not remove anything.");
+
+        assert_eq!(codexi.dedupe(false)?, 2);
+        assert_eq!(codexi.operations.len(), 3, "One credit must be kept, besides the init and the bonus.");
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(200.0));
+        assert_eq!(codexi.dedupe(false)?, 0, "Nothing left to collapse.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_future_date_policy() -> Result<()> {
+        let mut codexi = setup_codexi_with_data();
+        let today = Local::now().date_naive();
+        let far_future = (today + chrono::Duration::days(365 * 27)).format("%Y-%m-%d").to_string();
+
+        let result = codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, &far_future, 10.0, "Typo");
+        assert!(result.is_err_and(|e| e.to_string().contains("--allow-future")), "By default a future-dated debit is rejected.");
+        assert_eq!(codexi.operations.len(), 10);
+
+        codexi.future_dates = FutureDatePolicy::Allow;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, &far_future, 10.0, "Planned")?;
+        assert_eq!(codexi.operations.len(), 11, "--allow-future accepts the date.");
+
+        let mut fresh = Codexi::default();
+        fresh.initialize(100.0, &today.format("%Y-%m-%d").to_string(), false)?;
+        assert_eq!(fresh.operations.len(), 1, "The init can be dated today.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adjust_balance_stores_deviation() -> Result<()> {
+        let mut codexi = setup_codexi_with_data();
+        let theoretical = codexi.balance(&BalanceFilter::default())?.total;
+
+        codexi.adjust_balance(150.0, "2025-12-31")?;
+
+        let adjust = codexi.operations.iter()
+            .find(|op| op.kind == OperationKind::System(SystemKind::Adjust))
+            .expect("The adjustment operation is missing.");
+        let info = adjust.adjustment.expect("The adjustment details are missing.");
+
+        assert_eq!(info.previous_balance, theoretical);
+        assert_eq!(info.physical_balance, Amount::from(150.0));
+        assert_eq!(info.deviation, Amount::from(150.0) - theoretical, "The deviation is physical - theoretical.");
+        assert_eq!(adjust.flow, OperationFlow::Debit, "A negative deviation is a debit.");
+        assert_eq!(codexi.resume()?.adjustment_total, Amount::from(-25.20));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_history() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_codexi_with_data();
+
+        let october = codexi.balance(&BalanceFilter { to: Some("2025-10-31".to_string()), ..Default::default() })?.total;
+        let november = codexi.balance(&BalanceFilter { to: Some("2025-11-30".to_string()), ..Default::default() })?.total;
+        assert!(codexi.close_period("2025-10-31", Vec::new(), None)?);
+        assert!(codexi.close_period("2025-11-30", Vec::new(), None)?);
+
+        let history = codexi.close_history()?;
+        assert_eq!(history.len(), 2, "One entry per close is expected.");
+        assert_eq!(history[0].date, NaiveDate::from_ymd_opt(2025, 10, 31).unwrap());
+        assert_eq!(history[0].carry_forward, october);
+        assert_eq!(history[0].delta, None, "The first close has no previous carry-forward.");
+        assert_eq!(history[1].carry_forward, november);
+        assert_eq!(history[1].delta, Some(november - october));
+        assert!(history.iter().all(|e| e.archived.is_some_and(|n| n > 0)), "Both closes have an archive.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_id_is_stable() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let mut codexi = setup_empty_codexi();
+
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-05", 100.0, "Salary")?;
+        let id = codexi.operations[0].id;
+        assert_eq!(codexi.index_of_id(&codexi.operations[0].short_id())?, 0, "The short id must find the operation.");
+
+        // An older operation is sorted before it
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 50.0, "Gift")?;
+        assert_eq!(codexi.operations[1].id, id, "The id must survive a sort.");
+        assert_eq!(codexi.index_of_id(&id.to_string())?, 1);
+
+        codexi.save(data_dir.path())?;
+        let loaded = Codexi::load(data_dir.path())?;
+        assert_eq!(loaded.operations[1].id, id, "The id must survive a save/load round trip.");
+        assert!(loaded.index_of_id("zzzz").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recategorize_transaction_to_fee() -> Result<()> {
+        let mut codexi = setup_codexi_with_data();
+        let index = codexi.operations.iter().position(|op| op.flow.is_debit()).unwrap();
+        let fees = |c: &Codexi| c.search(&SearchFilter { kind: Some("fee".to_string()), ..Default::default() }, None).map(|r| r.len());
+        let before = fees(&codexi)?;
+
+        codexi.recategorize(index, "fee", None)?;
+        assert_eq!(codexi.operations[index].kind, OperationKind::Regular(RegularKind::Fee));
+        assert_eq!(fees(&codexi)?, before + 1, "The search -k fee must now find the operation.");
+        assert!(codexi.recategorize(index, "init", None).is_err(), "A regular operation cannot become a system one.");
+
+        // Flipping the salary to a debit would leave the balance negative
+        let mut codexi = setup_empty_codexi();
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 90.0, "Rent")?;
+        assert!(codexi.recategorize(0, "transaction", Some("debit")).is_err());
+        assert_eq!(codexi.operations[0].flow, OperationFlow::Credit, "A refused change must leave the operation unchanged.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_operation_append_prepend() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?;
+
+        codexi.edit_operation(0, &OperationEdit { append: Some("november"), separator: " - ", ..Default::default() })?;
+        assert_eq!(codexi.operations[0].description, "Salary - november", "Append must keep the original text.");
+
+        codexi.edit_operation(0, &OperationEdit { prepend: Some("[late]"), ..Default::default() })?;
+        assert_eq!(codexi.operations[0].description, "[late] Salary - november");
+
+        codexi.edit_operation(0, &OperationEdit { description: Some("Pay"), append: Some("bonus"), ..Default::default() })?;
+        assert_eq!(codexi.operations[0].description, "Pay bonus");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_operation_amount() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 21.0, "Lunch")?;
+
+        codexi.edit_operation(1, &OperationEdit { amount: Some(Amount::from(12.0)), ..Default::default() })?;
+        assert_eq!(codexi.operations[1].amount, Amount::from(12.0));
+        assert_eq!(codexi.operations[1].description, "Lunch", "The other fields are unchanged.");
+
+        // The amount of a system entry can be corrected
+        codexi.edit_operation(0, &OperationEdit { amount: Some(Amount::from(150.0)), ..Default::default() })?;
+        assert_eq!(codexi.balance(&BalanceFilter::default())?.total, Amount::from(138.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_operation_date_reorders() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 10.0, "Lunch")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 20.0, "Grocery")?;
+        let id = codexi.operations[1].id;
+
+        codexi.edit_operation(1, &OperationEdit { date: Some("2025-11-10"), kind: Some("fee"), ..Default::default() })?;
+        assert_eq!(codexi.operations[2].id, id, "The operation moves after the grocery.");
+        assert_eq!(codexi.operations[2].kind, OperationKind::Regular(RegularKind::Fee));
+        assert_eq!(codexi.operations[1].description, "Grocery");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_operation_rejects_illegal_edits() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 60.0, "Rent")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-03", 30.0, "Grocery")?;
+        let before = codexi.operations.clone();
+
+        let err = codexi.edit_operation(0, &OperationEdit { kind: Some("transaction"), ..Default::default() }).unwrap_err();
+        assert!(err.to_string().contains("protected system entry"), "{}", err);
+        assert!(codexi.edit_operation(0, &OperationEdit { date: Some("2025-11-02"), ..Default::default() }).is_err(), "A system entry keeps its date.");
+        assert!(codexi.edit_operation(1, &OperationEdit { date: Some("2025-10-30"), ..Default::default() }).is_err(), "Before the init anchor.");
+
+        // The running balance would become negative at the rent
+        assert!(codexi.edit_operation(1, &OperationEdit { amount: Some(Amount::from(120.0)), ..Default::default() }).is_err());
+        // A smaller init leaves the grocery uncovered
+        assert!(codexi.edit_operation(0, &OperationEdit { amount: Some(Amount::from(80.0)), ..Default::default() }).is_err());
+        assert_eq!(codexi.operations, before, "A refused edit must leave the codexi unchanged.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_operation_checks_running_balance() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 60.0, "Rent")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-05", 50.0, "Refund")?;
+        let before = codexi.operations.clone();
+
+        // The final balance stays at 30.00, but the rent is not covered on 2025-11-02
+        let err = codexi.edit_operation(1, &OperationEdit { amount: Some(Amount::from(120.0)), ..Default::default() }).unwrap_err();
+        assert!(err.to_string().contains("Negative running balance"), "{}", err);
+        // Moving a credit after the debit it funds is caught as well
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 40.0, "Gift")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-04", 70.0, "Bike")?;
+        assert!(codexi.edit_operation(2, &OperationEdit { date: Some("2025-11-06"), ..Default::default() }).is_err(), "The bike is no longer funded.");
+        assert_eq!(codexi.operations[..2], before[..2], "A refused edit must leave the codexi unchanged.");
+        assert_eq!(codexi.totals().total(), Amount::from(60.0));
+
+        // A description change keeps a sound ledger sound
+        codexi.edit_operation(1, &OperationEdit { description: Some("Rent november"), ..Default::default() })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_description_normalization() -> Result<()> {
+        let mut codexi = setup_empty_codexi();
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "ATM   withdrawal")?;
+        assert_eq!(codexi.operations[0].description, "ATM   withdrawal", "By default the description is left untouched.");
+
+        codexi.description_style = DescriptionStyle::Collapse;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 10.0, "  ATM  withdrawal ")?;
+        assert_eq!(codexi.operations[1].description, "ATM withdrawal");
+
+        codexi.description_style = DescriptionStyle::TitleCase;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-03", 10.0, "atm  WITHDRAWAL été")?;
+        assert_eq!(codexi.operations[2].description, "Atm Withdrawal Été");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_only_system_or_regular() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = setup_empty_codexi();
+        codexi.initialize(100.0, "2025-10-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-10-05", 20.0, "Grocery")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Fee), OperationFlow::Debit, "2025-10-06", 2.0, "Bank fee")?;
+        codexi.adjust_balance(75.0, "2025-10-10")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-10-12", 10.0, "Refund")?;
+
+        let system = codexi.search(&SearchFilter { system: Some(true), ..Default::default() }, None)?;
+        assert_eq!(system.len(), 2, "The Init and the Adjust are the system operations.");
+        assert!(system.iter().all(|item| item.op.kind.is_system()));
+
+        let regular = codexi.search(&SearchFilter { system: Some(false), ..Default::default() }, None)?;
+        assert_eq!(regular.len(), 3);
+        assert!(regular.iter().all(|item| item.op.kind.is_regular()));
+
+        // Composes with the other filters
+        let debits = codexi.search(&SearchFilter { system: Some(false), flow: Some("debit".to_string()), ..Default::default() }, None)?;
+        assert_eq!(debits.len(), 2);
 
         Ok(())
     }
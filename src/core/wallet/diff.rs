@@ -0,0 +1,157 @@
+// src/core/wallet/diff.rs
+
+use super::codexi::Codexi;
+use super::operation::Operation;
+
+/// Enum representing a difference between two codexi
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffEntry {
+    Added(Operation),
+    Removed(Operation),
+    Changed { before: Operation, after: Operation },
+}
+/// Methods for DiffEntry
+impl DiffEntry {
+    /// Get the date used to order the entries
+    fn date(&self) -> chrono::NaiveDate {
+        match self {
+            DiffEntry::Added(op) | DiffEntry::Removed(op) => op.date,
+            DiffEntry::Changed { after, .. } => after.date,
+        }
+    }
+    /// Get the list of changed fields as (name, before, after)
+    pub fn changed_fields(&self) -> Vec<(&'static str, String, String)> {
+        let DiffEntry::Changed { before, after } = self else {
+            return Vec::new();
+        };
+
+        let mut fields = Vec::new();
+        if before.date != after.date {
+            fields.push(("date", before.date.to_string(), after.date.to_string()));
+        }
+        if before.kind != after.kind {
+            fields.push(("kind", before.kind.to_string(), after.kind.to_string()));
+        }
+        if before.flow != after.flow {
+            fields.push(("flow", before.flow.as_str().to_string(), after.flow.as_str().to_string()));
+        }
+        if before.amount != after.amount {
+            fields.push(("amount", before.amount.to_string(), after.amount.to_string()));
+        }
+        if before.description != after.description {
+            fields.push(("description", before.description.clone(), after.description.clone()));
+        }
+        fields
+    }
+}
+
+/// Methods for comparing codexi
+impl Codexi {
+
+    /// Compares the codexi with another one (ex: the content of an import file).
+    /// ex: let entries = codexi.diff(&incoming);
+    /// Identical operations (same content, whatever their id) are ignored. Operations left on both sides sharing the same date
+    /// and the same description (or the same amount and flow) are reported as changed,
+    /// the others as added (only in `other`) or removed (only in `self`).
+    pub fn diff(&self, other: &Codexi) -> Vec<DiffEntry> {
+        let mut removed: Vec<&Operation> = self.operations.iter().collect();
+        let mut added: Vec<&Operation> = Vec::new();
+
+        // 1. Drop the identical operations
+        for op in &other.operations {
+            match removed.iter().position(|o| o.dedup_key() == op.dedup_key()) {
+                Some(pos) => { removed.remove(pos); },
+                None => added.push(op),
+            }
+        }
+
+        // 2. Pair the remaining operations describing the same event
+        let mut entries = Vec::new();
+        for op in added {
+            let same_event = removed.iter().position(|o| {
+                o.date == op.date
+                    && (o.description == op.description || (o.amount == op.amount && o.flow == op.flow))
+            });
+
+            match same_event {
+                Some(pos) => entries.push(DiffEntry::Changed {
+                    before: removed.remove(pos).clone(),
+                    after: op.clone(),
+                }),
+                None => entries.push(DiffEntry::Added(op.clone())),
+            }
+        }
+
+        // 3. What is left only exists in the current codexi
+        entries.extend(removed.into_iter().map(|op| DiffEntry::Removed(op.clone())));
+        entries.sort_by_key(|e| e.date());
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::super::amount::Amount;
+    use super::super::operation_flow::OperationFlow;
+    use super::super::operation_kind::OperationKind;
+    use super::super::regular_kind::RegularKind;
+    use crate::core::helpers::JsonStyle;
+
+    fn op(date: &str, flow: OperationFlow, amount: f64, desc: &str) -> Operation {
+        Operation::new(OperationKind::Regular(RegularKind::Transaction), flow, date, amount, desc).unwrap()
+    }
+
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let current = Codexi { operations: vec![
+            op("2025-11-01", OperationFlow::Credit, 100.0, "Salary"),
+            op("2025-11-02", OperationFlow::Debit, 12.0, "Lunch"),
+            op("2025-11-03", OperationFlow::Debit, 30.0, "Books"),
+        ], ..Default::default() };
+        let incoming = Codexi { operations: vec![
+            op("2025-11-01", OperationFlow::Credit, 100.0, "Salary"),
+            op("2025-11-02", OperationFlow::Debit, 14.5, "Lunch"),
+            op("2025-11-04", OperationFlow::Debit, 8.0, "Coffee"),
+        ], ..Default::default() };
+
+        let entries = current.diff(&incoming);
+
+        assert_eq!(entries.len(), 3, "Expected one change, one removal and one addition.");
+        assert!(matches!(&entries[0], DiffEntry::Changed { after, .. } if after.amount == Amount::from(14.5)));
+        assert_eq!(entries[0].changed_fields(), vec![("amount", "12.00".to_string(), "14.50".to_string())]);
+        assert!(matches!(&entries[1], DiffEntry::Removed(o) if o.description == "Books"));
+        assert!(matches!(&entries[2], DiffEntry::Added(o) if o.description == "Coffee"));
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        let current = Codexi { operations: vec![op("2025-11-01", OperationFlow::Credit, 100.0, "Salary")], ..Default::default() };
+
+        assert!(current.diff(&current.clone()).is_empty(), "Identical codexi must not differ.");
+    }
+
+    #[test]
+    fn test_diff_with_json_export() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut current = Codexi { operations: vec![
+            op("2025-11-01", OperationFlow::Credit, 100.0, "Salary"),
+            op("2025-11-02", OperationFlow::Debit, 12.0, "Lunch"),
+        ], ..Default::default() };
+        current.export_json(dir.path(), false, JsonStyle::Pretty)?;
+
+        let incoming = Codexi::import_json(dir.path())?;
+        assert!(current.diff(&incoming).is_empty(), "The json round trip must not differ.");
+
+        current.operations[1].amount = Amount::from(14.5);
+        current.operations.push(op("2025-11-04", OperationFlow::Debit, 8.0, "Coffee"));
+        let entries = incoming.diff(&current);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].changed_fields(), vec![("amount", "12.00".to_string(), "14.50".to_string())]);
+        assert!(matches!(&entries[1], DiffEntry::Added(o) if o.description == "Coffee"));
+
+        Ok(())
+    }
+}
@@ -1,24 +1,431 @@
 // src/core/wallet/file_management.rs
 
 use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use chrono::{Local, NaiveDate};
 use std::fs::File;
 use std::fs;
-use std::io;
+use std::io::{self, Read, Seek, Write};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use zip::write::{FileOptions, ZipWriter};
 use zip::ZipArchive;
 use walkdir::WalkDir;
+use uuid::Uuid;
 
+use super::amount::Amount;
 use super::operation::Operation;
-use super::codexi::Codexi;
+use super::operation::AdjustmentInfo;
+use super::operation_flow::OperationFlow;
+use super::operation_kind::OperationKind;
+use super::regular_kind::RegularKind;
+use super::system_kind::SystemKind;
+use super::codexi::{Codexi, CodexiError};
 
 use crate::core::helpers::get_data_dir;
+use crate::core::helpers::get_accounts_dir;
 use crate::core::helpers::get_snapshot_path;
+use crate::core::helpers::get_snapshot_dir;
+use crate::core::helpers::snapshot_order;
+use crate::core::helpers::get_archive_dir;
+use crate::core::helpers::get_archive_path;
+use crate::core::helpers::get_safety_backup_path;
+use crate::core::helpers::parse_backup_timestamp;
+use crate::core::helpers::write_atomic;
+use crate::core::helpers::move_file;
+use crate::core::helpers::JsonStyle;
+use crate::core::crypto;
 
+/// Magic bytes at the start of the versioned data files (codexi.dat, .snp)
+const DATA_MAGIC: &[u8; 4] = b"CDXI";
+/// Current version of the data layout
+/// 0: no header, operations only (files written before the versioned layout, archives)
+/// 1: title
+/// 2: adjustment details on the operations
+/// 3: stable id on the operations
+/// 4: flag on the operations
+/// 5: amounts in cents (i64), the older amounts (f64) are rounded to the cent
+/// 6: category on the operations
+pub const DATA_VERSION: u16 = 6;
+
+/// Layout of the adjustment details up to version 4 (amounts in f64)
+#[derive(Deserialize)]
+struct AdjustmentInfoV4 {
+    previous_balance: f64,
+    physical_balance: f64,
+    deviation: f64,
+}
+impl From<AdjustmentInfoV4> for AdjustmentInfo {
+    fn from(info: AdjustmentInfoV4) -> Self {
+        AdjustmentInfo {
+            previous_balance: Amount::from_f64(info.previous_balance),
+            physical_balance: Amount::from_f64(info.physical_balance),
+            deviation: Amount::from_f64(info.deviation),
+        }
+    }
+}
+
+/// Layout of an operation up to version 1
+#[derive(Deserialize)]
+struct OperationV1 {
+    kind: OperationKind,
+    flow: OperationFlow,
+    date: NaiveDate,
+    amount: f64,
+    description: String,
+}
+impl From<OperationV1> for Operation {
+    fn from(op: OperationV1) -> Self {
+        Operation {
+            id: Uuid::new_v4(),
+            kind: op.kind,
+            flow: op.flow,
+            date: op.date,
+            amount: Amount::from_f64(op.amount),
+            description: op.description,
+            adjustment: None,
+            flagged: false,
+            category: None,
+        }
+    }
+}
+/// Layout of an operation in version 2
+#[derive(Deserialize)]
+struct OperationV2 {
+    kind: OperationKind,
+    flow: OperationFlow,
+    date: NaiveDate,
+    amount: f64,
+    description: String,
+    adjustment: Option<AdjustmentInfoV4>,
+}
+impl From<OperationV2> for Operation {
+    fn from(op: OperationV2) -> Self {
+        Operation {
+            id: Uuid::new_v4(),
+            kind: op.kind,
+            flow: op.flow,
+            date: op.date,
+            amount: Amount::from_f64(op.amount),
+            description: op.description,
+            adjustment: op.adjustment.map(AdjustmentInfo::from),
+            flagged: false,
+            category: None,
+        }
+    }
+}
+/// Layout of an operation in version 3
+#[derive(Deserialize)]
+struct OperationV3 {
+    id: Uuid,
+    kind: OperationKind,
+    flow: OperationFlow,
+    date: NaiveDate,
+    amount: f64,
+    description: String,
+    adjustment: Option<AdjustmentInfoV4>,
+}
+impl From<OperationV3> for Operation {
+    fn from(op: OperationV3) -> Self {
+        Operation {
+            id: op.id,
+            kind: op.kind,
+            flow: op.flow,
+            date: op.date,
+            amount: Amount::from_f64(op.amount),
+            description: op.description,
+            adjustment: op.adjustment.map(AdjustmentInfo::from),
+            flagged: false,
+            category: None,
+        }
+    }
+}
+/// Layout of an operation in version 4
+#[derive(Deserialize)]
+struct OperationV4 {
+    id: Uuid,
+    kind: OperationKind,
+    flow: OperationFlow,
+    date: NaiveDate,
+    amount: f64,
+    description: String,
+    adjustment: Option<AdjustmentInfoV4>,
+    flagged: bool,
+}
+impl From<OperationV4> for Operation {
+    fn from(op: OperationV4) -> Self {
+        Operation {
+            id: op.id,
+            kind: op.kind,
+            flow: op.flow,
+            date: op.date,
+            amount: Amount::from_f64(op.amount),
+            description: op.description,
+            adjustment: op.adjustment.map(AdjustmentInfo::from),
+            flagged: op.flagged,
+            category: None,
+        }
+    }
+}
+/// Layout of an operation in version 5
+#[derive(Deserialize)]
+struct OperationV5 {
+    id: Uuid,
+    kind: OperationKind,
+    flow: OperationFlow,
+    date: NaiveDate,
+    amount: Amount,
+    description: String,
+    adjustment: Option<AdjustmentInfo>,
+    flagged: bool,
+}
+impl From<OperationV5> for Operation {
+    fn from(op: OperationV5) -> Self {
+        Operation {
+            id: op.id,
+            kind: op.kind,
+            flow: op.flow,
+            date: op.date,
+            amount: op.amount,
+            description: op.description,
+            adjustment: op.adjustment,
+            flagged: op.flagged,
+            category: None,
+        }
+    }
+}
+/// Layout of the data before the versioned layout (version 0)
+#[derive(Deserialize)]
+struct CodexiV0 {
+    operations: Vec<OperationV1>,
+}
+/// Layout of the data in version 1
+#[derive(Deserialize)]
+struct CodexiV1 {
+    title: Option<String>,
+    operations: Vec<OperationV1>,
+}
+/// Layout of the data in version 2
+#[derive(Deserialize)]
+struct CodexiV2 {
+    title: Option<String>,
+    operations: Vec<OperationV2>,
+}
+/// Layout of the data in version 3
+#[derive(Deserialize)]
+struct CodexiV3 {
+    title: Option<String>,
+    operations: Vec<OperationV3>,
+}
+/// Layout of the data in version 4
+#[derive(Deserialize)]
+struct CodexiV4 {
+    title: Option<String>,
+    operations: Vec<OperationV4>,
+}
+/// Layout of the data in version 5
+#[derive(Deserialize)]
+struct CodexiV5 {
+    title: Option<String>,
+    operations: Vec<OperationV5>,
+}
+
+/// Columns of the csv export/import, in order
+pub const CSV_COLUMNS: [&str; 5] = ["kind", "flow", "date", "amount", "description"];
+
+/// Enum for the period of a split export (one file per period)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportPeriod {
+    /// codexi_YYYY-MM.<ext>
+    Month,
+    /// codexi_YYYY.<ext>
+    Year,
+}
+/// Methods for ExportPeriod
+impl ExportPeriod {
+    /// Get the period key of a date (ex: 2025-11 for a month, 2025 for a year)
+    pub fn key(&self, date: NaiveDate) -> String {
+        match self {
+            ExportPeriod::Month => date.format("%Y-%m").to_string(),
+            ExportPeriod::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+/// Number of rows between two progress logs of an import
+const IMPORT_PROGRESS_EVERY: usize = 1000;
+
+/// Struct for import summary
+/// The errors are kept with the line number of the row in the imported file.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    /// Rows already in the codexi, not imported again (skip_existing)
+    pub existing: usize,
+    pub errors: Vec<(u64, String)>,
+    /// Rows imported with a substituted value (lenient mode)
+    pub warnings: Vec<(u64, String)>,
+    /// Operations not merged (closed period, before an anchor), with the reason (see merge_import)
+    pub conflicts: Vec<String>,
+}
+/// Methods for ImportSummary
+impl ImportSummary {
+    /// Log the progress of an import every IMPORT_PROGRESS_EVERY rows
+    pub fn log_progress(&self, rows: usize) {
+        if rows > 0 && rows.is_multiple_of(IMPORT_PROGRESS_EVERY) {
+            log::debug!("Import progress: {} rows read ({} imported, {} skipped, {} failed)",
+                rows, self.imported, self.skipped, self.errors.len());
+        }
+    }
+    /// Get the summary line
+    pub fn summary(&self) -> String {
+        let mut summary = format!("imported {}, skipped {}, failed {}", self.imported, self.skipped, self.errors.len());
+        if self.existing > 0 {
+            summary.push_str(&format!(", {} already present", self.existing));
+        }
+        if !self.warnings.is_empty() {
+            summary.push_str(&format!(", {} with warnings", self.warnings.len()));
+        }
+        summary
+    }
+}
+/// Struct for the toml and json exports (same layout as the codexi, read back as a Codexi)
+#[derive(Serialize)]
+struct DataExport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    operations: &'a [Operation],
+}
+/// Struct for a csv row, the kind is written by its variant name only (ex: Init, Transaction)
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    kind: String,
+    flow: String,
+    date: NaiveDate,
+    amount: Amount,
+    description: String,
+}
+/// Struct for the size limits of a backup
+#[derive(Debug, Clone, Copy)]
+pub struct BackupLimits {
+    /// Files bigger than this size (bytes) are skipped with a warning
+    pub max_file_size: u64,
+    /// The backup is aborted if the files to include exceed this size (bytes)
+    pub max_total_size: u64,
+}
+/// Default limits: 100 MiB per file, 1 GiB in total
+impl Default for BackupLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 100 * 1024 * 1024,
+            max_total_size: 1024 * 1024 * 1024,
+        }
+    }
+}
+/// Struct for a file a restore would write (see Codexi::restore_plan)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestorePlanEntry {
+    /// Destination path in the data (or archive) directory
+    pub path: PathBuf,
+    /// The file already exists and would be overwritten
+    pub exists: bool,
+}
+/// Struct for snapshot list item
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub file_name: String,
+    pub note: Option<String>,
+}
+/// Enum for the origin of a recovered codexi (see Codexi::recover)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecoverySource {
+    /// codexi.dat is readable, there is nothing to recover
+    Data,
+    /// A snapshot file, by its name in the snapshot directory
+    Snapshot(String),
+    /// A backup ZIP file (only its codexi.dat is recovered, not the archives)
+    Backup(PathBuf),
+}
+/// Struct for the result of a recovery
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    pub codexi: Codexi,
+    pub source: RecoverySource,
+    /// The sources tried first, with the reason they were rejected
+    pub failures: Vec<(String, String)>,
+}
 /// Methods for File Management of codexi
 impl Codexi {
 
+    /// Encode the codexi in the versioned data layout (magic, version, bincode data)
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(DATA_MAGIC.len() + 2);
+        bytes.extend_from_slice(DATA_MAGIC);
+        bytes.extend_from_slice(&DATA_VERSION.to_le_bytes());
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes)
+    }
+    /// Get the version of the data layout (0 for data without header)
+    fn data_version(bytes: &[u8]) -> u16 {
+        if bytes.len() < DATA_MAGIC.len() + 2 || &bytes[..DATA_MAGIC.len()] != DATA_MAGIC {
+            return 0;
+        }
+        u16::from_le_bytes([bytes[4], bytes[5]])
+    }
+    /// Decode a codexi from the versioned data layout
+    /// Data without header (older files, archives) is read as version 0.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let header_len = DATA_MAGIC.len() + 2;
+
+        if bytes.len() < header_len || &bytes[..DATA_MAGIC.len()] != DATA_MAGIC {
+            let legacy: CodexiV0 = bincode::deserialize(bytes)
+                .map_err(|e| anyhow!("{}", e))?;
+            let operations = legacy.operations.into_iter().map(Operation::from).collect();
+            return Ok(Codexi { operations, ..Default::default() });
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let data = &bytes[header_len..];
+        match version {
+            1 => {
+                let legacy: CodexiV1 = bincode::deserialize(data)
+                    .map_err(|e| anyhow!("{}", e))?;
+                let operations = legacy.operations.into_iter().map(Operation::from).collect();
+                Ok(Codexi { title: legacy.title, operations, ..Default::default() })
+            }
+            2 => {
+                let legacy: CodexiV2 = bincode::deserialize(data)
+                    .map_err(|e| anyhow!("{}", e))?;
+                let operations = legacy.operations.into_iter().map(Operation::from).collect();
+                Ok(Codexi { title: legacy.title, operations, ..Default::default() })
+            }
+            3 => {
+                let legacy: CodexiV3 = bincode::deserialize(data)
+                    .map_err(|e| anyhow!("{}", e))?;
+                let operations = legacy.operations.into_iter().map(Operation::from).collect();
+                Ok(Codexi { title: legacy.title, operations, ..Default::default() })
+            }
+            4 => {
+                let legacy: CodexiV4 = bincode::deserialize(data)
+                    .map_err(|e| anyhow!("{}", e))?;
+                let operations = legacy.operations.into_iter().map(Operation::from).collect();
+                Ok(Codexi { title: legacy.title, operations, ..Default::default() })
+            }
+            5 => {
+                let legacy: CodexiV5 = bincode::deserialize(data)
+                    .map_err(|e| anyhow!("{}", e))?;
+                let operations = legacy.operations.into_iter().map(Operation::from).collect();
+                Ok(Codexi { title: legacy.title, operations, ..Default::default() })
+            }
+            6 => bincode::deserialize(data).map_err(|e| anyhow!("{}", e)),
+            _ => Err(anyhow!("Unsupported data version {} (this codexi supports up to {}).", version, DATA_VERSION)),
+        }
+    }
     /// Save codexi to file
     pub fn save(&self, dir: &Path) -> Result<()> {
         let file_path = dir.join("codexi.dat");
@@ -27,8 +434,8 @@ impl Codexi {
             fs::create_dir_all(parent)?;
         }
 
-        let encoded = bincode::serialize(self)?;
-        fs::write(&file_path, encoded)?;
+        let encoded = self.to_bytes()?;
+        write_atomic(&file_path, encoded)?;
 
         log::debug!("codexi: {:?} saved.", file_path);
         Ok(())
@@ -43,33 +450,111 @@ impl Codexi {
         }
 
         let bytes = fs::read(&file_path)?;
-        let codexi = bincode::deserialize(&bytes)?;
+        let codexi = Self::from_bytes(&bytes)
+            .map_err(|e| anyhow!("{} is unreadable ({}), try `codexi system recover`.", file_path.display(), e))?;
+
+        // Older data is saved back right away, so the generated ids stay stable
+        let version = Self::data_version(&bytes);
+        if version < DATA_VERSION {
+            codexi.save(dir)?;
+            log::info!("File: {:?} migrated from data version {} to {}.", file_path, version, DATA_VERSION);
+        }
 
         log::debug!("File: {:?} loaded.", file_path);
         Ok(codexi)
 
     }
+    /// Salvage a codexi when codexi.dat cannot be read, nothing is written (see replace_data).
+    /// ex: Codexi::recover(&data_dir, &[get_default_backup_dir()?]);
+    /// The sources are tried in order: codexi.dat itself, the snapshots (newest first), then the backups
+    /// named codexi_backup_<timestamp>.zip found in `backup_dirs` (newest first).
+    /// The encrypted backups are skipped, they need `system restore` and their passphrase.
+    pub fn recover(dir: &Path, backup_dirs: &[PathBuf]) -> Result<Recovery> {
+        let read = |path: &Path| fs::read(path).map_err(anyhow::Error::from).and_then(|bytes| Self::from_bytes(&bytes));
+        let mut failures = Vec::new();
+
+        let file_path = dir.join("codexi.dat");
+        match read(&file_path) {
+            Ok(codexi) => return Ok(Recovery { codexi, source: RecoverySource::Data, failures }),
+            Err(e) => failures.push((file_path.display().to_string(), e.to_string())),
+        }
+
+        let snapshot_dir = get_snapshot_dir()?;
+        for info in Self::list_snapshot()?.into_iter().rev() {
+            match read(&snapshot_dir.join(&info.file_name)) {
+                Ok(codexi) => {
+                    log::warn!("codexi recovered from the snapshot {}", info.file_name);
+                    return Ok(Recovery { codexi, source: RecoverySource::Snapshot(info.file_name), failures });
+                }
+                Err(e) => failures.push((info.file_name, e.to_string())),
+            }
+        }
+
+        let mut backups = Vec::new();
+        for backup_dir in backup_dirs.iter().filter(|d| d.is_dir()) {
+            for entry in fs::read_dir(backup_dir)? {
+                let path = entry?.path();
+                if let Some(stamp) = path.file_name().and_then(|n| parse_backup_timestamp(&n.to_string_lossy())) {
+                    backups.push((stamp, path));
+                }
+            }
+        }
+        backups.sort_by(|a, b| b.cmp(a));
+        backups.dedup_by(|a, b| a.1 == b.1);
+
+        for (_, path) in backups {
+            if crypto::is_encrypted_file(&path)? {
+                failures.push((path.display().to_string(), "encrypted, use `codexi system restore` with its passphrase".to_string()));
+                continue;
+            }
+            match Self::inspect_backup(&path, None) {
+                Ok((codexi, _)) => {
+                    log::warn!("codexi recovered from the backup {}", path.display());
+                    return Ok(Recovery { codexi, source: RecoverySource::Backup(path), failures });
+                }
+                Err(e) => failures.push((path.display().to_string(), e.to_string())),
+            }
+        }
+
+        let tried: Vec<String> = failures.iter().map(|(source, e)| format!("{}: {}", source, e)).collect();
+        Err(anyhow!("Nothing could be recovered.\n{}", tried.join("\n")))
+    }
+    /// Replace codexi.dat with a recovered codexi, the unreadable file is kept aside
+    /// as codexi.dat.corrupt_<timestamp>. Returns its path (None if there was no codexi.dat).
+    pub fn replace_data(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let file_path = dir.join("codexi.dat");
+        let corrupt_path = if file_path.exists() {
+            let path = dir.join(format!("codexi.dat.corrupt_{}", Local::now().format("%Y%m%d_%H%M%S")));
+            fs::rename(&file_path, &path)?;
+            log::warn!("Unreadable data kept aside as {}", path.display());
+            Some(path)
+        } else {
+            None
+        };
+
+        self.save(dir)?;
+        Ok(corrupt_path)
+    }
     /// Export to toml
-    pub fn export_toml(&self, dir: &Path) -> Result<()> {
-        let file_path = dir.join("codexi.toml");
+    /// With `gzip`, the file is compressed (codexi.toml.gz).
+    pub fn export_toml(&self, dir: &Path, gzip: bool) -> Result<()> {
+        let file_path = dir.join(Self::export_name("codexi.toml", gzip));
 
         if let Some(parent) = file_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let toml_str = toml::to_string_pretty(self)
-            .map_err(|e| anyhow!("{}", e))?;
-
-
-        fs::write(&file_path, toml_str)?;
+        Self::write_toml(self.title.as_deref(), &self.operations, &file_path)?;
         log::info!("Export toml saved to {:?}", file_path);
         Ok(())
     }
     /// Import from toml
+    /// A compressed codexi.toml.gz is read as well (see import_path).
     pub fn import_toml(dir: &Path) -> Result<Self> {
-        let file_path = dir.join("codexi.toml");
+        let file_path = Self::import_path(dir, "codexi.toml");
 
-        let content = fs::read_to_string(&file_path)?;
+        let content = String::from_utf8(Self::read_import(&file_path)?)
+            .map_err(|e| anyhow!("{:?} is not valid UTF-8: {}", file_path, e))?;
         let mut codexi: Codexi = toml::from_str(&content)
             .map_err(|e| anyhow!("{}", e))?;
 
@@ -77,97 +562,506 @@ impl Codexi {
         log::info!("Import toml: {:?} loaded.", file_path);
         Ok(codexi)
     }
+    /// Export to json (same layout as the toml export)
+    /// With `gzip`, the file is compressed (codexi.json.gz).
+    pub fn export_json(&self, dir: &Path, gzip: bool, style: JsonStyle) -> Result<()> {
+        let file_path = dir.join(Self::export_name("codexi.json", gzip));
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Self::write_json(self.title.as_deref(), &self.operations, &file_path, style)?;
+        log::info!("Export json saved to {:?}", file_path);
+        Ok(())
+    }
+    /// Import from json
+    /// A compressed codexi.json.gz is read as well (see import_path).
+    pub fn import_json(dir: &Path) -> Result<Self> {
+        let file_path = Self::import_path(dir, "codexi.json");
+
+        let content = String::from_utf8(Self::read_import(&file_path)?)
+            .map_err(|e| anyhow!("{:?} is not valid UTF-8: {}", file_path, e))?;
+        let mut codexi: Codexi = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("{}", e))?;
+
+        codexi.operations.sort_by_key(|o| o.date);
+        log::info!("Import json: {:?} loaded.", file_path);
+        Ok(codexi)
+    }
     /// Export to csv
-    pub fn export_csv(&self, dir: &Path) -> Result<()> {
-        let file_path = dir.join("codexi.csv");
+    /// Without `header`, only the data rows are written (to append them to an existing sheet).
+    /// With `gzip`, the file is compressed (codexi.csv.gz).
+    pub fn export_csv(&self, dir: &Path, header: bool, gzip: bool) -> Result<()> {
+        let file_path = dir.join(Self::export_name("codexi.csv", gzip));
 
         if let Some(parent) = file_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let file = fs::File::create(&file_path)?;
-        let mut wtr = csv::Writer::from_writer(file);
+        Self::write_csv(&self.operations, &file_path, header)?;
+        log::info!("Export csv saved to {:?}", file_path);
+        Ok(())
+    }
+    /// Export the operations to one file per period (month or year) in `dir`, named codexi_<period>.<ext>
+    /// ex: codexi.export_split(Path::new("."), ExportPeriod::Month, "csv", true, false, JsonStyle::Pretty);
+    /// The extension (csv, toml or json) gives the format, `header` only applies to csv and `style` to json.
+    /// With `gzip`, the files are compressed (codexi_<period>.<ext>.gz).
+    /// Periods without operation get no file. Returns the files written, oldest period first.
+    pub fn export_split(&self, dir: &Path, period: ExportPeriod, extension: &str, header: bool, gzip: bool, style: JsonStyle) -> Result<Vec<PathBuf>> {
+        if !["csv", "toml", "json"].contains(&extension) {
+            return Err(anyhow!("Unsupported export format '{}': use csv, toml or json", extension));
+        }
+        fs::create_dir_all(dir)?;
 
+        let mut periods: BTreeMap<String, Vec<Operation>> = BTreeMap::new();
         for op in &self.operations {
-            wtr.serialize(op)
-                .map_err(|e| anyhow!("{}", e))?;
+            periods.entry(period.key(op.date)).or_default().push(op.clone());
         }
 
-        wtr.flush()?;
-        log::info!("Export csv saved to {:?}", file_path);
+        let mut files = Vec::new();
+        for (key, operations) in periods {
+            let file_path = dir.join(Self::export_name(&format!("codexi_{}.{}", key, extension), gzip));
+            match extension {
+                "csv" => Self::write_csv(&operations, &file_path, header)?,
+                "toml" => Self::write_toml(self.title.as_deref(), &operations, &file_path)?,
+                _ => Self::write_json(self.title.as_deref(), &operations, &file_path, style)?,
+            }
+            files.push(file_path);
+        }
+
+        log::info!("Export of {} operations split into {} files in {:?}", self.operations.len(), files.len(), dir);
+        Ok(files)
+    }
+    /// Export a slice of operations to a file, the format (csv, toml, json) is given by the extension.
+    /// ex: Codexi::export_operations(&ops, Path::new("november.csv"), JsonStyle::Pretty);
+    /// The toml and json files have the same layout as codexi.toml and codexi.json, so they can be imported back.
+    /// A .gz extension compresses the file (ex: november.csv.gz).
+    pub fn export_operations(operations: &[Operation], file_path: &Path, style: JsonStyle) -> Result<()> {
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let format_path = if Self::is_gzip(file_path) { file_path.with_extension("") } else { file_path.to_path_buf() };
+        let extension = format_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("csv") => Self::write_csv(operations, file_path, true)?,
+            Some("toml") => Self::write_toml(None, operations, file_path)?,
+            Some("json") => Self::write_json(None, operations, file_path, style)?,
+            _ => return Err(anyhow!("Unsupported export file {:?}: use a .csv, .toml or .json extension", file_path)),
+        }
+
+        log::info!("Export of {} operations saved to {:?}", operations.len(), file_path);
         Ok(())
     }
+    /// Write operations as csv
+    fn write_csv(operations: &[Operation], file_path: &Path, header: bool) -> Result<()> {
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(header)
+            .from_writer(Vec::new());
+
+        // The csv keeps the main fields only (the adjustment details are in the codexi data and toml)
+        // The fields are the CSV_COLUMNS
+        #[derive(Serialize)]
+        struct CsvOut<'a> {
+            kind: &'a OperationKind,
+            flow: OperationFlow,
+            date: NaiveDate,
+            amount: Amount,
+            description: &'a str,
+        }
+
+        for op in operations {
+            wtr.serialize(CsvOut {
+                kind: &op.kind,
+                flow: op.flow,
+                date: op.date,
+                amount: op.amount,
+                description: &op.description,
+            })
+            .map_err(|e| anyhow!("{}", e))?;
+        }
+
+        let content = wtr.into_inner().map_err(|e| anyhow!("{}", e))?;
+        write_atomic(file_path, Self::encode_export(file_path, content)?)
+    }
+    /// Write operations as toml (same layout as the codexi)
+    fn write_toml(title: Option<&str>, operations: &[Operation], file_path: &Path) -> Result<()> {
+        let toml_str = toml::to_string_pretty(&DataExport { title, operations })
+            .map_err(|e| anyhow!("{}", e))?;
+
+        write_atomic(file_path, Self::encode_export(file_path, toml_str.into_bytes())?)
+    }
+    /// Write operations as json (same layout as the codexi)
+    fn write_json(title: Option<&str>, operations: &[Operation], file_path: &Path, style: JsonStyle) -> Result<()> {
+        let json_str = style.format(&DataExport { title, operations })?;
+
+        write_atomic(file_path, Self::encode_export(file_path, json_str.into_bytes())?)
+    }
+    /// Check if a file is gzip compressed, from its .gz extension
+    fn is_gzip(file_path: &Path) -> bool {
+        file_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+    }
+    /// Get the name of an export file, with the .gz extension when compressed
+    fn export_name(name: &str, gzip: bool) -> String {
+        if gzip { format!("{}.gz", name) } else { name.to_string() }
+    }
+    /// Compress the content of an export file when its extension is .gz
+    fn encode_export(file_path: &Path, content: Vec<u8>) -> Result<Vec<u8>> {
+        if !Self::is_gzip(file_path) {
+            return Ok(content);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content)?;
+        Ok(encoder.finish()?)
+    }
+    /// Read an import file, a .gz file is decompressed
+    fn read_import(file_path: &Path) -> Result<Vec<u8>> {
+        let bytes = fs::read(file_path)
+            .map_err(|e| anyhow!("Cannot read {:?}: {}", file_path, e))?;
+        if !Self::is_gzip(file_path) {
+            return Ok(bytes);
+        }
+        let mut content = Vec::new();
+        GzDecoder::new(&bytes[..]).read_to_end(&mut content)
+            .map_err(|e| anyhow!("Cannot decompress {:?}: {}", file_path, e))?;
+        Ok(content)
+    }
+    /// Get the file to import in `dir`: the plain file (ex: codexi.csv) or its compressed form (codexi.csv.gz).
+    /// When both exist, the most recently modified one is taken.
+    fn import_path(dir: &Path, name: &str) -> PathBuf {
+        let plain = dir.join(name);
+        let gzip = dir.join(format!("{}.gz", name));
+        let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+
+        match (modified(&plain), modified(&gzip)) {
+            (Some(plain_time), Some(gzip_time)) => {
+                log::warn!("Both {:?} and {:?} exist, the most recent one is imported.", plain, gzip);
+                if gzip_time > plain_time { gzip } else { plain }
+            }
+            (None, Some(_)) => gzip,
+            _ => plain,
+        }
+    }
+    /// Merge the operations of an import into the codexi instead of replacing it
+    /// ex: codexi.merge_import(Codexi::import_toml(dir)?, true);
+    /// An operation on or before the latest close, or before the latest init/adjust anchor, is skipped
+    /// and reported in the summary. The system anchors of the file are only merged into an empty codexi,
+    /// an anchor the codexi already has is counted as present.
+    /// With `dedup`, an operation whose canonical key (see Operation::dedup_key) is already in the codexi
+    /// is counted as present too, each operation of the codexi matching one imported operation only
+    /// (like import-bank --skip-existing).
+    /// The merged codexi must stay valid (see validate), otherwise it is left unchanged.
+    pub fn merge_import(&mut self, incoming: Codexi, dedup: bool) -> Result<ImportSummary> {
+        let mut existing: HashMap<String, usize> = HashMap::new();
+        for op in &self.operations {
+            *existing.entry(op.dedup_key()).or_default() += 1;
+        }
+        let ids: HashSet<Uuid> = self.operations.iter().map(|op| op.id).collect();
+        let empty = self.operations.is_empty();
+
+        let mut summary = ImportSummary::default();
+        let mut merged = Vec::new();
+        for mut op in incoming.operations {
+            if (dedup || op.kind.is_system())
+                && let Some(count) = existing.get_mut(&op.dedup_key())
+                && *count > 0
+            {
+                *count -= 1;
+                summary.existing += 1;
+                continue;
+            }
+
+            let conflict = if empty {
+                None
+            } else if op.kind.is_system() {
+                Some("a system anchor is only merged into an empty codexi".to_string())
+            } else {
+                self.check_new_date(op.date).err().map(|e| match e.downcast_ref::<CodexiError>() {
+                    Some(CodexiError::ClosedPeriod { close_date, .. }) => format!("in the period closed on {}", close_date),
+                    Some(CodexiError::BeforeAnchor { anchor_date, .. }) => format!("before the anchor of {}", anchor_date),
+                    _ => e.to_string(),
+                })
+            };
+            if let Some(reason) = conflict {
+                log::warn!("Import merge: [{}] skipped, {}.", op.to_csv_row(), reason);
+                summary.conflicts.push(format!("[{}] {}", op.to_csv_row(), reason));
+                summary.skipped += 1;
+                continue;
+            }
+
+            // Same id, different content (ex: edited since the export): a new operation
+            if ids.contains(&op.id) {
+                op.id = Uuid::new_v4();
+            }
+            merged.push(op);
+            summary.imported += 1;
+        }
+
+        let backup = self.operations.clone();
+        self.operations.extend(merged);
+        self.reset_totals();
+        // Stable: on a same day, the imported operations come after the existing ones
+        self.operations.sort_by_key(|op| op.date);
+
+        let problems = self.validate();
+        if let Some(problem) = problems.first() {
+            self.operations = backup;
+            return Err(anyhow!("The merged codexi is invalid ({} problem(s)), nothing was imported: {}", problems.len(), problem));
+        }
+
+        log::info!("Import merged: {}", summary.summary());
+        Ok(summary)
+    }
     /// Import from csv
-    pub fn import_csv(dir: &Path) -> Result<Self> {
-        let file_path = dir.join("codexi.csv");
+    /// The invalid rows do not abort the import, they are returned in the ImportSummary
+    /// with their line number. Blank rows are skipped.
+    /// With `lenient`, an unknown kind is imported as a Transaction and an unknown flow is inferred
+    /// from the sign of the amount, the substitutions are returned as warnings.
+    /// A compressed codexi.csv.gz is read as well (see import_path).
+    pub fn import_csv(dir: &Path, lenient: bool) -> Result<(Self, ImportSummary)> {
+        let file_path = Self::import_path(dir, "codexi.csv");
 
-        let file = fs::File::open(&file_path)?;
-        let mut rdr = csv::Reader::from_reader(file);
+        let content = Self::read_import(&file_path)?;
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(&content[..]);
+        let headers = rdr.headers()?.clone();
 
         let mut operations = Vec::new();
+        let mut summary = ImportSummary::default();
 
-        for result in rdr.deserialize::<Operation>() {
-            let op: Operation = result
-                .map_err(|e| anyhow!("{}", e))?;
-            operations.push(op);
+        for (rows, result) in rdr.records().enumerate() {
+            summary.log_progress(rows);
+
+            let record = result.map_err(|e| anyhow!("{}", e))?;
+            let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+            if record.iter().all(|field| field.trim().is_empty()) {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let row = match record.deserialize::<CsvRow>(Some(&headers)) {
+                Ok(row) => row,
+                Err(e) => {
+                    summary.errors.push((line, e.to_string()));
+                    continue;
+                }
+            };
+
+            let kind = match OperationKind::try_from_str(&row.kind) {
+                Ok(kind) => kind,
+                Err(e) if lenient => {
+                    summary.warnings.push((line, format!("{}, imported as Transaction", e)));
+                    OperationKind::Regular(RegularKind::Transaction)
+                }
+                Err(e) => {
+                    summary.errors.push((line, e.to_string()));
+                    continue;
+                }
+            };
+
+            let (flow, amount) = match OperationFlow::try_from_str(&row.flow) {
+                Ok(flow) => (flow, row.amount),
+                Err(e) if lenient && !row.amount.is_zero() => {
+                    let flow = OperationFlow::from_sign(row.amount.cents());
+                    summary.warnings.push((line, format!("{}, {} inferred from the amount", e, flow.as_str())));
+                    (flow, row.amount.abs())
+                }
+                Err(e) => {
+                    summary.errors.push((line, e.to_string()));
+                    continue;
+                }
+            };
+
+            operations.push(Operation {
+                id: Uuid::new_v4(),
+                kind,
+                flow,
+                date: row.date,
+                amount,
+                description: row.description,
+                adjustment: None,
+                flagged: false,
+                category: None,
+            });
+            summary.imported += 1;
         }
         operations.sort_by_key(|o| o.date);
-        log::info!("Import csv: {:?} loaded", file_path);
-        Ok(Codexi { operations })
+        log::info!("Import csv: {:?} loaded, {}", file_path, summary.summary());
+        Ok((Codexi { operations, ..Default::default() }, summary))
     }
     /// List snapshot files
-    pub fn list_snapshot() -> Result<Vec<String>> {
+    /// The note of a snapshot is read from its sidecar .txt file, if any.
+    pub fn list_snapshot() -> Result<Vec<SnapshotInfo>> {
 
-        let data_dir = get_data_dir()?;
-        let snapshot_dir = data_dir.join("snapshots");
+        let snapshot_dir = get_snapshot_dir()?;
         let mut files = Vec::new();
 
         if snapshot_dir.exists() {
-            for entry in fs::read_dir(snapshot_dir)? {
+            for entry in fs::read_dir(&snapshot_dir)? {
                 let entry = entry?;
                 let file_name = entry.file_name().to_string_lossy().to_string();
 
                 if file_name.starts_with("codexi_") && file_name.ends_with(".snp") {
-                    files.push(file_name);
+                    let note = fs::read_to_string(snapshot_dir.join(&file_name).with_extension("txt"))
+                        .ok()
+                        .map(|n| n.trim().to_string());
+                    files.push(SnapshotInfo { file_name, note });
                 }
             }
         }
-        files.sort();
+        files.sort_by_key(|info| snapshot_order(&info.file_name));
+        Ok(files)
+    }
+    /// List the snapshot files beyond the newest `keep`, with their size in bytes
+    /// The names encode the timestamp (codexi_YYYYMMDD_HHMMSS[_N].snp), see snapshot_order.
+    pub fn snapshots_beyond(keep: usize) -> Result<Vec<(String, u64)>> {
+        let snapshot_dir = get_snapshot_dir()?;
+        let mut files = Vec::new();
+
+        // Newest first
+        for info in Self::list_snapshot()?.into_iter().rev().skip(keep) {
+            let size = fs::metadata(snapshot_dir.join(&info.file_name))?.len();
+            files.push((info.file_name, size));
+        }
         Ok(files)
     }
+    /// Delete the snapshot files beyond the newest `keep`, with their note (see snapshots_beyond)
+    /// Returns the number of deleted files and the freed bytes.
+    pub fn prune_snapshots(keep: usize) -> Result<(usize, u64)> {
+        let snapshot_dir = get_snapshot_dir()?;
+        let files = Self::snapshots_beyond(keep)?;
+        let mut freed = 0;
+
+        for (file_name, size) in &files {
+            let file_path = snapshot_dir.join(file_name);
+            fs::remove_file(&file_path)?;
+            let _ = fs::remove_file(file_path.with_extension("txt"));
+            let _ = fs::remove_file(file_path.with_extension("cld"));
+            freed += size;
+            log::info!("Snapshot {} deleted", file_name);
+        }
+
+        log::warn!("{} snapshots deleted, {} kept ({} bytes freed).", files.len(), keep, freed);
+        Ok((files.len(), freed))
+    }
     /// Restore a snapshot file
     /// The filename is just the file name, not the full path
     pub fn restore_snapshot(filename: &str) -> Result<Self> {
-        let data_dir = get_data_dir()?;
-        let file_path = data_dir.join("snapshots").join(filename);
+        let file_path = get_snapshot_dir()?.join(filename);
 
         let data = fs::read(&file_path)?;
-        let codexi: Codexi = bincode::deserialize(&data)
-            .map_err(|e| anyhow!("{}", e))?;
+        let codexi = Self::from_bytes(&data)?;
 
         log::info!("Snapshot {} restored", file_path.display());
 
         Ok(codexi)
     }
 
+    /// Revert the last change: restore the newest snapshot taken automatically before a change
+    /// (its note starts with "before ") and save it as codexi.dat.
+    /// ex: let restored = Codexi::undo(&data_dir)?;
+    /// The restored snapshot is removed, so the next undo goes one change further back. A snapshot
+    /// identical to the current data (the command after it failed) is removed and skipped.
+    /// The snapshots taken by hand are never used nor removed.
+    /// Returns the restored codexi and its snapshot.
+    pub fn undo(dir: &Path) -> Result<(Self, SnapshotInfo)> {
+        let current = Self::load(dir)?.to_bytes()?;
+        let snapshot_dir = get_snapshot_dir()?;
+
+        let candidates = Self::list_snapshot()?.into_iter()
+            .rev()
+            .filter(|info| info.note.as_deref().is_some_and(|n| n.starts_with("before ")));
+
+        for info in candidates {
+            let file_path = snapshot_dir.join(&info.file_name);
+            let bytes = fs::read(&file_path)?;
+            let codexi = Self::from_bytes(&bytes)?;
+            let unchanged = codexi.to_bytes()? == current;
+
+            // The snapshot is removed once the restored data is saved, a failed save keeps it
+            let archive_path = file_path.with_extension("cld");
+            if !unchanged {
+                codexi.save(dir)?;
+                if archive_path.exists() {
+                    codexi.restore_reopened_archive(&archive_path)?;
+                }
+            }
+            fs::remove_file(&file_path)?;
+            let _ = fs::remove_file(file_path.with_extension("txt"));
+            let _ = fs::remove_file(&archive_path);
+            if unchanged {
+                log::debug!("Snapshot {} skipped, identical to the current data", info.file_name);
+                continue;
+            }
+
+            log::warn!("Undo: snapshot {} ({}) restored", info.file_name, info.note.as_deref().unwrap_or(""));
+            return Ok((codexi, info));
+        }
+
+        Err(anyhow!("Nothing to undo: no snapshot was taken before a change."))
+    }
     /// Create a snapshot of the current codexi state
-    pub fn snapshot(&self) -> Result<()> {
+    /// The optional note is stored in a sidecar .txt file next to the snapshot.
+    /// Returns the path of the created snapshot file.
+    pub fn snapshot(&self, note: Option<&str>) -> Result<PathBuf> {
 
         let file_path = get_snapshot_path()?;
-        let data = bincode::serialize(self)
-            .map_err(|e| anyhow!("{}", e))?;
+        let data = self.to_bytes()?;
 
-        fs::write(&file_path, data)?;
+        write_atomic(&file_path, data)?;
+
+        if let Some(note) = note {
+            fs::write(file_path.with_extension("txt"), note)?;
+        }
 
         log::info!("snapshot done to {:?}", file_path);
-        Ok(())
+        Ok(file_path)
+    }
+    /// Move the archive of a reopened period next to the "before reopen" snapshot
+    /// (same name, .cld extension), so that undo can put it back.
+    /// Returns the new path of the archive.
+    pub fn keep_reopened_archive(archive_path: &Path, snapshot_path: &Path) -> Result<PathBuf> {
+        let kept_path = snapshot_path.with_extension("cld");
+        move_file(archive_path, &kept_path)?;
+
+        log::info!("Archive {} kept as {}", archive_path.display(), kept_path.display());
+        Ok(kept_path)
+    }
+    /// Put back the archive kept by keep_reopened_archive, under the name of the latest close
+    /// of the restored codexi.
+    fn restore_reopened_archive(&self, kept_path: &Path) -> Result<PathBuf> {
+        let close_date = self.operations.iter()
+            .filter(|op| op.kind == OperationKind::System(SystemKind::Close))
+            .map(|op| op.date)
+            .max()
+            .ok_or_else(|| anyhow!("No Close anchor in the restored codexi for the archive {}.", kept_path.display()))?;
+
+        let archive_path = get_archive_path(&close_date.format("%Y-%m-%d").to_string())?;
+        move_file(kept_path, &archive_path)?;
+
+        log::warn!("Archive {} restored", archive_path.display());
+        Ok(archive_path)
     }
     /// Creates a complete ZIP backup of the application's data directory.
     /// The `target_path` is the FULL path where the ZIP file should be written.
-    /// It includes all files except internal snapshots.
-    pub fn backup(target_path: &Path) -> Result<()> {
+    /// It includes all files except internal snapshots. An archive directory outside of the data
+    /// directory (see get_archive_dir) is added under archives/.
+    /// Symbolic links are never followed nor included, files over the size cap are skipped
+    /// and the backup is aborted if the total size exceeds the limit.
+    pub fn backup(target_path: &Path, limits: &BackupLimits) -> Result<()> {
         let data_dir = get_data_dir()?;
         let internal_snapshot_dir = data_dir.join("snapshots");
+        let snapshot_dir = get_snapshot_dir()?;
+        let archive_dir = get_archive_dir()?;
+        // The backup of the default account leaves out the other accounts (accounts/)
+        let accounts_dir = get_accounts_dir()?;
 
         // The data directory SHALL exist and contain at least the codexi.dat file
         if !data_dir.exists() {
@@ -183,33 +1077,65 @@ impl Codexi {
             .compression_method(zip::CompressionMethod::Deflated)
             .unix_permissions(0o755); // Standard Unix permissions if necessary
 
+        let mut total_size: u64 = 0;
+
+        // Directories to walk, with their path in the ZIP
+        let mut roots = vec![(data_dir.clone(), PathBuf::new())];
+        if !archive_dir.starts_with(&data_dir) {
+            roots.push((archive_dir, PathBuf::from("archives")));
+        }
+
         // 3. Iterate the data directory (including codexi.dat and archives/, exclude snapshot)
-        for entry in WalkDir::new(&data_dir).into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
+        for (root, prefix) in &roots {
+            for entry in WalkDir::new(root).follow_links(false).into_iter().filter_map(|e| e.ok()) {
+                let path = entry.path();
 
-            if path.starts_with(&internal_snapshot_dir) && path != internal_snapshot_dir {
-                continue;
-            }
+                if (path.starts_with(&internal_snapshot_dir) && path != internal_snapshot_dir)
+                    || (path.starts_with(&snapshot_dir) && path != snapshot_dir)
+                    || path.starts_with(&accounts_dir)
+                {
+                    continue;
+                }
+
+                if entry.path_is_symlink() {
+                    log::warn!("Backup: symbolic link {} skipped.", path.display());
+                    continue;
+                }
 
-            // Paths in the ZIP to be relative to the data_dir, not absolute.
-            let name_in_zip = path.strip_prefix(&data_dir)
-                .map_err(|_| anyhow!("Failure to calculate relative path for archive."))?
-                .to_path_buf();
+                if entry.file_type().is_file() {
+                    let size = entry.metadata()?.len();
+                    if size > limits.max_file_size {
+                        log::warn!("Backup: {} skipped, {} bytes is over the {} bytes limit.", path.display(), size, limits.max_file_size);
+                        continue;
+                    }
+
+                    total_size += size;
+                    if total_size > limits.max_total_size {
+                        drop(zip);
+                        let _ = fs::remove_file(target_path);
+                        return Err(anyhow!("Backup aborted: the data exceeds the {} bytes limit.", limits.max_total_size));
+                    }
+                }
 
-            if path.is_file() {
-                // Add teh ZIP file
-                let name_in_zip_str = name_in_zip.to_str().ok_or_else(|| anyhow!("Path invalid (non-UTF8)."))?;
+                // Paths in the ZIP to be relative to the data_dir, not absolute.
+                let name_in_zip = prefix.join(path.strip_prefix(root)
+                    .map_err(|_| anyhow!("Failure to calculate relative path for archive."))?);
 
-                // Avoid adding temporary or locked files if present (non-standard)
-                if name_in_zip_str.contains(".temp") { continue; }
+                if entry.file_type().is_file() {
+                    // Add teh ZIP file
+                    let name_in_zip_str = name_in_zip.to_str().ok_or_else(|| anyhow!("Path invalid (non-UTF8)."))?;
 
-                zip.start_file(name_in_zip_str, options)?;
-                io::copy(&mut File::open(path)?, &mut zip)?;
+                    // Avoid adding temporary or locked files if present (non-standard)
+                    if name_in_zip_str.contains(".temp") { continue; }
 
-            } else if path.is_dir() && name_in_zip.as_os_str().len() != 0 {
-                // Add the directory (only if it is not the root directory itself)
-                let name_in_zip_str = name_in_zip.to_str().ok_or_else(|| anyhow!("Path invalid (non-UTF8)."))?;
-                zip.add_directory(name_in_zip_str, options)?;
+                    zip.start_file(name_in_zip_str, options)?;
+                    io::copy(&mut File::open(path)?, &mut zip)?;
+
+                } else if entry.file_type().is_dir() && !name_in_zip.as_os_str().is_empty() {
+                    // Add the directory (only if it is not the root directory itself)
+                    let name_in_zip_str = name_in_zip.to_str().ok_or_else(|| anyhow!("Path invalid (non-UTF8)."))?;
+                    zip.add_directory(name_in_zip_str, options)?;
+                }
             }
         }
 
@@ -217,25 +1143,129 @@ impl Codexi {
         log::info!("Full backup successful to: {}", target_path.display());
         Ok(())
     }
+    /// Creates a complete ZIP backup (see backup) encrypted with a passphrase.
+    /// ex: Codexi::backup_encrypted(Path::new("codexi_backup.zip.enc"), &BackupLimits::default(), "secret");
+    /// The ZIP is built in a temporary directory, only the encrypted file is written to `target_path`.
+    pub fn backup_encrypted(target_path: &Path, limits: &BackupLimits, passphrase: &str) -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let zip_path = temp_dir.path().join("backup.zip");
+        Self::backup(&zip_path, limits)?;
+
+        let bytes = crypto::encrypt(&fs::read(&zip_path)?, passphrase)?;
+        write_atomic(target_path, bytes)?;
+        log::info!("Encrypted backup successful to: {}", target_path.display());
+        Ok(())
+    }
+    /// Open a backup file, an encrypted backup is decrypted to an anonymous temporary file.
+    /// A wrong passphrase fails before anything is read from the backup.
+    fn open_backup(zip_path: &Path, passphrase: Option<&str>) -> Result<ZipArchive<File>> {
+        if !crypto::is_encrypted_file(zip_path)? {
+            return Ok(ZipArchive::new(File::open(zip_path)?)?);
+        }
+
+        let passphrase = passphrase
+            .ok_or_else(|| anyhow!("The backup {} is encrypted, a passphrase is required.", zip_path.display()))?;
+        let bytes = crypto::decrypt(&fs::read(zip_path)?, passphrase)?;
+
+        let mut file = tempfile::tempfile()?;
+        file.write_all(&bytes)?;
+        file.rewind()?;
+        Ok(ZipArchive::new(file)?)
+    }
     /// Restores the contents of a full ZIP backup to the application's data directory.
     /// The `zip_path` is the FULL path to the backup ZIP file.
-    /// Existing files in the data directory will be overwritten.
-    pub fn restore(zip_path: &Path) -> Result<()> {
+    /// Existing files in the data directory will be overwritten, so a safety backup of the
+    /// current data is taken first. If the extraction fails, the safety backup is restored.
+    /// An encrypted backup (.zip.enc) needs its passphrase.
+    /// Returns the path of the safety backup (None if there was no data to protect).
+    pub fn restore(zip_path: &Path, passphrase: Option<&str>) -> Result<Option<PathBuf>> {
 
         let data_dir = get_data_dir()?;
-        let file = File::open(zip_path)?;
 
         // Attempting to create the ZIP archive
-        let mut archive = ZipArchive::new(file)?;
+        let mut archive = Self::open_backup(zip_path, passphrase)?;
+
+        // Safety backup of the current data
+        let safety_path = if data_dir.join("codexi.dat").exists() {
+            let path = get_safety_backup_path()?;
+            Self::backup(&path, &BackupLimits::default())?;
+            log::info!("Safety backup of the current data written to {}", path.display());
+            Some(path)
+        } else {
+            None
+        };
 
         log::warn!("Restoration in progress. Existing files in {} will be overwritten.", data_dir.display());
 
+        let archive_dir = get_archive_dir()?;
+        if let Err(e) = Self::extract_zip(&mut archive, &data_dir, &archive_dir) {
+            let Some(path) = safety_path else {
+                return Err(e);
+            };
+
+            log::error!("Restore failed: {}. Rolling back to the safety backup {}", e, path.display());
+            Self::clear_restored_dirs(&data_dir, &archive_dir)?;
+            let mut safety = ZipArchive::new(File::open(&path)?)?;
+            Self::extract_zip(&mut safety, &data_dir, &archive_dir)?;
+
+            return Err(anyhow!("Restore failed ({}), the previous data was restored from {}.", e, path.display()));
+        }
+
+        log::info!("Complete restore successful. The codexi has been reloaded from the backup.");
+        Ok(safety_path)
+    }
+    /// List the files a restore of the backup would write, without writing anything
+    /// ex: Codexi::restore_plan(Path::new("backup.zip"), None);
+    /// An encrypted backup (.zip.enc) needs its passphrase.
+    pub fn restore_plan(zip_path: &Path, passphrase: Option<&str>) -> Result<Vec<RestorePlanEntry>> {
+        let mut archive = Self::open_backup(zip_path, passphrase)?;
+        let (data_dir, archive_dir) = (get_data_dir()?, get_archive_dir()?);
+        let mut plan = Vec::new();
+
+        for i in 0..archive.len() {
+            let file = archive.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let path = Self::zip_entry_path(&file.mangled_name(), &data_dir, &archive_dir);
+            plan.push(RestorePlanEntry { exists: path.exists(), path });
+        }
+        Ok(plan)
+    }
+    /// Inspect a backup ZIP file without touching the live data
+    /// ex: Codexi::inspect_backup(Path::new("backup.zip"), None);
+    /// The backup is extracted to a temporary directory (removed afterwards) and its codexi.dat loaded.
+    /// An encrypted backup (.zip.enc) needs its passphrase.
+    /// Returns the codexi of the backup and the number of archive files it contains.
+    pub fn inspect_backup(zip_path: &Path, passphrase: Option<&str>) -> Result<(Self, usize)> {
+        let mut archive = Self::open_backup(zip_path, passphrase)?;
+        let temp_dir = tempfile::tempdir()?;
+
+        Self::extract_zip(&mut archive, temp_dir.path(), &temp_dir.path().join("archives"))?;
+        if !temp_dir.path().join("codexi.dat").exists() {
+            return Err(anyhow!("No codexi.dat found in the backup {}", zip_path.display()));
+        }
+
+        let codexi = Self::load(temp_dir.path())?;
+        let archives = match fs::read_dir(temp_dir.path().join("archives")) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_string_lossy().ends_with(".cld"))
+                .count(),
+            Err(_) => 0,
+        };
+
+        log::info!("Backup {} inspected: {} operations, {} archive(s).", zip_path.display(), codexi.operations.len(), archives);
+        Ok((codexi, archives))
+    }
+    /// Extract all the files of a ZIP archive to the data directory, the archives/ entries go to archive_dir
+    fn extract_zip(archive: &mut ZipArchive<File>, data_dir: &Path, archive_dir: &Path) -> Result<()> {
+
         // Iterate over all files in the archive
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
 
-            // The destination path is data_dir + the path to the file in the ZIP archive
-            let outpath = data_dir.join(file.mangled_name());
+            let outpath = Self::zip_entry_path(&file.mangled_name(), data_dir, archive_dir);
 
             if file.is_dir() {
                 // Create the directories (e.g., 'archives/')
@@ -243,10 +1273,10 @@ impl Codexi {
             } else if file.is_file() {
 
                 // Ensure that the parent directory exists (in the case of files in 'archives/')
-                if let Some(p) = outpath.parent() {
-                    if !p.exists() {
-                        fs::create_dir_all(p)?;
-                    }
+                if let Some(p) = outpath.parent()
+                    && !p.exists()
+                {
+                    fs::create_dir_all(p)?;
                 }
 
                 // Write the contents of the file
@@ -256,15 +1286,45 @@ impl Codexi {
                 log::debug!("Restore : {}", outpath.file_name().unwrap_or_default().to_string_lossy());
             }
         }
+        Ok(())
+    }
+    /// The destination path of a ZIP entry: data_dir + the path in the archive, the archives/ entries go to archive_dir
+    fn zip_entry_path(name: &Path, data_dir: &Path, archive_dir: &Path) -> PathBuf {
+        match name.strip_prefix("archives") {
+            Ok(rest) => archive_dir.join(rest),
+            Err(_) => data_dir.join(name),
+        }
+    }
+    /// Remove what a restore writes: the content of the data directory and of the archive directory
+    /// (when overridden outside of it), except the snapshots (which hold the safety backups) and the other accounts
+    fn clear_restored_dirs(data_dir: &Path, archive_dir: &Path) -> Result<()> {
+        let accounts_dir = get_accounts_dir()?;
+        let snapshot_dir = get_snapshot_dir()?;
+        let mut roots = vec![data_dir];
+        if !archive_dir.starts_with(data_dir) && archive_dir.exists() {
+            roots.push(archive_dir);
+        }
 
-        log::info!("Complete restore successful. The codexi has been reloaded from the backup.");
+        for root in roots {
+            for entry in fs::read_dir(root)? {
+                let path = entry?.path();
+                // A directory holding the snapshots (the overridden one too) is kept as a whole
+                if path == data_dir.join("snapshots") || snapshot_dir.starts_with(&path) || path == accounts_dir {
+                    continue;
+                }
+                if path.is_dir() {
+                    fs::remove_dir_all(&path)?;
+                } else {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
         Ok(())
     }
     /// List archive files
-    /// The archive files are stored in the "archives" subdirectory of the data directory.
+    /// The archive files are stored in the archive directory (see get_archive_dir).
     pub fn list_archives() -> Result<Vec<String>> {
-        let data_dir = get_data_dir()?;
-        let archive_dir = data_dir.join("archives");
+        let archive_dir = get_archive_dir()?;
         let mut files = Vec::new();
 
         if archive_dir.exists() {
@@ -280,14 +1340,1005 @@ impl Codexi {
         files.sort();
         Ok(files)
     }
+    /// List the archive files closed before a date, with their size in bytes
+    /// Only the files named exactly codexi_YYYY-MM-DD.cld in the archives directory are considered,
+    /// the date is read from the file name.
+    pub fn archives_before(before: &str) -> Result<Vec<(String, u64)>> {
+        let cutoff = NaiveDate::parse_from_str(before, "%Y-%m-%d")?;
+        let archive_dir = get_archive_dir()?;
+        let mut files = Vec::new();
+
+        if archive_dir.exists() {
+            for entry in fs::read_dir(&archive_dir)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_file() {
+                    continue;
+                }
+
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let close_date = file_name
+                    .strip_prefix("codexi_")
+                    .and_then(|rest| rest.strip_suffix(".cld"))
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+                if let Some(date) = close_date
+                    && date < cutoff
+                {
+                    files.push((file_name, entry.metadata()?.len()));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+    /// Delete the archive files closed before a date (see archives_before)
+    /// Returns the number of deleted files and the freed bytes.
+    pub fn prune_archives(before: &str) -> Result<(usize, u64)> {
+        let archive_dir = get_archive_dir()?;
+        let files = Self::archives_before(before)?;
+        let mut freed = 0;
+
+        for (file_name, size) in &files {
+            fs::remove_file(archive_dir.join(file_name))?;
+            freed += size;
+            log::info!("Archive {} deleted", file_name);
+        }
+
+        log::warn!("{} archives closed before {} deleted ({} bytes freed).", files.len(), before, freed);
+        Ok((files.len(), freed))
+    }
+    /// List the backup files of a directory beyond the newest `keep`, with their size in bytes
+    /// Only the default backup names (codexi_backup_YYYYMMDD_HHMMSS.zip or .zip.enc) are considered,
+    /// they are ordered by the timestamp of their name, not by the file dates.
+    pub fn backups_beyond(dir: &Path, keep: usize) -> Result<Vec<(String, u64)>> {
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Some(stamp) = parse_backup_timestamp(&file_name) {
+                files.push((stamp, file_name, entry.metadata()?.len()));
+            }
+        }
+        // Newest first
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+
+        Ok(files.into_iter().skip(keep).map(|(_, name, size)| (name, size)).collect())
+    }
+    /// Delete the backup files of a directory beyond the newest `keep` (see backups_beyond)
+    /// Returns the number of deleted files and the freed bytes.
+    pub fn prune_backups(dir: &Path, keep: usize) -> Result<(usize, u64)> {
+        let files = Self::backups_beyond(dir, keep)?;
+        let mut freed = 0;
+
+        for (file_name, size) in &files {
+            fs::remove_file(dir.join(file_name))?;
+            freed += size;
+            log::info!("Backup {} deleted", file_name);
+        }
+
+        log::warn!("{} backups deleted in {}, {} kept ({} bytes freed).", files.len(), dir.display(), keep, freed);
+        Ok((files.len(), freed))
+    }
     /// Load an archive file (view only)
     pub fn load_archive(filename: &str) -> Result<Self> {
-         let data_dir = get_data_dir()?;
-        let file_path = data_dir.join("archives").join(filename);
+        let file_path = get_archive_dir()?.join(filename);
         let data = fs::read(&file_path)?;
-        let codexi: Codexi = bincode::deserialize(&data)
-            .map_err(|e| anyhow!("{}", e))?;
+        let codexi = Self::from_bytes(&data)?;
         Ok(codexi)
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::wallet::codexi::BalanceFilter;
+
+    /// Content of the operations, without their ids (regenerated for older data)
+    fn keys(operations: &[Operation]) -> Vec<String> {
+        operations.iter().map(|op| op.dedup_key()).collect()
+    }
+
+    #[test]
+    fn test_export_operations_by_extension() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let operations = vec![
+            Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?,
+            Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?,
+        ];
+
+        let toml_path = dir.path().join("results.toml");
+        Codexi::export_operations(&operations, &toml_path, JsonStyle::Pretty)?;
+        let codexi: Codexi = toml::from_str(&fs::read_to_string(&toml_path)?)?;
+        assert_eq!(codexi.operations, operations, "The toml export must have the codexi layout.");
+
+        let csv_path = dir.path().join("results.csv");
+        Codexi::export_operations(&operations, &csv_path, JsonStyle::Pretty)?;
+        assert_eq!(fs::read_to_string(&csv_path)?.lines().count(), 3, "Expected a header and two rows.");
+
+        assert!(Codexi::export_operations(&operations, &dir.path().join("results.txt"), JsonStyle::Pretty).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_csv_collects_failures() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        codexi.export_csv(dir.path(), true, false)?;
+
+        let csv_path = dir.path().join("codexi.csv");
+        let mut content = fs::read_to_string(&csv_path)?;
+        content.push_str("Transaction,Debit,2025-11-31,10.0,Bad date\n,,,,\nTransaction,Sideways,2025-11-03,5.0,Bad flow\n");
+        fs::write(&csv_path, content)?;
+
+        let (imported, summary) = Codexi::import_csv(dir.path(), false)?;
+
+        assert_eq!(keys(&imported.operations), keys(&codexi.operations), "The exported rows must be imported back.");
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.skipped, 1, "The blank row must be skipped.");
+        assert_eq!(summary.errors.len(), 2, "The invalid rows must be collected, not abort the import.");
+        assert_eq!(summary.errors[0].0, 4, "The error must carry the line number.");
+        assert_eq!(summary.errors[1].0, 6, "The error must carry the line number.");
+        assert_eq!(summary.summary(), "imported 2, skipped 1, failed 2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_bytes_versioned_and_legacy() -> Result<()> {
+        let operations = vec![
+            Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?,
+        ];
+        let codexi = Codexi { title: Some("Joint Account".to_string()), operations: operations.clone(), ..Default::default() };
+
+        let decoded = Codexi::from_bytes(&codexi.to_bytes()?)?;
+        assert_eq!(decoded.title.as_deref(), Some("Joint Account"), "The title must travel with the data.");
+        assert_eq!(decoded.operations, operations);
+
+        // Layout of the operations up to version 1 (no adjustment details)
+        #[derive(Serialize)]
+        struct OldOperation<'a> {
+            kind: &'a OperationKind,
+            flow: OperationFlow,
+            date: NaiveDate,
+            amount: f64,
+            description: &'a str,
+        }
+        let old_operations: Vec<OldOperation> = operations.iter()
+            .map(|op| OldOperation { kind: &op.kind, flow: op.flow, date: op.date, amount: op.amount.to_f64(), description: &op.description })
+            .collect();
+
+        // Files written before the versioned layout (and archives) are the bare operations
+        let legacy = Codexi::from_bytes(&bincode::serialize(&old_operations)?)?;
+        assert_eq!(legacy.title, None);
+        assert_eq!(keys(&legacy.operations), keys(&operations), "The legacy data must still be readable.");
+
+        // Version 1: title and operations without adjustment details
+        let mut v1 = DATA_MAGIC.to_vec();
+        v1.extend_from_slice(&1u16.to_le_bytes());
+        v1.extend(bincode::serialize(&(Some("Joint Account"), &old_operations))?);
+        let legacy = Codexi::from_bytes(&v1)?;
+        assert_eq!(legacy.title.as_deref(), Some("Joint Account"));
+        assert_eq!(keys(&legacy.operations), keys(&operations), "The version 1 data must still be readable.");
+
+        // Version 3: operations with their id, without the flag
+        #[derive(Serialize)]
+        struct OperationV3Out<'a> {
+            id: Uuid,
+            kind: &'a OperationKind,
+            flow: OperationFlow,
+            date: NaiveDate,
+            amount: f64,
+            description: &'a str,
+            adjustment: Option<(f64, f64, f64)>,
+        }
+        let v3_operations: Vec<OperationV3Out> = operations.iter()
+            .map(|op| OperationV3Out { id: op.id, kind: &op.kind, flow: op.flow, date: op.date, amount: op.amount.to_f64(), description: &op.description, adjustment: None })
+            .collect();
+        let mut v3 = DATA_MAGIC.to_vec();
+        v3.extend_from_slice(&3u16.to_le_bytes());
+        v3.extend(bincode::serialize(&(Some("Joint Account"), &v3_operations))?);
+        let legacy = Codexi::from_bytes(&v3)?;
+        assert_eq!(legacy.operations, operations, "The version 3 data keeps its ids, unflagged.");
+
+        // Version 4: amounts in f64, rounded to the cent when read
+        let v4_operations: Vec<(&OperationV3Out, bool)> = v3_operations.iter().map(|op| (op, true)).collect();
+        let mut drifted = v4_operations[0].0.amount;
+        for _ in 0..10 {
+            drifted += 0.1;
+        }
+        let drifted_op = OperationV3Out { amount: drifted, adjustment: Some((0.1 + 0.2, 0.3, 0.1 + 0.1)), ..OperationV3Out { ..*v4_operations[0].0 } };
+        let mut v4 = DATA_MAGIC.to_vec();
+        v4.extend_from_slice(&4u16.to_le_bytes());
+        v4.extend(bincode::serialize(&(Some("Joint Account"), vec![(&drifted_op, true)]))?);
+        let legacy = Codexi::from_bytes(&v4)?;
+        assert_ne!(drifted, 101.0, "The f64 sum drifts.");
+        assert_eq!(legacy.operations[0].amount, Amount::from_cents(10100), "The version 4 amount is rounded to the cent.");
+        assert!(legacy.operations[0].flagged, "The version 4 data keeps its flag.");
+        assert_eq!(legacy.operations[0].adjustment.map(|info| info.previous_balance), Some(Amount::from_cents(30)));
+
+        // Version 5: amounts in cents, without the category
+        let v5_operations: Vec<_> = operations.iter()
+            .map(|op| (op.id, &op.kind, op.flow, op.date, op.amount, &op.description, op.adjustment, true))
+            .collect();
+        let mut v5 = DATA_MAGIC.to_vec();
+        v5.extend_from_slice(&5u16.to_le_bytes());
+        v5.extend(bincode::serialize(&(Some("Joint Account"), &v5_operations))?);
+        let legacy = Codexi::from_bytes(&v5)?;
+        assert_eq!(legacy.operations[0].amount, operations[0].amount);
+        assert!(legacy.operations[0].flagged, "The version 5 data keeps its flag.");
+        assert_eq!(legacy.operations[0].category, None, "The version 5 data is uncategorized.");
+
+        let mut categorized = codexi.clone();
+        categorized.operations[0].category = Some("income".to_string());
+        let decoded = Codexi::from_bytes(&categorized.to_bytes()?)?;
+        assert_eq!(decoded.operations[0].category.as_deref(), Some("income"), "The category must travel with the data.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_failure_rolls_back() -> Result<()> {
+        use std::io::Write;
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        fs::write(data_dir.path().join("codexi.dat"), b"current data")?;
+
+        // A backup whose second file is corrupted (checksum mismatch while extracting)
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("bad.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path)?);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("codexi.dat", options)?;
+        zip.write_all(b"restored data")?;
+        zip.start_file("archives/codexi_2025-11-30.cld", options)?;
+        zip.write_all(b"ARCHIVE-CONTENT")?;
+        zip.finish()?;
+
+        let mut bytes = fs::read(&zip_path)?;
+        let pos = bytes.windows(15).position(|w| w == b"ARCHIVE-CONTENT").unwrap();
+        bytes[pos] = b'X';
+        fs::write(&zip_path, bytes)?;
+
+        let result = Codexi::restore(&zip_path, None);
+
+        assert!(result.is_err(), "The corrupted backup must fail to restore.");
+        assert_eq!(fs::read(data_dir.path().join("codexi.dat"))?, b"current data", "The previous data must be rolled back.");
+        assert!(!data_dir.path().join("archives").exists(), "The partially restored files must be removed.");
+
+        let safety: Vec<_> = fs::read_dir(data_dir.path().join("snapshots"))?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("restore_safety_"))
+            .collect();
+        assert_eq!(safety.len(), 1, "The safety backup must be kept.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_failure_rolls_back_overridden_dirs() -> Result<()> {
+        use std::io::Write;
+        use crate::core::helpers::{set_test_data_dir, set_test_dir_overrides, DirOverrides};
+
+        let data_dir = tempfile::tempdir()?;
+        let elsewhere = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        set_test_dir_overrides(DirOverrides {
+            archive_dir: Some(elsewhere.path().join("archives")),
+            snapshot_dir: Some(elsewhere.path().join("archives").join("snapshots")),
+            account: None,
+        });
+        fs::write(data_dir.path().join("codexi.dat"), b"current data")?;
+        fs::create_dir_all(get_archive_dir()?)?;
+        fs::write(get_archive_dir()?.join("codexi_2024-12-31.cld"), b"current archive")?;
+
+        // The first archive is overwritten, the second one is corrupted (checksum mismatch while extracting)
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("bad.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path)?);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("codexi.dat", options)?;
+        zip.write_all(b"restored data")?;
+        zip.start_file("archives/codexi_2024-12-31.cld", options)?;
+        zip.write_all(b"restored archive")?;
+        zip.start_file("archives/codexi_2025-11-30.cld", options)?;
+        zip.write_all(b"ARCHIVE-CONTENT")?;
+        zip.finish()?;
+
+        let mut bytes = fs::read(&zip_path)?;
+        let pos = bytes.windows(15).position(|w| w == b"ARCHIVE-CONTENT").unwrap();
+        bytes[pos] = b'X';
+        fs::write(&zip_path, bytes)?;
+
+        assert!(Codexi::restore(&zip_path, None).is_err(), "The corrupted backup must fail to restore.");
+        assert_eq!(fs::read(data_dir.path().join("codexi.dat"))?, b"current data");
+        assert_eq!(Codexi::list_archives()?, vec!["codexi_2024-12-31.cld"], "The partially restored archive must be removed.");
+        assert_eq!(fs::read(get_archive_dir()?.join("codexi_2024-12-31.cld"))?, b"current archive", "The previous archive must be rolled back.");
+
+        let safety = fs::read_dir(get_snapshot_dir()?)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("restore_safety_"))
+            .count();
+        assert_eq!(safety, 1, "The safety backup in the overridden snapshot directory must be kept.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_archives_before() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+        use crate::core::helpers::get_archive_path;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        for date in ["2023-12-31", "2024-06-30", "2025-01-31"] {
+            fs::write(get_archive_path(date)?, b"archive")?;
+        }
+        // Not an archive name: never pruned
+        fs::write(data_dir.path().join("archives").join("notes_2020-01-01.cld"), b"keep")?;
+
+        let (count, freed) = Codexi::prune_archives("2025-01-01")?;
+
+        assert_eq!(count, 2, "The two archives closed before 2025 must be deleted.");
+        assert_eq!(freed, 14);
+        assert_eq!(Codexi::list_archives()?, vec!["codexi_2025-01-31.cld"]);
+        assert!(data_dir.path().join("archives").join("notes_2020-01-01.cld").exists());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_backup_skips_symlinks_and_large_files() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        fs::write(data_dir.path().join("codexi.dat"), b"data")?;
+        fs::write(data_dir.path().join("big.bin"), vec![0u8; 2048])?;
+
+        let outside = tempfile::tempdir()?;
+        fs::write(outside.path().join("huge.bin"), b"outside")?;
+        std::os::unix::fs::symlink(outside.path(), data_dir.path().join("linked"))?;
+
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("backup.zip");
+        Codexi::backup(&zip_path, &BackupLimits { max_file_size: 1024, max_total_size: 4096 })?;
+
+        let archive = ZipArchive::new(File::open(&zip_path)?)?;
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names, vec!["codexi.dat"], "The symlink must not be followed and the big file must be skipped.");
+
+        let result = Codexi::backup(&zip_path, &BackupLimits { max_file_size: 4096, max_total_size: 1024 });
+        assert!(result.is_err(), "The total size guard must abort the backup.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect_backup_keeps_live_data() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = Codexi { title: Some("Backup".to_string()), ..Default::default() };
+        codexi.operations.push(Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?);
+        codexi.save(data_dir.path())?;
+
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("backup.zip");
+        Codexi::backup(&zip_path, &BackupLimits::default())?;
+
+        // The live data changes after the backup
+        let live = Codexi { title: Some("Live".to_string()), ..Default::default() };
+        live.save(data_dir.path())?;
+
+        let (inspected, archives) = Codexi::inspect_backup(&zip_path, None)?;
+        assert_eq!(inspected.title.as_deref(), Some("Backup"));
+        assert_eq!(inspected.operations.len(), 1);
+        assert_eq!(archives, 0);
+        assert_eq!(Codexi::load(data_dir.path())?.title.as_deref(), Some("Live"), "The live data must not be touched.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_import_with_operation_in_closed_period() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let codexi = Codexi {
+            operations: vec![
+                Operation::new(OperationKind::System(SystemKind::Close), OperationFlow::Credit, "2025-11-30", 100.0, "SOLDE REPORTÉ : 100")?,
+                Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-15", 12.5, "Lunch")?,
+                Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-12-02", 10.0, "Books")?,
+            ],
+            ..Default::default()
+        };
+        codexi.export_toml(dir.path(), false)?;
+
+        let imported = Codexi::import_toml(dir.path())?;
+        let problems = imported.validate();
+        assert_eq!(problems.len(), 1, "Only the operation in the closed period is a problem: {:?}", problems);
+        assert!(problems[0].contains("2025-11-15"));
+
+        let valid = Codexi { operations: imported.operations[1..].to_vec(), ..Default::default() };
+        assert!(valid.validate().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_csv_lenient_flow() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("codexi.csv"), "\
+kind,flow,date,amount,description
+Transaction,Credit,2025-11-01,100.0,Salary
+Transaction,Sideways,2025-11-02,-12.5,Lunch
+,Out,2025-11-03,4.0,Coffee
+")?;
+
+        let (strict, summary) = Codexi::import_csv(dir.path(), false)?;
+        assert_eq!(strict.operations.len(), 1, "By default the malformed rows are not imported.");
+        assert_eq!(summary.errors.len(), 2);
+        assert!(summary.warnings.is_empty());
+
+        let (lenient, summary) = Codexi::import_csv(dir.path(), true)?;
+        assert_eq!(lenient.operations.len(), 3, "The lenient mode imports every row.");
+        assert!(summary.errors.is_empty());
+        assert_eq!(summary.warnings.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![3, 4, 4]);
+        assert_eq!(lenient.operations[1].flow, OperationFlow::Debit, "The flow is inferred from the amount sign.");
+        assert_eq!(lenient.operations[1].amount, Amount::from(12.5));
+        assert_eq!(lenient.operations[2].kind, OperationKind::Regular(RegularKind::Transaction));
+        assert_eq!(lenient.operations[2].flow, OperationFlow::Credit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_csv_no_header() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+
+        codexi.export_csv(dir.path(), false, false)?;
+        let content = fs::read_to_string(dir.path().join("codexi.csv"))?;
+        let lines: Vec<&str> = content.lines().collect();
+
+        assert_eq!(lines.len(), 2, "Only the data rows are expected.");
+        assert!(lines.iter().all(|l| !l.starts_with("kind,")), "No header line must appear.");
+        assert!(lines[1].starts_with("Transaction,Debit,2025-11-02,12.5,"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_is_atomic() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.save(dir.path())?;
+        assert!(!dir.path().join("codexi.dat.tmp").exists(), "The temp file must be renamed away.");
+
+        // The temp file cannot be created: the save fails and the previous file is intact
+        fs::create_dir(dir.path().join("codexi.dat.tmp"))?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        assert!(codexi.save(dir.path()).is_err());
+        assert_eq!(Codexi::load(dir.path())?.operations.len(), 1, "The previous data must be kept.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_split_by_month() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-10-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-10-15", 20.0, "Books")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-02", 50.0, "Gift")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2026-01-05", 10.0, "Coffee")?;
+
+        let files = codexi.export_split(dir.path(), ExportPeriod::Month, "csv", true, false, JsonStyle::Pretty)?;
+        let names: Vec<String> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec!["codexi_2025-10.csv", "codexi_2025-11.csv", "codexi_2026-01.csv"], "The empty month gets no file.");
+
+        let october = fs::read_to_string(&files[0])?;
+        assert_eq!(october.lines().count(), 3, "A header and the two operations of October.");
+        assert!(october.contains("Books") && !october.contains("Gift"));
+
+        let years = codexi.export_split(dir.path(), ExportPeriod::Year, "toml", true, false, JsonStyle::Pretty)?;
+        assert_eq!(years.len(), 2);
+        let year_2025: Codexi = toml::from_str(&fs::read_to_string(&years[0])?)?;
+        assert_eq!(year_2025.operations.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_backup_round_trip() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = Codexi { title: Some("Backup".to_string()), ..Default::default() };
+        codexi.operations.push(Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?);
+        codexi.save(data_dir.path())?;
+
+        let zip_dir = tempfile::tempdir()?;
+        let enc_path = zip_dir.path().join("backup.zip.enc");
+        Codexi::backup_encrypted(&enc_path, &BackupLimits::default(), "secret")?;
+        assert!(crypto::is_encrypted_file(&enc_path)?);
+        assert!(ZipArchive::new(File::open(&enc_path)?).is_err(), "The backup must not be a readable ZIP.");
+
+        let (inspected, _) = Codexi::inspect_backup(&enc_path, Some("secret"))?;
+        assert_eq!(inspected.title.as_deref(), Some("Backup"));
+        assert!(Codexi::inspect_backup(&enc_path, None).is_err(), "The passphrase is required.");
+
+        // The live data changes after the backup
+        let live = Codexi { title: Some("Live".to_string()), ..Default::default() };
+        live.save(data_dir.path())?;
+
+        assert!(Codexi::restore(&enc_path, Some("wrong")).is_err(), "A wrong passphrase must fail.");
+        assert_eq!(Codexi::load(data_dir.path())?.title.as_deref(), Some("Live"), "A failed restore must not touch the data.");
+
+        Codexi::restore(&enc_path, Some("secret"))?;
+        let restored = Codexi::load(data_dir.path())?;
+        assert_eq!(restored.title.as_deref(), Some("Backup"));
+        assert_eq!(restored.operations.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_and_archive_dir_overrides() -> Result<()> {
+        use crate::core::helpers::{set_test_data_dir, set_test_dir_overrides, DirOverrides};
+
+        let data_dir = tempfile::tempdir()?;
+        let elsewhere = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        set_test_dir_overrides(DirOverrides {
+            archive_dir: Some(elsewhere.path().join("archives")),
+            snapshot_dir: Some(elsewhere.path().join("synced").join("snapshots")),
+            account: None,
+        });
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 20.0, "Lunch")?;
+        codexi.save(data_dir.path())?;
+
+        // Snapshot round-trip through the overridden directory
+        let snapshot_path = codexi.snapshot(Some("before close"))?;
+        assert!(snapshot_path.starts_with(elsewhere.path().join("synced").join("snapshots")));
+        let snapshots = Codexi::list_snapshot()?;
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].note.as_deref(), Some("before close"));
+        let restored = Codexi::restore_snapshot(&snapshots[0].file_name)?;
+        assert_eq!(restored.operations.len(), 2);
+
+        // Archive round-trip through the overridden directory
+        codexi.close_period("2025-11-30", vec![], None)?;
+        assert_eq!(Codexi::list_archives()?, vec!["codexi_2025-11-30.cld"]);
+        assert!(elsewhere.path().join("archives").join("codexi_2025-11-30.cld").exists());
+        assert!(!data_dir.path().join("archives").exists(), "Nothing is archived in the data directory.");
+        assert_eq!(Codexi::load_archive("codexi_2025-11-30.cld")?.operations.len(), 2);
+
+        // The backup carries the archives, the snapshots stay out
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("backup.zip");
+        Codexi::backup(&zip_path, &BackupLimits::default())?;
+        let archive = ZipArchive::new(File::open(&zip_path)?)?;
+        assert!(archive.file_names().any(|n| n == "archives/codexi_2025-11-30.cld"));
+        assert!(!archive.file_names().any(|n| n.ends_with(".snp")));
+
+        let (_, archives) = Codexi::inspect_backup(&zip_path, None)?;
+        assert_eq!(archives, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_gzip_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let codexi = Codexi {
+            title: Some("Joint Account".to_string()),
+            operations: vec![
+                Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-01", 100.0, "Salary")?,
+                Operation::new(OperationKind::Regular(RegularKind::Fee), OperationFlow::Debit, "2025-11-02", 2.5, "Card, yearly fee")?,
+            ],
+            ..Default::default()
+        };
+
+        codexi.export_csv(dir.path(), true, true)?;
+        codexi.export_toml(dir.path(), true)?;
+        assert!(!dir.path().join("codexi.csv").exists());
+        let compressed = fs::read(dir.path().join("codexi.csv.gz"))?;
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b], "The export must be gzip data.");
+
+        let (from_csv, summary) = Codexi::import_csv(dir.path(), false)?;
+        assert_eq!(summary.imported, 2);
+        assert_eq!(keys(&from_csv.operations), keys(&codexi.operations));
+
+        let from_toml = Codexi::import_toml(dir.path())?;
+        assert_eq!(from_toml.title.as_deref(), Some("Joint Account"));
+        assert_eq!(from_toml.operations, codexi.operations);
+
+        let slice_path = dir.path().join("november.csv.gz");
+        Codexi::export_operations(&codexi.operations, &slice_path, JsonStyle::Pretty)?;
+        assert_eq!(&fs::read(&slice_path)?[..2], &[0x1f, 0x8b]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_backups_keeps_newest() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let stamps = ["20250101_080000", "20250301_080000", "20250201_080000", "20250501_080000", "20250401_080000"];
+        for stamp in stamps {
+            fs::write(dir.path().join(format!("codexi_backup_{}.zip", stamp)), b"zip")?;
+        }
+        fs::write(dir.path().join("codexi_backup_20240101_080000.zip.enc"), b"enc")?;
+        fs::write(dir.path().join("my_backup.zip"), b"other")?;
+
+        let beyond = Codexi::backups_beyond(dir.path(), 3)?;
+        let names: Vec<&str> = beyond.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["codexi_backup_20250201_080000.zip", "codexi_backup_20250101_080000.zip", "codexi_backup_20240101_080000.zip.enc"]);
+
+        let (count, freed) = Codexi::prune_backups(dir.path(), 3)?;
+        assert_eq!((count, freed), (3, 9));
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())?
+            .map(|e| e.map(|e| e.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<_>>()?;
+        left.sort();
+        assert_eq!(left, ["codexi_backup_20250301_080000.zip", "codexi_backup_20250401_080000.zip", "codexi_backup_20250501_080000.zip", "my_backup.zip"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_truncated_data_from_snapshot() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        codexi.save(data_dir.path())?;
+        codexi.snapshot(Some("before the crash"))?;
+
+        // An older snapshot, corrupt as well: the newest readable one wins
+        fs::write(get_snapshot_dir()?.join("codexi_20000101_000000.snp"), b"CDXI")?;
+
+        let bytes = fs::read(data_dir.path().join("codexi.dat"))?;
+        fs::write(data_dir.path().join("codexi.dat"), &bytes[..bytes.len() / 2])?;
+        let err = Codexi::load(data_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("system recover"), "The load error must point to the recovery: {}", err);
+
+        let recovery = Codexi::recover(data_dir.path(), &[])?;
+        assert!(matches!(recovery.source, RecoverySource::Snapshot(ref name) if name != "codexi_20000101_000000.snp"));
+        assert_eq!(recovery.failures.len(), 1, "Only codexi.dat was rejected.");
+        assert_eq!(keys(&recovery.codexi.operations), keys(&codexi.operations));
+
+        let corrupt_path = recovery.codexi.replace_data(data_dir.path())?;
+        assert!(corrupt_path.is_some_and(|p| p.exists()), "The unreadable file must be kept aside.");
+        assert_eq!(keys(&Codexi::load(data_dir.path())?.operations), keys(&codexi.operations));
+
+        // Nothing readable at all
+        fs::write(data_dir.path().join("codexi.dat"), b"CDXI")?;
+        for info in Codexi::list_snapshot()? {
+            fs::remove_file(get_snapshot_dir()?.join(info.file_name))?;
+        }
+        assert!(Codexi::recover(data_dir.path(), &[data_dir.path().to_path_buf()]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_debit() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let balance = |c: &Codexi| c.balance(&BalanceFilter::default()).map(|b| b.total);
+
+        let err = Codexi::undo(data_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Nothing to undo"), "{}", err);
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.save(data_dir.path())?;
+        codexi.snapshot(Some("by hand"))?;
+
+        // As main does for a debit
+        codexi.snapshot(Some("before debit"))?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 30.0, "Lunch")?;
+        codexi.save(data_dir.path())?;
+        // A failed command leaves a snapshot identical to the data
+        codexi.snapshot(Some("before debit"))?;
+
+        let (restored, info) = Codexi::undo(data_dir.path())?;
+        assert_eq!(info.note.as_deref(), Some("before debit"));
+        assert_eq!(balance(&restored)?, Amount::from(100.0));
+        assert_eq!(balance(&Codexi::load(data_dir.path())?)?, Amount::from(100.0), "The restored state is saved.");
+
+        assert!(Codexi::undo(data_dir.path()).is_err(), "The snapshot taken by hand is not an undo step.");
+        assert_eq!(Codexi::list_snapshot()?.len(), 1, "Only the snapshot taken by hand is left.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_many_snapshots_in_one_second() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let snapshot_dir = get_snapshot_dir()?;
+        fs::create_dir_all(&snapshot_dir)?;
+
+        // 12 changes in the same second: codexi_..._120000.snp, then _1 to _11
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        for n in 0..12 {
+            let name = if n == 0 { "codexi_20251101_120000.snp".to_string() } else { format!("codexi_20251101_120000_{}.snp", n) };
+            write_atomic(&snapshot_dir.join(&name), codexi.to_bytes()?)?;
+            fs::write(snapshot_dir.join(&name).with_extension("txt"), "before debit")?;
+            codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 1.0, "Coffee")?;
+        }
+        codexi.save(data_dir.path())?;
+
+        let names: Vec<String> = Codexi::list_snapshot()?.into_iter().map(|info| info.file_name).collect();
+        assert_eq!(names.last().map(String::as_str), Some("codexi_20251101_120000_11.snp"));
+        assert_eq!(names[9..], ["codexi_20251101_120000_9.snp", "codexi_20251101_120000_10.snp", "codexi_20251101_120000_11.snp"]);
+
+        let (restored, info) = Codexi::undo(data_dir.path())?;
+        assert_eq!(info.file_name, "codexi_20251101_120000_11.snp", "The newest snapshot is restored.");
+        assert_eq!(restored.operations.len(), 12, "Only the last debit is undone.");
+
+        let pruned: Vec<String> = Codexi::snapshots_beyond(2)?.into_iter().map(|(name, _)| name).collect();
+        assert!(!pruned.contains(&"codexi_20251101_120000_10.snp".to_string()), "The newest ones are kept.");
+        assert!(pruned.contains(&"codexi_20251101_120000.snp".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_reopen_restores_archive() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-10-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-10-05", 30.0, "Lunch")?;
+        assert!(codexi.close_period("2025-10-31", vec![], None)?);
+        codexi.save(data_dir.path())?;
+        let closed = codexi.operations.clone();
+        let archive_path = get_archive_path("2025-10-31")?;
+        let archived = fs::read(&archive_path)?;
+
+        // As main does for a reopen
+        let previous = codexi.clone();
+        let reopened_path = codexi.reopen_period("codexi_2025-10-31.cld")?;
+        let snapshot_path = previous.snapshot(Some("before reopen"))?;
+        codexi.save(data_dir.path())?;
+        let kept_path = Codexi::keep_reopened_archive(&reopened_path, &snapshot_path)?;
+        assert!(!archive_path.exists(), "The archive is no longer in the archives.");
+        assert_eq!(codexi.operations.len(), 2, "The archived operations are back.");
+
+        let (restored, _) = Codexi::undo(data_dir.path())?;
+        assert_eq!(restored.operations, closed, "The ledger is closed again.");
+        assert_eq!(fs::read(&archive_path)?, archived, "The archive is back with the same operations.");
+        assert!(!kept_path.exists());
+        assert_eq!(Codexi::load_archive("codexi_2025-10-31.cld")?.operations.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accounts_are_independent() -> Result<()> {
+        use crate::core::helpers::{set_test_data_dir, set_test_dir_overrides, DirOverrides, list_accounts};
+
+        let base_dir = tempfile::tempdir()?;
+        set_test_data_dir(base_dir.path());
+        let use_account = |name: &str| set_test_dir_overrides(DirOverrides { account: Some(name.to_string()), ..Default::default() });
+        let balance = |c: &Codexi| c.balance(&BalanceFilter::default()).map(|b| b.total);
+
+        for (account, amount) in [("default", 100.0), ("business", 2500.0), ("perso", 40.0)] {
+            use_account(account);
+            let mut codexi = Codexi::load(&get_data_dir()?)?;
+            codexi.initialize(amount, "2025-11-01", false)?;
+            codexi.save(&get_data_dir()?)?;
+        }
+        use_account("perso");
+        let mut perso = Codexi::load(&get_data_dir()?)?;
+        perso.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 15.0, "Lunch")?;
+        perso.save(&get_data_dir()?)?;
+
+        assert_eq!(get_data_dir()?, base_dir.path().join("accounts").join("perso"));
+        assert_eq!(balance(&Codexi::load(&get_data_dir()?)?)?, Amount::from(25.0));
+        use_account("business");
+        assert_eq!(balance(&Codexi::load(&get_data_dir()?)?)?, Amount::from(2500.0));
+        use_account("default");
+        assert_eq!(get_data_dir()?, base_dir.path(), "The default account keeps the data directory.");
+        assert_eq!(balance(&Codexi::load(&get_data_dir()?)?)?, Amount::from(100.0));
+
+        assert_eq!(list_accounts()?, ["default", "business", "perso"]);
+        use_account("../evil");
+        assert!(get_data_dir().is_err(), "An account name cannot leave the data directory.");
+
+        // The backup of the default account leaves the other accounts out
+        use_account("default");
+        let zip_path = base_dir.path().join("backup.zip");
+        Codexi::backup(&zip_path, &BackupLimits::default())?;
+        let archive = ZipArchive::new(File::open(&zip_path)?)?;
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&"codexi.dat"));
+        assert!(!names.iter().any(|n| n.starts_with("accounts")), "{:?}", names);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_json_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut codexi = Codexi { title: Some("Joint Account".to_string()), ..Default::default() };
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 0.1, "Candy")?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Custom("Food".into())), OperationFlow::Debit, "2025-11-03", 12.5, "Lunch, with \"Bob\"")?;
+        codexi.adjust_balance(80.0, "2025-11-04")?;
+        codexi.operations[1].flagged = true;
+
+        codexi.export_json(dir.path(), false, JsonStyle::Pretty)?;
+        let imported = Codexi::import_json(dir.path())?;
+        assert_eq!(imported.title, codexi.title);
+        assert_eq!(imported.operations, codexi.operations, "The json export must be imported back identical.");
+
+        // Written by another tool: any order, amounts as numbers, no id
+        fs::write(dir.path().join("codexi.json"), r#"{"operations": [
+            {"kind": {"Regular": "Transaction"}, "flow": "Debit", "date": "2025-11-05", "amount": 2.5, "description": "Coffee"},
+            {"kind": {"System": "Init"}, "flow": "Credit", "date": "2025-11-01", "amount": 10, "description": "INITIAL AMOUNT"}
+        ]}"#)?;
+        let imported = Codexi::import_json(dir.path())?;
+        assert_eq!(imported.operations[0].kind, OperationKind::System(SystemKind::Init), "The operations are sorted by date.");
+        assert_eq!(imported.operations[1].amount, Amount::from_cents(250));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_snapshots_keeps_newest() -> Result<()> {
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let snapshot_dir = get_snapshot_dir()?;
+        fs::create_dir_all(&snapshot_dir)?;
+
+        let names = [
+            "codexi_20250103_080000.snp",
+            "codexi_20241231_235959.snp",
+            "codexi_20250102_120000.snp",
+            "codexi_20250101_000000.snp",
+            "codexi_20250103_090000.snp",
+        ];
+        for name in names {
+            fs::write(snapshot_dir.join(name), b"CDXI")?;
+        }
+        fs::write(snapshot_dir.join("codexi_20241231_235959.txt"), "before debit")?;
+        fs::write(snapshot_dir.join("notes.snp"), b"not a snapshot name")?;
+
+        let (count, freed) = Codexi::prune_snapshots(2)?;
+        assert_eq!(count, 3);
+        assert_eq!(freed, 12);
+
+        let left: Vec<String> = Codexi::list_snapshot()?.into_iter().map(|info| info.file_name).collect();
+        assert_eq!(left, vec!["codexi_20250103_080000.snp", "codexi_20250103_090000.snp"], "The two newest are kept.");
+        assert!(!snapshot_dir.join("codexi_20241231_235959.txt").exists(), "The note goes with its snapshot.");
+        assert!(snapshot_dir.join("notes.snp").exists(), "Only the snapshot names are pruned.");
+        assert_eq!(Codexi::prune_snapshots(2)?, (0, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_plan_leaves_data_untouched() -> Result<()> {
+        use std::io::Write;
+        use crate::core::helpers::set_test_data_dir;
+
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        fs::write(data_dir.path().join("codexi.dat"), b"current data")?;
+
+        let zip_dir = tempfile::tempdir()?;
+        let zip_path = zip_dir.path().join("backup.zip");
+        let mut zip = ZipWriter::new(File::create(&zip_path)?);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("codexi.dat", options)?;
+        zip.write_all(b"restored data")?;
+        zip.add_directory("archives/", options)?;
+        zip.start_file("archives/codexi_2025-11-30.cld", options)?;
+        zip.write_all(b"archive")?;
+        zip.finish()?;
+
+        let plan = Codexi::restore_plan(&zip_path, None)?;
+
+        assert_eq!(plan, vec![
+            RestorePlanEntry { path: data_dir.path().join("codexi.dat"), exists: true },
+            RestorePlanEntry { path: get_archive_dir()?.join("codexi_2025-11-30.cld"), exists: false },
+        ]);
+        assert_eq!(fs::read(data_dir.path().join("codexi.dat"))?, b"current data", "A dry run must not write the data.");
+        assert!(!get_archive_dir()?.join("codexi_2025-11-30.cld").exists(), "A dry run must not write the archives.");
+        assert!(!data_dir.path().join("snapshots").exists(), "A dry run takes no safety backup.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_import_twice_with_dedup() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut exported = Codexi::default();
+        exported.initialize(100.0, "2025-11-01", false)?;
+        exported.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        exported.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        exported.export_toml(dir.path(), false)?;
+
+        let mut codexi = Codexi::default();
+        let first = codexi.merge_import(Codexi::import_toml(dir.path())?, true)?;
+        assert_eq!((first.imported, first.existing), (3, 0), "The two same-day lunches are both imported.");
+
+        let second = codexi.merge_import(Codexi::import_toml(dir.path())?, true)?;
+        assert_eq!((second.imported, second.existing), (0, 3));
+        assert_eq!(codexi.operations.len(), 3, "The second import must not change the ledger.");
+
+        // A new operation in the file is merged, the known ones are skipped
+        exported.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-05", 40.0, "Refund")?;
+        exported.export_toml(dir.path(), false)?;
+        let third = codexi.merge_import(Codexi::import_toml(dir.path())?, true)?;
+        assert_eq!((third.imported, third.existing), (1, 3));
+        assert_eq!(codexi.operations.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_import_skips_conflicts() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        crate::core::helpers::set_test_data_dir(data_dir.path());
+        let mut codexi = Codexi::default();
+        codexi.initialize(100.0, "2025-11-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch")?;
+        assert!(codexi.close_period("2025-11-30", vec![], None)?);
+
+        let mut incoming = Codexi::default();
+        incoming.initialize(5.0, "2025-10-01", false)?;
+        incoming.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-11-15", 20.0, "Old refund")?;
+        incoming.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-12-03", 30.0, "Gift")?;
+        incoming.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Credit, "2025-12-03", 30.0, "Gift")?;
+
+        let summary = codexi.merge_import(incoming, false)?;
+        assert_eq!((summary.imported, summary.skipped), (2, 2), "Without dedup, both gifts are merged.");
+        assert!(summary.conflicts[0].contains("system anchor"), "The second Init is not merged.");
+        assert!(summary.conflicts[1].contains("Old refund") && summary.conflicts[1].contains("closed on 2025-11-30"));
+
+        let descriptions: Vec<&str> = codexi.operations.iter().map(|op| op.description.as_str()).collect();
+        assert_eq!(descriptions[1..], ["Gift", "Gift"]);
+        assert!(codexi.validate().is_empty());
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 // src/core/wallet/mod.rs
 
+mod amount;
 mod system_kind;
 mod regular_kind;
 mod operation_kind;
@@ -7,10 +8,33 @@ mod operation_flow;
 mod operation;
 mod viewer;
 mod file_management;
+mod diff;
+mod bank_import;
+mod batch;
+mod schema;
+mod recurring;
 mod codexi;
 
+pub use amount::Amount;
 pub use regular_kind::RegularKind;
 pub use operation_kind::OperationKind;
 pub use operation_flow::OperationFlow;
 pub use operation::Operation;
+pub use operation::DescriptionStyle;
 pub use codexi::Codexi;
+pub use codexi::FutureDatePolicy;
+pub use codexi::SearchSort;
+pub use codexi::SearchField;
+pub use codexi::SearchPage;
+pub use codexi::SearchFilter;
+pub use codexi::BalanceFilter;
+pub use codexi::OperationEdit;
+pub use codexi::CodexiError;
+pub use file_management::BackupLimits;
+pub use file_management::ExportPeriod;
+pub use file_management::RecoverySource;
+pub use bank_import::{BankCsvMapping, BankAmountColumns};
+pub use viewer::DEFAULT_DESC_WIDTH;
+pub use viewer::SearchColumn;
+pub use schema::DataSchema;
+pub use recurring::{RecurringRule, RecurringRules};
@@ -5,11 +5,14 @@ use thiserror::Error;
 use serde::{Serialize, Deserialize};
 use chrono::NaiveDate;
 use thousands::Separable;
+use uuid::Uuid;
 
+use super::amount::Amount;
 use super::operation_kind::OperationKind;
 use super::operation_flow::OperationFlow;
 use super::system_kind::SystemKind;
 use super::regular_kind::RegularKind;
+use super::schema::FieldSchema;
 
 /// Error type for Operation
 #[derive(Debug, Error)]
@@ -17,23 +20,76 @@ pub enum OperationError {
     #[error("Invalid Operation Date format: {0}")]
     InvalidDate(#[from] chrono::ParseError),
 }
+/// Enum for the normalization applied to the descriptions of the new operations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DescriptionStyle {
+    /// Keep the description as entered (only trimmed)
+    #[default]
+    Keep,
+    /// Trim and collapse the inner whitespace (ex: "  ATM   withdrawal " -> "ATM withdrawal")
+    Collapse,
+    /// Collapse the whitespace and capitalize each word (ex: "atm  WITHDRAWAL" -> "Atm Withdrawal")
+    TitleCase,
+}
+/// Struct for the details of an adjustment operation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdjustmentInfo {
+    /// Theoretical balance of the codexi before the adjustment
+    pub previous_balance: Amount,
+    /// Physical balance the codexi is adjusted to
+    pub physical_balance: Amount,
+    /// Signed deviation: physical - theoretical
+    pub deviation: Amount,
+}
 /// Struct representing a wallet operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Operation {
+    /// Stable identifier, kept across sorts and removals (generated for older data)
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub kind: OperationKind,
     pub flow: OperationFlow,
     pub date: NaiveDate,
-    pub amount: f64,
+    pub amount: Amount,
     pub description: String,
+    /// Details of an adjustment (Adjust operations only)
+    #[serde(default)]
+    pub adjustment: Option<AdjustmentInfo>,
+    /// Starred for a follow-up (ex: "verify this charge")
+    #[serde(default)]
+    pub flagged: bool,
+    /// Category for the category report (ex: groceries), independent of the kind
+    #[serde(default)]
+    pub category: Option<String>,
+}
+/// Fields of AdjustmentInfo, for the schema (keep in sync with the struct)
+impl AdjustmentInfo {
+    pub const FIELDS: &'static [FieldSchema] = &[
+        FieldSchema { name: "previous_balance", type_name: "number", optional: false, description: "Theoretical balance before the adjustment" },
+        FieldSchema { name: "physical_balance", type_name: "number", optional: false, description: "Physical balance the codexi is adjusted to" },
+        FieldSchema { name: "deviation", type_name: "number", optional: false, description: "Signed deviation: physical - theoretical" },
+    ];
 }
 /// Methods for Operation
 impl Operation {
+    /// Fields of the operation, for the schema (keep in sync with the struct)
+    pub const FIELDS: &'static [FieldSchema] = &[
+        FieldSchema { name: "id", type_name: "uuid", optional: true, description: "Stable identifier (generated when missing)" },
+        FieldSchema { name: "kind", type_name: "kind", optional: false, description: "System or regular kind (see kinds)" },
+        FieldSchema { name: "flow", type_name: "flow", optional: false, description: "Debit, Credit or None (see flows)" },
+        FieldSchema { name: "date", type_name: "date (YYYY-MM-DD)", optional: false, description: "Date of the operation" },
+        FieldSchema { name: "amount", type_name: "number", optional: false, description: "Amount with at most 2 decimals, never negative (the flow gives the sign)" },
+        FieldSchema { name: "description", type_name: "string", optional: false, description: "Free text" },
+        FieldSchema { name: "adjustment", type_name: "adjustment", optional: true, description: "Details of an Adjust operation (see adjustment)" },
+        FieldSchema { name: "flagged", type_name: "bool", optional: true, description: "Starred for a follow-up (default false)" },
+        FieldSchema { name: "category", type_name: "string", optional: true, description: "Category for the category report (ex: groceries)" },
+    ];
 
     pub fn new(
         kind: OperationKind,
         flow: OperationFlow,
         dt: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
         desc: impl Into<String>,
     ) -> Result<Self, OperationError>
     {
@@ -46,11 +102,15 @@ impl Operation {
         let naive_date = NaiveDate::parse_from_str(dt, "%Y-%m-%d")?;
 
         Ok(Self {
-            kind: kind,
-            flow: flow,
+            id: Uuid::new_v4(),
+            kind,
+            flow,
             date: naive_date,
-            amount: amount,
-            description:description,
+            amount: amount.into(),
+            description,
+            adjustment: None,
+            flagged: false,
+            category: None,
         })
     }
     /// Create a new System Operation
@@ -58,11 +118,11 @@ impl Operation {
         kind: SystemKind,
         flow: OperationFlow,
         dt: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
         desc: impl Into<String>,
     ) -> Result<Self, OperationError>
     {
-        Ok(Self::new(OperationKind::System(kind), flow, dt, amount,desc)?)
+        Self::new(OperationKind::System(kind), flow, dt, amount,desc)
 
     }
     /// Create a new Regular Operation
@@ -71,25 +131,120 @@ impl Operation {
         kind: RegularKind,
         flow: OperationFlow,
         dt: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
         desc: impl Into<String>,
     ) -> Result<Self, OperationError>
     {
-        Ok(Self::new(OperationKind::Regular(kind), flow, dt, amount,desc)?)
+        Self::new(OperationKind::Regular(kind), flow, dt, amount,desc)
+    }
+    /// Normalize a description according to the style
+    /// ex: Operation::normalize_description("  ATM   withdrawal ", DescriptionStyle::Collapse); // "ATM withdrawal"
+    pub fn normalize_description(desc: &str, style: DescriptionStyle) -> String {
+        match style {
+            DescriptionStyle::Keep => desc.to_string(),
+            DescriptionStyle::Collapse => desc.split_whitespace().collect::<Vec<_>>().join(" "),
+            DescriptionStyle::TitleCase => desc
+                .split_whitespace()
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+    /// Get the short form of the id (its first 8 characters), enough to reference an operation
+    pub fn short_id(&self) -> String {
+        self.id.simple().to_string()[..8].to_string()
+    }
+    /// Get the payee of the operation: the part of the description before " - " (ex: "Bistro - Lunch"),
+    /// the convention of the batch files (see BatchLine::full_description), or the whole description.
+    pub fn payee(&self) -> &str {
+        let description = self.description.trim();
+        match description.split_once(" - ") {
+            Some((payee, _)) if !payee.trim().is_empty() => payee.trim(),
+            _ => description,
+        }
+    }
+    /// Get the canonical key of the operation (date, flow, kind, amount, description)
+    /// Two operations with the same key describe the same event (ex: a duplicate import).
+    pub fn dedup_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}",
+            self.date.format("%Y-%m-%d"),
+            self.flow.as_str(),
+            self.kind,
+            self.amount,
+            self.description.trim()
+        )
+    }
+    /// Get the operation as a csv row (kind,flow,date,amount,description), without line ending
+    /// ex: Transaction,Debit,2025-11-02,12.5,"Lunch, with ""Bob"""
+    /// The fields are quoted per RFC 4180, the same row as the csv export.
+    pub fn to_csv_row(&self) -> String {
+        let kind = match &self.kind {
+            OperationKind::System(kind) => format!("{:?}", kind),
+            OperationKind::Regular(kind) => kind.as_str().to_string(),
+        };
+        [
+            kind,
+            self.flow.as_str().to_string(),
+            self.date.format("%Y-%m-%d").to_string(),
+            format!("{:?}", self.amount.to_f64()),
+            self.description.clone(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
     }
+}
 
+/// Quote a csv field when it holds a comma, a quote or a line break (the quotes are doubled)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 /// Implement Display for Operation
 impl fmt::Display for Operation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} | {} | {} | {:.2} | {}",
+            "{} | {} | {} | {} | {}",
             self.date.format("%Y-%m-%d"),
             self.kind,
             self.flow,
-            self.amount.separate_with_commas(),
+            self.amount.to_string().separate_with_commas(),
             self.description
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_to_csv_row_escapes_the_description() -> anyhow::Result<()> {
+        let kind = OperationKind::Regular(RegularKind::Transaction);
+        let op = Operation::new(kind.clone(), OperationFlow::Debit, "2025-11-02", 12.5, "Lunch, with \"Bob\"")?;
+        assert_eq!(op.to_csv_row(), "Transaction,Debit,2025-11-02,12.5,\"Lunch, with \"\"Bob\"\"\"");
+
+        let plain = Operation::new(kind, OperationFlow::Credit, "2025-11-03", 100.0, "Salary")?;
+        assert_eq!(plain.to_csv_row(), "Transaction,Credit,2025-11-03,100.0,Salary", "A plain field is not quoted.");
+
+        // Same row as the csv crate
+        let mut wtr = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+        wtr.write_record(["Transaction", "Debit", "2025-11-02", "12.5", "Lunch, with \"Bob\""])?;
+        assert_eq!(String::from_utf8(wtr.into_inner()?)?.trim_end(), op.to_csv_row());
+
+        Ok(())
+    }
+}
@@ -19,6 +19,8 @@ pub enum OperationFlow {
 }
 /// Methods for OperationFlow
 impl OperationFlow {
+    /// All the flows
+    pub const ALL: [OperationFlow; 3] = [OperationFlow::Debit, OperationFlow::Credit, OperationFlow::None];
     /// Get the string representation of the specific flow
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -61,18 +63,22 @@ impl OperationFlow {
         *self = self.opposite();
     }
     /// Get the sign associated with the flow
-    pub fn to_sign(&self) -> f64 {
+    pub fn to_sign(self) -> i64 {
         match self {
-            OperationFlow::Debit => -1.0,
-            OperationFlow::Credit => 1.0,
-            OperationFlow::None => 0.0,
+            OperationFlow::Debit => -1,
+            OperationFlow::Credit => 1,
+            OperationFlow::None => 0,
         }
     }
     /// Create an OperationFlow from a sign
-    pub fn from_sign(sign: f64) -> Self {
-        if sign > 0.0 {
+    /// A zero gives None, a flow that moves nothing. It is legitimate only for the anchors:
+    /// an Init of an empty account and a Close with nothing to carry forward still mark their date.
+    /// Any other zero-amount operation is a bug.
+    /// ex: OperationFlow::from_sign(difference.cents());
+    pub fn from_sign(sign: i64) -> Self {
+        if sign > 0 {
             OperationFlow::Credit
-        } else if sign < 0.0 {
+        } else if sign < 0 {
             OperationFlow::Debit
         } else {
             OperationFlow::None
@@ -13,7 +13,7 @@ pub enum OperationKindError {
     Unknown(String),
 }
 /// Enum representing the kind of operation: System or Regular
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Ord, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Ord, PartialOrd)]
 pub enum OperationKind {
     System(SystemKind),
     Regular(RegularKind),
@@ -36,12 +36,19 @@ impl OperationKind {
         }
     }
     /// Get the string representation of the specific kind
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             OperationKind::System(kind) => kind.as_str(),
             OperationKind::Regular(kind) => kind.as_str(),
         }
     }
+    /// Check if the OperationKind matches a kind filter (custom categories ignore the case)
+    pub fn matches(&self, filter: &OperationKind) -> bool {
+        match (self, filter) {
+            (OperationKind::Regular(kind), OperationKind::Regular(other)) => kind.matches(other),
+            _ => self == filter,
+        }
+    }
     /// Try to create an OperationKind from a string
     pub fn try_from_str(s: &str) -> Result<Self, OperationKindError> {
         let lower = s.to_ascii_lowercase();
@@ -50,7 +57,7 @@ impl OperationKind {
             return Ok(OperationKind::System(sk));
         }
 
-        if let Ok(rk) = RegularKind::try_from_str(&lower) {
+        if let Ok(rk) = RegularKind::try_from_str(s) {
             return Ok(OperationKind::Regular(rk));
         }
 
@@ -65,10 +72,10 @@ impl TryFrom<&str> for OperationKind {
     }
 }
 
-/// Implement From<OperationKind> for &'static str
-impl From<OperationKind> for &'static str {
+/// Implement From<OperationKind> for String
+impl From<OperationKind> for String {
     fn from(t: OperationKind) -> Self {
-        t.as_str()
+        t.as_str().to_string()
     }
 }
 /// Implement Display for OperationKind
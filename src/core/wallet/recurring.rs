@@ -0,0 +1,212 @@
+// src/core/wallet/recurring.rs
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::Path;
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+
+use super::amount::Amount;
+use super::codexi::{Codexi, CodexiError};
+use super::operation_flow::OperationFlow;
+use super::operation_kind::OperationKind;
+use crate::core::helpers::write_atomic;
+
+/// Struct for a recurring rule: an operation repeated each month (ex: rent, salary)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringRule {
+    pub kind: OperationKind,
+    pub flow: OperationFlow,
+    pub amount: Amount,
+    pub description: String,
+    /// Day of the month (1-31), the last day of the shorter months
+    pub day: u32,
+    /// No operation is generated before this date
+    pub start: NaiveDate,
+    /// Date of the latest occurrence generated (or skipped), none yet
+    pub last_generated: Option<NaiveDate>,
+}
+/// Methods for RecurringRule
+impl RecurringRule {
+
+    /// Create a rule, the flow is credit or debit and the amount positive
+    /// ex: RecurringRule::new(kind, OperationFlow::Debit, Amount::from(800.0), "Rent", 1, start);
+    pub fn new(kind: OperationKind, flow: OperationFlow, amount: Amount, description: &str, day: u32, start: NaiveDate) -> Result<Self> {
+        if kind.is_system() {
+            return Err(anyhow!("A recurring rule cannot have a system kind ({}).", kind));
+        }
+        if flow == OperationFlow::None {
+            return Err(anyhow!("The flow of a recurring rule is 'debit' or 'credit'."));
+        }
+        if !amount.is_positive() {
+            return Err(anyhow!("The amount of a recurring rule must be positive."));
+        }
+        if !(1..=31).contains(&day) {
+            return Err(anyhow!("Invalid day of month {} (expected 1 to 31).", day));
+        }
+        Ok(RecurringRule {
+            kind,
+            flow,
+            amount,
+            description: description.trim().to_string(),
+            day,
+            start,
+            last_generated: None,
+        })
+    }
+    /// Date of the occurrence in a month (the last day of the month when it is shorter)
+    fn occurrence(&self, year: i32, month: u32) -> Option<NaiveDate> {
+        (1..=self.day).rev().find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+    }
+    /// Dates of the occurrences not generated yet, up to today (included)
+    pub fn due_dates(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let first = match self.last_generated {
+            Some(last) if last >= self.start => last,
+            _ => self.start,
+        };
+        let (mut year, mut month) = (first.year(), first.month());
+        let mut dates = Vec::new();
+        while let Some(date) = self.occurrence(year, month) {
+            if date > today {
+                break;
+            }
+            if date >= self.start && self.last_generated.is_none_or(|last| date > last) {
+                dates.push(date);
+            }
+            (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        }
+        dates
+    }
+}
+/// Struct for the recurring rules file (recurring.dat in the data directory)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecurringRules {
+    pub rules: Vec<RecurringRule>,
+}
+/// Struct for the result of a materialization
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterializeSummary {
+    /// Number of operations added
+    pub generated: usize,
+    /// Occurrences dated on or before the latest close, not added
+    pub skipped: Vec<(String, NaiveDate)>,
+}
+/// Methods for RecurringRules
+impl RecurringRules {
+
+    /// Load the rules (empty if the file does not exist yet)
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("recurring.dat");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = fs::read(&path)?;
+        bincode::deserialize(&bytes).map_err(|e| anyhow!("Invalid recurring file {:?}: {}", path, e))
+    }
+    /// Save the rules
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join("recurring.dat");
+        write_atomic(&path, bincode::serialize(self)?)?;
+        log::debug!("Recurring rules saved to {:?}", path);
+        Ok(())
+    }
+    /// Remove a rule by index
+    pub fn remove(&mut self, index: usize) -> Result<RecurringRule> {
+        if index >= self.rules.len() {
+            return Err(anyhow!("Recurring rule index {} is out of bounds.", index));
+        }
+        Ok(self.rules.remove(index))
+    }
+    /// Generate the operations of the rules from their latest occurrence up to today, with add_operation.
+    /// ex: rules.materialize(&mut codexi, Local::now().date_naive());
+    /// An occurrence in a closed period is skipped and logged. The other errors (ex: insufficient funds)
+    /// stop the materialization, nothing should be saved then.
+    pub fn materialize(&mut self, codexi: &mut Codexi, today: NaiveDate) -> Result<MaterializeSummary> {
+        let mut summary = MaterializeSummary::default();
+        for rule in self.rules.iter_mut() {
+            for date in rule.due_dates(today) {
+                let date_str = date.format("%Y-%m-%d").to_string();
+                match codexi.add_operation(rule.kind.clone(), rule.flow, &date_str, rule.amount, &rule.description) {
+                    Ok(()) => summary.generated += 1,
+                    Err(e) if matches!(e.downcast_ref::<CodexiError>(), Some(CodexiError::ClosedPeriod { .. })) => {
+                        log::warn!("Recurring '{}' of {} skipped, the period is closed.", rule.description, date_str);
+                        summary.skipped.push((rule.description.clone(), date));
+                    }
+                    Err(e) => return Err(e),
+                }
+                rule.last_generated = Some(date);
+            }
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::super::regular_kind::RegularKind;
+    use crate::core::helpers::set_test_data_dir;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn rent(day: u32, start: &str) -> Result<RecurringRule> {
+        RecurringRule::new(OperationKind::Regular(RegularKind::Custom("rent".to_string())), OperationFlow::Debit, Amount::from(800.0), "Rent", day, date(start))
+    }
+
+    #[test]
+    fn test_materialize_monthly_rule() -> Result<()> {
+        let mut codexi = Codexi::default();
+        codexi.initialize(5000.0, "2025-01-01", false)?;
+        let mut rules = RecurringRules { rules: vec![rent(5, "2025-01-10")?] };
+
+        // 2025-02-05, 2025-03-05 and 2025-04-05: the start skips January, today is before May
+        let summary = rules.materialize(&mut codexi, date("2025-04-30"))?;
+        assert_eq!(summary.generated, 3);
+        let rents: Vec<NaiveDate> = codexi.operations.iter().filter(|op| op.description == "Rent").map(|op| op.date).collect();
+        assert_eq!(rents, vec![date("2025-02-05"), date("2025-03-05"), date("2025-04-05")]);
+        assert_eq!(rules.rules[0].last_generated, Some(date("2025-04-05")));
+
+        let again = rules.materialize(&mut codexi, date("2025-04-30"))?;
+        assert_eq!(again.generated, 0, "The generated occurrences are not repeated.");
+        assert_eq!(rules.materialize(&mut codexi, date("2025-05-05"))?.generated, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_materialize_skips_closed_period() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        set_test_data_dir(data_dir.path());
+        let mut codexi = Codexi::default();
+        codexi.initialize(5000.0, "2025-01-01", false)?;
+        codexi.add_operation(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-01-15", 20.0, "Lunch")?;
+        assert!(codexi.close_period("2025-02-28", vec![], None)?);
+        let mut rules = RecurringRules { rules: vec![rent(31, "2025-01-01")?] };
+
+        let summary = rules.materialize(&mut codexi, date("2025-04-30"))?;
+        assert_eq!(summary.generated, 2, "2025-03-31 and 2025-04-30 (the last day of April).");
+        assert_eq!(summary.skipped.len(), 2, "2025-01-31 and 2025-02-28 are in the closed period.");
+        assert_eq!(summary.skipped[1].1, date("2025-02-28"));
+        assert_eq!(rules.materialize(&mut codexi, date("2025-04-30"))?.skipped.len(), 0, "The skipped occurrences are not retried.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rules_save_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert!(RecurringRules::load(dir.path())?.rules.is_empty());
+
+        let rules = RecurringRules { rules: vec![rent(1, "2025-01-01")?] };
+        rules.save(dir.path())?;
+        assert_eq!(RecurringRules::load(dir.path())?, rules);
+
+        assert!(rent(0, "2025-01-01").is_err());
+        assert!(RecurringRule::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::None, Amount::from(1.0), "x", 1, date("2025-01-01")).is_err());
+
+        Ok(())
+    }
+}
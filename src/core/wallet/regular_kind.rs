@@ -11,32 +11,46 @@ pub enum RegularKindError {
     Unknown(String),
 }
 /// Enum representing the regular kinds of operations
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Ord, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Ord, PartialOrd)]
 pub enum RegularKind {
     Transaction,
     Fee,
     Transfer,
     Refund,
+    /// User-defined category (ex: Salary, Rent), displayed as entered
+    Custom(String),
 }
 /// Methods for RegularKind
 impl RegularKind {
+    /// The built-in regular kinds (any other name is a custom category)
+    pub const BUILTIN: [RegularKind; 4] = [RegularKind::Transaction, RegularKind::Fee, RegularKind::Transfer, RegularKind::Refund];
     /// Get the string representation of the specific regular kind
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             RegularKind::Transaction => "Transaction",
             RegularKind::Fee => "Fee",
             RegularKind::Transfer => "Transfer",
             RegularKind::Refund => "Refund",
+            RegularKind::Custom(category) => category,
+        }
+    }
+    /// Check if two regular kinds are the same, custom categories are compared case-insensitively
+    pub fn matches(&self, other: &RegularKind) -> bool {
+        match (self, other) {
+            (RegularKind::Custom(a), RegularKind::Custom(b)) => a.eq_ignore_ascii_case(b),
+            _ => self == other,
         }
     }
     /// Try to create a RegularKind from a string
+    /// An unknown name gives a custom category.
     pub fn try_from_str(s: &str) -> Result<Self, RegularKindError> {
-        match s.to_ascii_lowercase().as_str() {
+        match s.trim().to_ascii_lowercase().as_str() {
             "transaction" | "trans" => Ok(RegularKind::Transaction),
             "fee" => Ok(RegularKind::Fee),
             "transfer" => Ok(RegularKind::Transfer),
             "refund" => Ok(RegularKind::Refund),
-            _ => Err(RegularKindError::Unknown(s.to_string())),
+            "" => Err(RegularKindError::Unknown(s.to_string())),
+            _ => Ok(RegularKind::Custom(s.trim().to_string())),
         }
     }
 }
@@ -47,10 +61,10 @@ impl TryFrom<&str> for RegularKind {
         RegularKind::try_from_str(s)
     }
 }
-/// Implement From<RegularKind> for &'static str
-impl From<RegularKind> for &'static str {
+/// Implement From<RegularKind> for String
+impl From<RegularKind> for String {
     fn from(t: RegularKind) -> Self {
-        t.as_str()
+        t.as_str().to_string()
     }
 }
 /// Implement Display for RegularKind
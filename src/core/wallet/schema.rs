@@ -0,0 +1,102 @@
+// src/core/wallet/schema.rs
+
+use serde::Serialize;
+
+use super::codexi::Codexi;
+use super::file_management::{CSV_COLUMNS, DATA_VERSION};
+use super::operation::{AdjustmentInfo, Operation};
+use super::operation_flow::OperationFlow;
+use super::regular_kind::RegularKind;
+use super::system_kind::SystemKind;
+
+/// Struct describing one field of the data model
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    pub optional: bool,
+    pub description: &'static str,
+}
+/// Struct describing the valid kinds
+#[derive(Debug, Clone, Serialize)]
+pub struct KindSchema {
+    pub system: Vec<String>,
+    pub regular: Vec<String>,
+    /// How a custom category is written
+    pub custom: &'static str,
+    /// How a kind is written in each format
+    pub encoding: &'static str,
+}
+/// Struct describing the data model (codexi, operations, csv columns, kinds and flows)
+#[derive(Debug, Clone, Serialize)]
+pub struct DataSchema {
+    pub data_version: u16,
+    pub codexi: Vec<FieldSchema>,
+    pub operation: Vec<FieldSchema>,
+    pub adjustment: Vec<FieldSchema>,
+    pub csv_columns: Vec<&'static str>,
+    pub kinds: KindSchema,
+    pub flows: Vec<&'static str>,
+}
+/// Methods for DataSchema
+impl DataSchema {
+
+    /// Get the schema of the current data model, read from the type definitions
+    pub fn current() -> Self {
+        Self {
+            data_version: DATA_VERSION,
+            codexi: Codexi::FIELDS.to_vec(),
+            operation: Operation::FIELDS.to_vec(),
+            adjustment: AdjustmentInfo::FIELDS.to_vec(),
+            csv_columns: CSV_COLUMNS.to_vec(),
+            kinds: KindSchema {
+                // The names as written in the files (serde variant names)
+                system: SystemKind::ALL.iter().map(|k| format!("{:?}", k)).collect(),
+                regular: RegularKind::BUILTIN.iter().map(|k| format!("{:?}", k)).collect(),
+                custom: "any other name is a custom category (ex: Salary)",
+                encoding: "json/toml: {\"Regular\": \"Fee\"}, {\"Regular\": {\"Custom\": \"Salary\"}}, {\"System\": \"Close\"}; csv: the name only",
+            },
+            flows: OperationFlow::ALL.iter().map(|f| f.as_str()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use anyhow::Result;
+    use super::super::amount::Amount;
+    use super::super::operation_kind::OperationKind;
+
+    /// Names of the fields of a serialized value
+    fn keys(value: serde_json::Value) -> Vec<String> {
+        value.as_object().map(|o| o.keys().cloned().collect()).unwrap_or_default()
+    }
+    /// Names of the fields of a schema
+    fn names(fields: &[FieldSchema]) -> Vec<String> {
+        let mut names: Vec<String> = fields.iter().map(|f| f.name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_schema_matches_the_types() -> Result<()> {
+        let op = Operation::new(OperationKind::Regular(RegularKind::Fee), OperationFlow::Debit, "2025-11-01", 1.5, "Bank fee")?;
+        let adjustment = AdjustmentInfo { previous_balance: Amount::from(1.0), physical_balance: Amount::from(2.0), deviation: Amount::from(1.0) };
+        let codexi = Codexi { operations: vec![op.clone()], ..Default::default() };
+
+        assert_eq!(names(Operation::FIELDS), keys(serde_json::to_value(&op)?), "Operation::FIELDS is out of sync.");
+        assert_eq!(names(AdjustmentInfo::FIELDS), keys(serde_json::to_value(adjustment)?), "AdjustmentInfo::FIELDS is out of sync.");
+        assert_eq!(names(Codexi::FIELDS), keys(serde_json::to_value(&codexi)?), "Codexi::FIELDS is out of sync.");
+
+        let dir = tempfile::tempdir()?;
+        let csv_path = dir.path().join("schema.csv");
+        Codexi::export_operations(&[op], &csv_path, crate::core::helpers::JsonStyle::Pretty)?;
+        let header = std::fs::read_to_string(&csv_path)?.lines().next().unwrap_or_default().to_string();
+        assert_eq!(header, CSV_COLUMNS.join(","), "CSV_COLUMNS is out of sync.");
+
+        Ok(())
+    }
+}
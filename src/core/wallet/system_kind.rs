@@ -19,6 +19,8 @@ pub enum SystemKind {
 }
 /// Methods for SystemKind
 impl SystemKind {
+    /// All the system kinds
+    pub const ALL: [SystemKind; 3] = [SystemKind::Init, SystemKind::Adjust, SystemKind::Close];
     /// Get the string representation of the specific system kind
     pub fn as_str(&self) -> &'static str {
         match self {
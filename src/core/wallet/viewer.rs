@@ -1,38 +1,139 @@
 // src/core/wallet/viewer.rs
 
-use thousands::Separable;
+use chrono::NaiveDate;
 use owo_colors::{OwoColorize, Style};
 
 use super::codexi::Codexi;
 use super::codexi::SearchItem;
+use super::codexi::SearchPage;
 use super::codexi::BalanceResult;
+use super::codexi::BalanceCompareResult;
+use super::codexi::MonthlyBalance;
+use super::codexi::CloseEntry;
+use super::codexi::ActivityResult;
+use super::codexi::DiscretionaryResult;
+use super::codexi::PayeeBreakdown;
+use super::codexi::CategoryTotal;
+use super::codexi::StatsResult;
+use super::recurring::RecurringRules;
+use super::codexi::GapEntry;
+use super::codexi::ClosePreview;
+use super::codexi::GoalResult;
 use super::codexi::ResumeResult;
+use super::diff::DiffEntry;
+use super::schema::DataSchema;
+use super::file_management::SnapshotInfo;
+use super::file_management::ImportSummary;
+use super::amount::Amount;
+use super::operation::Operation;
 use super::operation_flow::OperationFlow;
+use crate::core::helpers::{paint, Formatter};
+
+/// Default width of the description column of the search view
+pub const DEFAULT_DESC_WIDTH: usize = 30;
+/// Minimum width of the description column of the search view (fits the "Description" header)
+const MIN_DESC_WIDTH: usize = 11;
+/// Width of the label cell of the search totals
+const SEARCH_TOTALS_LABEL: usize = 26;
+/// Minimum inner width of the search view: the totals label and amount cells, and a last cell
+const SEARCH_TOTALS_WIDTH: usize = SEARCH_TOTALS_LABEL + 1 + 18 + 2;
+
+/// Enum for the columns of the search view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchColumn {
+    Index,
+    Date,
+    Flow,
+    Amount,
+    /// Running balance of the whole codexi (chronological, not of the filtered operations)
+    Balance,
+    Description,
+}
+/// Methods for SearchColumn
+impl SearchColumn {
+    /// Columns of the search view by default, in order
+    pub const DEFAULT: [SearchColumn; 6] = [
+        SearchColumn::Index,
+        SearchColumn::Date,
+        SearchColumn::Flow,
+        SearchColumn::Amount,
+        SearchColumn::Balance,
+        SearchColumn::Description,
+    ];
+    /// Width of the column (the description width is given by --desc-width)
+    fn width(&self, desc_width: usize) -> usize {
+        match self {
+            SearchColumn::Index | SearchColumn::Flow => 7,
+            SearchColumn::Date => 10,
+            SearchColumn::Amount | SearchColumn::Balance => 18,
+            SearchColumn::Description => desc_width,
+        }
+    }
+    /// Header of the column, padded to its width
+    fn header(&self, width: usize) -> String {
+        match self {
+            SearchColumn::Index => format!("{:<width$}", "Index"),
+            SearchColumn::Date => format!("{:<width$}", "Date"),
+            SearchColumn::Flow => format!("{:<width$}", " Type"),
+            SearchColumn::Amount => format!("{:>width$}", "Montant"),
+            SearchColumn::Balance => format!("{:>width$}", "Balance"),
+            SearchColumn::Description => format!("{:<width$}", "Description"),
+        }
+    }
+    /// Cell of an operation in the column, padded to its width
+    fn cell(&self, item: &SearchItem, width: usize, fmt: &Formatter) -> String {
+        match self {
+            SearchColumn::Index => {
+                // A starred operation gets a marker after its index
+                let (index_str, index_style) = if item.op.flagged {
+                    (format!("#{} ★", item.index), Style::new().yellow().bold())
+                } else {
+                    (format!("#{}", item.index), Style::new().dimmed())
+                };
+                format!("{:<width$}", index_str.style(paint(index_style)))
+            }
+            SearchColumn::Date => format!("{:<width$}", item.op.date.format("%Y-%m-%d").to_string()),
+            SearchColumn::Flow => format!("{:<width$}", item.op.flow.as_str()),
+            SearchColumn::Amount => {
+                // Determine the color according to the flow (credit/debit)
+                let amount_style = match item.op.flow {
+                    OperationFlow::Credit => Style::new().green(),
+                    OperationFlow::Debit  => Style::new().red(),
+                    OperationFlow::None   => Style::new().dimmed(),
+                };
+                format!("{:>width$}", fmt.amount(item.op.amount).style(paint(amount_style)))
+            }
+            SearchColumn::Balance => format!("{:>width$}", fmt.amount(item.balance).style(paint(Style::new().yellow()))),
+            SearchColumn::Description => Codexi::truncate_desc(&item.op.description, width),
+        }
+    }
+}
 
 /// Methods for viewing codexi data
 impl Codexi {
     /// view to list the snapshot file
-    pub fn view_snapshot(datas: &[String]) {
-        println!("┌─────────────────────────────┐");
+    pub fn view_snapshot(datas: &[SnapshotInfo]) {
+        println!("┌─────────────────────────────┬────────────────────────────────────────┐");
         let title_text = format!("{:<28}", "Snapshot(s)");
-        println!("│ {}│", title_text.cyan().bold());
-        println!("├─────────────────────────────┤");
-        if datas.len() == 0 {
-            println!("│ {:<28}│", "No snapshot");
+        println!("│ {}│ {:<39}│", title_text.style(paint(Style::new().cyan().bold())), "Note".style(paint(Style::new().cyan().bold())));
+        println!("├─────────────────────────────┼────────────────────────────────────────┤");
+        if datas.is_empty() {
+            println!("│ {:<28}│ {:<39}│", "No snapshot", "");
         } else {
             for f in datas {
-                println!("│ {:<28}│", f);
+                let note = f.note.as_deref().unwrap_or("");
+                println!("│ {:<28}│ {}│", f.file_name, Self::truncate_desc(note, 39).style(paint(Style::new().dimmed())));
             }
         }
-        println!("└─────────────────────────────┘");
+        println!("└─────────────────────────────┴────────────────────────────────────────┘");
     }
     /// view to list the archive file
     pub fn view_archive(datas: &[String]) {
         println!("┌─────────────────────────────┐");
         let title_text = format!("{:<28}", "Archive(s)");
-        println!("│ {}│", title_text.cyan().bold());
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
         println!("├─────────────────────────────┤");
-        if datas.len() == 0 {
+        if datas.is_empty() {
             println!("│ {:<28}│", "No archive");
         } else {
             for f in datas {
@@ -41,62 +142,531 @@ impl Codexi {
         }
         println!("└─────────────────────────────┘");
     }
+    /// view the details of one operation, with its id
+    pub fn view_operation(index: usize, op: &Operation, fmt: &Formatter) {
+        println!("┌───────────────────────────────────────────────────────┐");
+        let title_text = format!("{:<54}", format!("Operation #{}", index));
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────────┬──────────────────────────────────────────┤");
+        println!("│Id          │ {:<41}│", op.short_id().style(paint(Style::new().yellow())));
+        println!("│Full id     │ {:<41}│", op.id.to_string().style(paint(Style::new().dimmed())));
+        println!("│Date        │ {:<41}│", op.date.format("%Y-%m-%d"));
+        println!("│Kind        │ {:<41}│", op.kind.to_string().trim());
+        println!("│Flow        │ {:<41}│", op.flow.as_str());
+        println!("│Amount      │ {:<41}│", fmt.amount(op.amount));
+        println!("│Description │ {:<41}│", Self::truncate_desc(&op.description, 41));
+        if let Some(category) = op.category.as_deref() {
+            println!("│Category    │ {}│", Self::truncate_desc(category, 41));
+        }
+        if let Some(adjustment) = op.adjustment {
+            println!("│Deviation   │ {:<41}│", fmt.signed_amount(adjustment.deviation));
+        }
+        if op.flagged {
+            println!("│Flagged     │ {:<41}│", "★ yes".style(paint(Style::new().yellow())));
+        }
+        println!("└────────────┴──────────────────────────────────────────┘");
+    }
     /// view the balance (credit/debit/balance)
-    pub fn view_balance(balance: &BalanceResult) {
+    pub fn view_balance(balance: &BalanceResult, title: Option<&str>, fmt: &Formatter) {
         println!("┌───────────────────────────┐");
-        println!("│ {}    │", "codexi balance summary".cyan().bold());
+        let label = if title.is_some() { "balance" } else { "codexi balance summary" };
+        let title_text = Self::title_text(title, label, 26);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
         println!("├────────┬──────────────────┤");
-        println!("│Credit  │{:>18}│", format!("{:.2}", balance.credit).separate_with_commas().green());
-        println!("│Debit   │{:>18}│", format!("{:.2}", balance.debit).separate_with_commas().red());
-        println!("│Balance │{:>18}│", format!("{:.2}", balance.total).separate_with_commas().yellow().bold());
+        println!("│Credit  │{:>18}│", fmt.amount(balance.credit).style(paint(Style::new().green())));
+        println!("│Debit   │{:>18}│", fmt.amount(balance.debit).style(paint(Style::new().red())));
+        println!("│Balance │{:>18}│", fmt.amount(balance.total).style(paint(Style::new().yellow().bold())));
+        if let Some(activity) = balance.activity {
+            println!("│Activity│{:>18}│", fmt.amount(activity).style(paint(Style::new().cyan())));
+        }
+        println!("│Count   │{:>18}│", balance.count);
+        if let Some(average) = &balance.average {
+            println!("├────────┴──────────────────┤");
+            println!("│{:<27}│", format!("Spend over {} day(s)", average.days).style(paint(Style::new().blue().italic())));
+            println!("├────────┬──────────────────┤");
+            println!("│Per day │{:>18}│", fmt.amount(average.per_day).style(paint(Style::new().red())));
+            println!("│Per mon.│{:>18}│", fmt.amount(average.per_month).style(paint(Style::new().red())));
+        }
         println!("└────────┴──────────────────┘");
     }
-    /// view of the search results
-    pub fn view_search(rows: &[SearchItem]){
-        println!("┌───────────────────────────────────────────────────────────────────────────────────────────────┐");
-        let title_text = format!("{:<94}", "Operation(s)");
-        println!("│ {}│", title_text.bold().cyan());
-        println!("├───────┬──────────┬───────┬──────────────────┬──────────────────┬──────────────────────────────┤");
-        println!("│Index  │Date      │ Type  │           Montant│           Balance│Description                   │");
-        println!("├───────┼──────────┼───────┼──────────────────┼──────────────────┼──────────────────────────────┤");
+    /// view the balance of a period compared to the previous one
+    pub fn view_balance_compare(compare: &BalanceCompareResult, fmt: &Formatter) {
+        let no_data = compare.previous.credit.is_zero() && compare.previous.debit.is_zero();
 
-        for item in rows {
-            // Determine the color according to the flow (credit/debit)
-            let amount_str = format!("{:.2}", item.op.amount).separate_with_commas();
-            let amount_style = match item.op.flow {
-                OperationFlow::Credit => Style::new().green(),
-                OperationFlow::Debit  => Style::new().red(),
-                OperationFlow::None   => Style::new().dimmed(),
+        println!("┌─────────────────────────────────────────────────────────────────────────────┐");
+        let title_text = format!("{:<76}", format!("codexi balance {} vs {}", compare.period, compare.previous_period));
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────┬──────────────────┬──────────────────┬──────────────────┬───────────┤");
+        println!("│        │{:>18}│{:>18}│{:>18}│{:>11}│", compare.period, compare.previous_period, "Change", "%");
+        println!("├────────┼──────────────────┼──────────────────┼──────────────────┼───────────┤");
+
+        let rows = [
+            ("Credit", compare.current.credit, compare.previous.credit),
+            ("Debit", compare.current.debit, compare.previous.debit),
+            ("Balance", compare.current.total, compare.previous.total),
+        ];
+
+        for (label, current, previous) in rows {
+            let change = current - previous;
+            let change_str = fmt.signed_amount(change);
+            let change_style = if change.is_positive() {
+                Style::new().green()
+            } else if change.is_negative() {
+                Style::new().red()
+            } else {
+                Style::new().dimmed()
+            };
+            let colored_change = change_str.style(paint(change_style));
+            let percent = if no_data || previous.is_zero() {
+                "n/a".to_string()
+            } else {
+                format!("{:+.1}%", change.to_f64() / previous.abs().to_f64() * 100.0)
+            };
+
+            println!("│{:<8}│{:>18}│{:>18}│{:>18}│{:>11}│",
+                label,
+                fmt.amount(current),
+                fmt.amount(previous),
+                colored_change,
+                percent);
+        }
+
+        println!("└────────┴──────────────────┴──────────────────┴──────────────────┴───────────┘");
+        if no_data {
+            println!();
+            println!("{}", format!("Note: no operation found for {}, nothing to compare with.", compare.previous_period).style(paint(Style::new().blue().italic())));
+        }
+        println!();
+    }
+    /// view the balance of each month, with the trend against the previous month
+    pub fn view_monthly(months: &[MonthlyBalance], trend: bool, title: Option<&str>, fmt: &Formatter) {
+        println!("┌──────────────────────────────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi monthly balance", 81);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────┬──────────────────┬──────────────────┬──────────────────┬───────────────┤");
+        println!("│Month   │{:>18}│{:>18}│{:>18}│{:>15}│", "Credit", "Debit", "Net", if trend { "Trend" } else { "" });
+        println!("├────────┼──────────────────┼──────────────────┼──────────────────┼───────────────┤");
+
+        let mut previous: Option<Amount> = None;
+        for m in months {
+            let net = m.balance.total;
+            let trend_str = match (trend, previous) {
+                (true, Some(prev)) => Self::trend_text(net, prev),
+                _ => format!("{:>15}", ""),
             };
-            let colored_amount = amount_str.style(amount_style);
 
-            let index_style = Style::new().dimmed();
-            let index_str = format!("#{}", item.index);
-            let colored_index = index_str.style(index_style);
+            println!("│{:<8}│{:>18}│{:>18}│{:>18}│{}│",
+                m.month,
+                fmt.amount(m.balance.credit).style(paint(Style::new().green())),
+                fmt.amount(m.balance.debit).style(paint(Style::new().red())),
+                fmt.amount(net).style(paint(Style::new().yellow().bold())),
+                trend_str);
+            previous = Some(net);
+        }
+
+        println!("└────────┴──────────────────┴──────────────────┴──────────────────┴───────────────┘");
+        println!();
+    }
+    /// view the history of the period closes, with the change between carry-forwards
+    pub fn view_closes(entries: &[CloseEntry], title: Option<&str>, fmt: &Formatter) {
+        println!("┌─────────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi closes", 60);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────────┬──────────┬──────────────────┬──────────────────┤");
+        println!("│Close date  │{:>10}│{:>18}│{:>18}│", "Archived", "Carry-forward", "Period net");
+        println!("├────────────┼──────────┼──────────────────┼──────────────────┤");
+
+        for e in entries {
+            let archived = e.archived.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+            let delta = match e.delta {
+                Some(d) if d.is_negative() => format!("{:>18}", fmt.amount(d)).style(paint(Style::new().red())).to_string(),
+                Some(d) => format!("{:>18}", fmt.signed_amount(d)).style(paint(Style::new().green())).to_string(),
+                None => format!("{:>18}", ""),
+            };
+
+            println!("│{:<12}│{:>10}│{:>18}│{}│",
+                e.date.format("%Y-%m-%d"),
+                archived,
+                fmt.amount(e.carry_forward).style(paint(Style::new().yellow().bold())),
+                delta);
+        }
+
+        if entries.is_empty() {
+            println!("│{:<61}│", " No close yet.".style(paint(Style::new().blue().italic())));
+        }
+        println!("└────────────┴──────────┴──────────────────┴──────────────────┘");
+        println!();
+    }
+    /// view the progress toward a savings goal
+    pub fn view_goal(goal: &GoalResult, fmt: &Formatter) {
+        const BAR_WIDTH: usize = 40;
+        let filled = ((goal.percent.clamp(0.0, 100.0) / 100.0) * BAR_WIDTH as f64).round() as usize;
+
+        println!("┌──────────────────────────────────────────┐");
+        println!("│ {}│", format!("{:<41}", "savings goal").style(paint(Style::new().cyan().bold())));
+        println!("├────────┬─────────────────────────────────┤");
+        println!("│Balance │{:>33}│", fmt.amount(goal.balance).style(paint(Style::new().yellow().bold())));
+        println!("│Target  │{:>33}│", fmt.amount(goal.target));
+        println!("│Missing │{:>33}│", fmt.amount(goal.remaining).style(paint(Style::new().red())));
+        println!("│Done    │{:>33}│", format!("{:.1} %", goal.percent));
+        println!("├────────┴─────────────────────────────────┤");
+        println!("│ {}{} │", "█".repeat(filled).style(paint(Style::new().green())), "░".repeat(BAR_WIDTH - filled));
+        println!("└──────────────────────────────────────────┘");
+
+        match (goal.months_to_goal, goal.monthly_average) {
+            (Some(0), _) => println!("{}", "Goal reached!".style(paint(Style::new().green().bold()))),
+            (Some(months), Some(average)) => println!("About {} month(s) to the goal at {} per month.", months, fmt.amount(average)),
+            (_, Some(average)) => println!("{}", format!("The monthly trend is not positive ({} per month), the goal is not getting closer.", fmt.signed_amount(average)).style(paint(Style::new().yellow()))),
+            (_, None) => println!("{}", "No regular operation yet to estimate the time to the goal.".style(paint(Style::new().blue().italic()))),
+        }
+        println!();
+    }
+    /// view the preview of a period close (None: nothing to close at this date)
+    pub fn view_close_preview(date: &str, preview: Option<&ClosePreview>, fmt: &Formatter) {
+        println!("┌──────────────────────────────────────────┐");
+        println!("│ {}│", format!("{:<41}", format!("projected close at {}", date)).style(paint(Style::new().cyan().bold())));
+        println!("├────────────────────┬─────────────────────┤");
+        match preview {
+            Some(preview) => {
+                println!("│Carry-forward       │{:>21}│", fmt.amount(preview.carry_forward).style(paint(Style::new().yellow().bold())));
+                println!("│Operations archived │{:>21}│", preview.archived);
+            }
+            None => println!("│{:<42}│", " Nothing to close at this date.".style(paint(Style::new().blue().italic()))),
+        }
+        println!("└────────────────────┴─────────────────────┘");
+        println!();
+    }
+    /// view the activity statistics (operations per day)
+    pub fn view_activity(activity: &ActivityResult, title: Option<&str>, fmt: &Formatter) {
+        println!("┌──────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi activity", 41);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────────────────┬─────────────────────┤");
+
+        match activity.busiest_day {
+            Some((busiest_date, busiest_count)) => {
+                let highest_spend = match activity.highest_spend_day {
+                    Some((date, amount)) => format!("{} {:>10}", date.format("%Y-%m-%d"), fmt.amount(amount)),
+                    None => "-".to_string(),
+                };
+                println!("│Operations          │{:>21}│", activity.count);
+                println!("│Active days         │{:>21}│", activity.active_days);
+                println!("│Busiest day         │{:>21}│", format!("{} ({} op.)", busiest_date.format("%Y-%m-%d"), busiest_count).style(paint(Style::new().yellow())));
+                println!("│Highest spend day   │{:>21}│", highest_spend.style(paint(Style::new().red())));
+                println!("│Average / active day│{:>21.2}│", activity.average_per_day);
+                println!("│Median / active day │{:>21.1}│", activity.median_per_day);
+            }
+            None => println!("│{:<42}│", " No activity in this period.".style(paint(Style::new().blue().italic()))),
+        }
+        println!("└────────────────────┴─────────────────────┘");
+    }
+    /// view the discretionary spend
+    pub fn view_discretionary(result: &DiscretionaryResult, title: Option<&str>, fmt: &Formatter) {
+        println!("┌──────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi discretionary spend", 41);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├────────────────────┬─────────────────────┤");
+        println!("│Spend               │{:>21}│", fmt.amount(result.spend).style(paint(Style::new().red())));
+        println!("│Refunds             │{:>21}│", fmt.amount(result.refunds).style(paint(Style::new().green())));
+        println!("│Discretionary       │{:>21}│", fmt.amount(result.total).style(paint(Style::new().yellow().bold())));
+        println!("├────────────────────┼─────────────────────┤");
+        println!("│Operations counted  │{:>21}│", result.count);
+        println!("│Operations ignored  │{:>21}│", result.ignored);
+        println!("└────────────────────┴─────────────────────┘");
+        println!("{}", " Transactions and custom categories, minus refunds (fees, transfers and system excluded).".style(paint(Style::new().dimmed())));
+    }
+    /// view the spend per payee
+    pub fn view_payee_breakdown(breakdown: &PayeeBreakdown, title: Option<&str>, fmt: &Formatter) {
+        println!("┌──────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi spend per payee", 57);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├──────────────────────────────┬──────┬────────────────────┤");
+        println!("│{:<30}│{:>6}│{:>20}│", "Payee", "Count", "Total");
+        println!("├──────────────────────────────┼──────┼────────────────────┤");
+        if breakdown.payees.is_empty() && breakdown.other.count == 0 {
+            println!("│{:<30}│{:>6}│{:>20}│", " No spend in this period.".style(paint(Style::new().blue().italic())), "", "");
+        }
+        for payee in &breakdown.payees {
+            println!("│{}│{:>6}│{:>20}│", Self::truncate_desc(&payee.payee, 30), payee.count, fmt.amount(payee.total).style(paint(Style::new().red())));
+        }
+        if breakdown.other.count > 0 {
+            println!("│{}│{:>6}│{:>20}│", Self::truncate_desc(&breakdown.other.payee, 30).style(paint(Style::new().dimmed())), breakdown.other.count, fmt.amount(breakdown.other.total).style(paint(Style::new().red())));
+        }
+        println!("├──────────────────────────────┴──────┼────────────────────┤");
+        println!("│{:<37}│{:>20}│", "Total spend", fmt.amount(breakdown.total).style(paint(Style::new().yellow().bold())));
+        println!("└─────────────────────────────────────┴────────────────────┘");
+        println!("{}", " Debits of the regular operations, the payee is the description before \" - \".".style(paint(Style::new().dimmed())));
+    }
+    /// view the credit and debit per category
+    pub fn view_categories(categories: &[CategoryTotal], title: Option<&str>, fmt: &Formatter) {
+        println!("┌───────────────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi per category", 66);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├──────────────────────────────┬──────┬──────────────┬──────────────┤");
+        println!("│{:<30}│{:>6}│{:>14}│{:>14}│", "Category", "Count", "Credit", "Debit");
+        println!("├──────────────────────────────┼──────┼──────────────┼──────────────┤");
+        if categories.is_empty() {
+            println!("│{:<30}│{:>6}│{:>14}│{:>14}│", " No operation in this period.".style(paint(Style::new().blue().italic())), "", "", "");
+        }
+        for category in categories {
+            println!("│{}│{:>6}│{:>14}│{:>14}│",
+                Self::truncate_desc(&category.category, 30),
+                category.count,
+                fmt.amount(category.credit).style(paint(Style::new().green())),
+                fmt.amount(category.debit).style(paint(Style::new().red())));
+        }
+        println!("├──────────────────────────────┼──────┼──────────────┼──────────────┤");
+        println!("│{:<30}│{:>6}│{:>14}│{:>14}│",
+            "Total",
+            categories.iter().map(|c| c.count).sum::<usize>(),
+            fmt.amount(categories.iter().map(|c| c.credit).sum()).style(paint(Style::new().green().bold())),
+            fmt.amount(categories.iter().map(|c| c.debit).sum()).style(paint(Style::new().red().bold())));
+        println!("└──────────────────────────────┴──────┴──────────────┴──────────────┘");
+        println!("{}", " Regular operations, grouped by their category (see --category of debit and credit).".style(paint(Style::new().dimmed())));
+    }
+    /// view the distribution of the credit and debit amounts
+    pub fn view_stats(stats: &StatsResult, title: Option<&str>, fmt: &Formatter) {
+        println!("┌─────────────────────────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi stats", 76);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├──────────┬──────┬──────────────┬──────────────┬──────────────┬──────────────┤");
+        println!("│{:<10}│{:>6}│{:>14}│{:>14}│{:>14}│{:>14}│", "Flow", "Count", "Mean", "Median", "Min", "Max");
+        println!("├──────────┼──────┼──────────────┼──────────────┼──────────────┼──────────────┤");
+        for (label, flow_stats, amount_style) in [
+            ("Credit", &stats.credit, Style::new().green()),
+            ("Debit", &stats.debit, Style::new().red()),
+        ] {
+            match flow_stats {
+                Some(f) => println!("│{:<10}│{:>6}│{:>14}│{:>14}│{:>14}│{:>14}│",
+                    label,
+                    f.count,
+                    fmt.amount(f.mean).style(paint(amount_style)),
+                    fmt.amount(f.median).style(paint(amount_style)),
+                    fmt.amount(f.min).style(paint(amount_style)),
+                    fmt.amount(f.max).style(paint(amount_style))),
+                None => println!("│{:<10}│{:>6}│{:<59}│", label, 0, " No operation in this period.".style(paint(Style::new().blue().italic()))),
+            }
+        }
+        println!("├──────────┴──────┴──────────────┴──────────────┴──────────────┴──────────────┤");
+        let largest = match &stats.largest {
+            Some(op) => format!("Largest: {} {} {} {}", op.date.format("%Y-%m-%d"), op.flow.as_str(), fmt.amount(op.amount), op.description),
+            None => "Largest: none".to_string(),
+        };
+        println!("│{}│", Self::truncate_desc(&largest, 77).style(paint(Style::new().bold())));
+        println!("└─────────────────────────────────────────────────────────────────────────────┘");
+        println!("{}", " Regular operations only, the system operations (init, adjust, close) are left out.".style(paint(Style::new().dimmed())));
+    }
+    /// view the recurring rules
+    pub fn view_recurring(rules: &RecurringRules, fmt: &Formatter) {
+        println!("┌─────────────────────────────────────────────────────────────────────────────┐");
+        println!("│ {}│", format!("{:<76}", "codexi recurring operations").style(paint(Style::new().cyan().bold())));
+        println!("├───┬────┬───────┬────────────┬──────────┬──────────┬─────────────────────────┤");
+        println!("│{:>3}│{:>4}│{:<7}│{:>12}│{:<10}│{:<10}│{:<25}│", "#", "Day", "Flow", "Amount", "Start", "Latest", "Description");
+        println!("├───┼────┼───────┼────────────┼──────────┼──────────┼─────────────────────────┤");
+        if rules.rules.is_empty() {
+            println!("│{:<77}│", " No recurring operation yet (see recurring add).".style(paint(Style::new().blue().italic())));
+        }
+        for (index, rule) in rules.rules.iter().enumerate() {
+            let amount_style = match rule.flow {
+                OperationFlow::Credit => Style::new().green(),
+                _ => Style::new().red(),
+            };
+            println!("│{:>3}│{:>4}│{:<7}│{:>12}│{}│{}│{}│",
+                index,
+                rule.day,
+                rule.flow.as_str(),
+                fmt.amount(rule.amount).style(paint(amount_style)),
+                rule.start.format("%Y-%m-%d"),
+                Self::fmt_opt_date(rule.last_generated).style(paint(Style::new().dimmed())),
+                Self::truncate_desc(&rule.description, 25));
+        }
+        println!("└───┴────┴───────┴────────────┴──────────┴──────────┴─────────────────────────┘");
+    }
+    /// view the running balance of the filtered operations (bank-statement view)
+    pub fn view_running_balance(items: &[SearchItem], title: Option<&str>, fmt: &Formatter) {
+        println!("┌───────────────────────────────────────────────────────────────────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi running balance", 94);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├──────┬──────────┬──────────────┬──────────────┬────────────────┬──────────────────────────────┤");
+        println!("│{:<6}│{:<10}│{:>14}│{:>14}│{:>16}│{:<30}│", "Index", "Date", "Credit", "Debit", "Balance", "Description");
+        println!("├──────┼──────────┼──────────────┼──────────────┼────────────────┼──────────────────────────────┤");
+        if items.is_empty() {
+            println!("│{:<95}│", " No operation in this period.".style(paint(Style::new().blue().italic())));
+        }
+        for item in items {
+            let (credit, debit) = match item.op.flow {
+                OperationFlow::Credit => (fmt.amount(item.op.amount), String::new()),
+                OperationFlow::Debit => (String::new(), fmt.amount(item.op.amount)),
+                OperationFlow::None => (String::new(), String::new()),
+            };
+            println!("│{:<6}│{}│{:>14}│{:>14}│{:>16}│{}│",
+                format!("#{}", item.index).style(paint(Style::new().dimmed())),
+                item.op.date.format("%Y-%m-%d"),
+                credit.style(paint(Style::new().green())),
+                debit.style(paint(Style::new().red())),
+                fmt.amount(item.balance).style(paint(Style::new().yellow())),
+                Self::truncate_desc(&item.op.description, 30));
+        }
+        let last = items.last().map_or(Amount::ZERO, |item| item.balance);
+        println!("├──────┴──────────┴──────────────┴──────────────┼────────────────┼──────────────────────────────┤");
+        println!("│{:<47}│{:>16}│{:<30}│", "Balance of the period", fmt.amount(last).style(paint(Style::new().yellow().bold())), "");
+        println!("└───────────────────────────────────────────────┴────────────────┴──────────────────────────────┘");
+        println!("{}", " The balance is the cumulative net of the listed operations (see search for the balance of the whole codexi).".style(paint(Style::new().dimmed())));
+    }
+    /// view the gaps in the records
+    pub fn view_gaps(gaps: &[GapEntry], max_gap: i64, title: Option<&str>) {
+        println!("┌──────────────────────────────────┐");
+        let title_text = Self::title_text(title, "codexi gaps", 33);
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        println!("├──────────┬──────────┬────────────┤");
+        println!("│{:<10}│{:<10}│{:>12}│", "From", "To", "Days");
+        println!("├──────────┼──────────┼────────────┤");
+        if gaps.is_empty() {
+            println!("│{:<34}│", format!(" No gap over {} days.", max_gap).style(paint(Style::new().blue().italic())));
+        }
+        for gap in gaps {
+            println!("│{}│{}│{:>12}│", gap.start.format("%Y-%m-%d"), gap.end.format("%Y-%m-%d"), gap.days.style(paint(Style::new().yellow())));
+        }
+        println!("└──────────┴──────────┴────────────┘");
+    }
+    /// view the data model (fields, csv columns, kinds and flows)
+    pub fn view_schema(schema: &DataSchema) {
+        let sections = [("codexi", &schema.codexi), ("operation", &schema.operation), ("adjustment", &schema.adjustment)];
 
-            println!(
-                "│{:<7}│{}│{}│{:>18}│{:>18}│{:<30}│",
-                colored_index,
-                item.op.date,
-                item.op.flow,
-                colored_amount,
-                format!("{:.2}", item.balance).separate_with_commas().yellow(),
-                Self::truncate_desc(&item.op.description, 30),
-            );
+        println!("┌─────────────────────────────────────────────────────────────────────────────────────────────────────┐");
+        let title_text = format!("{:<100}", format!("codexi data model (version {})", schema.data_version));
+        println!("│ {}│", title_text.style(paint(Style::new().cyan().bold())));
+        for (i, (name, fields)) in sections.into_iter().enumerate() {
+            let joint = if i == 0 { "┬" } else { "┼" };
+            println!("├──────────────────{j}────────────────────{j}──────────{j}──────────────────────────────────────────────────┤", j = joint);
+            println!("│{:<18}│{:<20}│{:<10}│{:<50}│", name.style(paint(Style::new().yellow().bold())), "Type", "Optional", "Description");
+            println!("├──────────────────┼────────────────────┼──────────┼──────────────────────────────────────────────────┤");
+            for f in fields.iter() {
+                println!("│{:<18}│{:<20}│{:<10}│{}│",
+                    f.name,
+                    f.type_name,
+                    if f.optional { "yes" } else { "no" },
+                    Self::truncate_desc(f.description, 50).style(paint(Style::new().dimmed())));
+            }
         }
+        println!("└──────────────────┴────────────────────┴──────────┴──────────────────────────────────────────────────┘");
+        println!();
+        println!("{} {}", "CSV columns:".style(paint(Style::new().bold())), schema.csv_columns.join(","));
+        println!("{} {}", "System kinds:".style(paint(Style::new().bold())), schema.kinds.system.join(", "));
+        println!("{} {} ({})", "Regular kinds:".style(paint(Style::new().bold())), schema.kinds.regular.join(", "), schema.kinds.custom);
+        println!("{} {}", "Kind encoding:".style(paint(Style::new().bold())), schema.kinds.encoding);
+        println!("{} {}", "Flows:".style(paint(Style::new().bold())), schema.flows.join(", "));
+        println!();
+    }
+    /// Format the trend of a net against the previous one (ex: "▲ +12.5%"), 15 characters wide
+    fn trend_text(net: Amount, previous: Amount) -> String {
+        let change = net - previous;
+        let percent = if previous.is_zero() {
+            "n/a".to_string()
+        } else {
+            format!("{:+.1}%", change.to_f64() / previous.abs().to_f64() * 100.0)
+        };
+
+        let (arrow, style) = if change.is_positive() {
+            ("▲", Style::new().green())
+        } else if change.is_negative() {
+            ("▼", Style::new().red())
+        } else {
+            ("=", Style::new().dimmed())
+        };
+        format!("{:>15}", format!("{} {}", arrow, percent)).style(paint(style)).to_string()
+    }
+    /// view of the search results
+    /// The description column is `desc_width` characters wide (at least MIN_DESC_WIDTH).
+    /// `columns` gives the columns and their order (empty: SearchColumn::DEFAULT).
+    /// With a page, the footer gives its position in the results (the totals are the ones of the page).
+    pub fn view_search(rows: &[SearchItem], title: Option<&str>, desc_width: usize, columns: &[SearchColumn], page: Option<&SearchPage>, fmt: &Formatter) {
+        print!("{}", Self::render_search(rows, title, desc_width, columns, fmt));
 
         let note_style = Style::new().blue().italic();
+        let w = desc_width.max(MIN_DESC_WIDTH);
 
-        println!("└───────┴──────────┴───────┴──────────────────┴──────────────────┴──────────────────────────────┘");
         println!();
-        println!("Total operations found: {}", rows.len());
+        match page {
+            Some(page) => println!("{}", Self::page_footer(page)),
+            None => println!("Total operations found: {}", rows.len()),
+        }
         println!();
-        println!("{}", "Note: Descriptions longer than 30 characters are truncated with '...'.".style(note_style));
-        println!("{}", "Remember to regularly perform closing operations to maintain accurate financial records.".style(note_style));
+        println!("{}", format!("Note: Descriptions longer than {} characters are truncated with '...' (see --desc-width).", w).style(paint(note_style)));
+        println!("{}", "Remember to regularly perform closing operations to maintain accurate financial records.".style(paint(note_style)));
         println!();
     }
+    /// Footer of a page of the search results
+    /// ex: "Page 2/5 (showing 21–40 of 93)"
+    pub fn page_footer(page: &SearchPage) -> String {
+        let range = page.range();
+        if range.is_empty() {
+            format!("Page {}/{} (showing 0 of {})", page.number, page.count(), page.total)
+        } else {
+            format!("Page {}/{} (showing {}–{} of {})", page.number, page.count(), range.start + 1, range.end, page.total)
+        }
+    }
+    /// Render the table of the search results, with the totals of the displayed operations
+    pub fn render_search(rows: &[SearchItem], title: Option<&str>, desc_width: usize, columns: &[SearchColumn], fmt: &Formatter) -> String {
+        let w = desc_width.max(MIN_DESC_WIDTH);
+        let columns = if columns.is_empty() { &SearchColumn::DEFAULT[..] } else { columns };
+
+        // The totals (label and amount cells) need a minimum width, the last column takes the slack
+        let mut widths: Vec<usize> = columns.iter().map(|c| c.width(w)).collect();
+        let inner = widths.iter().sum::<usize>() + widths.len() - 1;
+        if inner < SEARCH_TOTALS_WIDTH
+            && let Some(last) = widths.last_mut() {
+            *last += SEARCH_TOTALS_WIDTH - inner;
+        }
+        let inner = inner.max(SEARCH_TOTALS_WIDTH);
+
+        // Positions of the column separators, for the borders
+        let column_bounds: Vec<usize> = widths.iter()
+            .scan(0, |pos, width| { *pos += width + 1; Some(*pos - 1) })
+            .take(widths.len() - 1)
+            .collect();
+        let totals_bounds = [SEARCH_TOTALS_LABEL, SEARCH_TOTALS_LABEL + 19];
+        let border = |left: char, right: char, table: Option<char>, totals: Option<char>, both: char| -> String {
+            let line: String = (0..inner)
+                .map(|i| match (column_bounds.contains(&i), totals_bounds.contains(&i)) {
+                    (true, true) => both,
+                    (true, false) => table.unwrap_or('─'),
+                    (false, true) => totals.unwrap_or('─'),
+                    (false, false) => '─',
+                })
+                .collect();
+            format!("{}{}{}\n", left, line, right)
+        };
+
+        let mut out = String::new();
+        out += &border('┌', '┐', None, None, '─');
+        let title_text = Self::title_text(title, "Operation(s)", inner - 1);
+        out += &format!("│ {}│\n", title_text.style(paint(Style::new().bold().cyan())));
+        out += &border('├', '┤', Some('┬'), None, '┬');
+
+        let header: Vec<String> = columns.iter().zip(&widths).map(|(column, &width)| column.header(width)).collect();
+        out += &format!("│{}│\n", header.join("│"));
+        out += &border('├', '┤', Some('┼'), None, '┼');
+
+        for item in rows {
+            let cells: Vec<String> = columns.iter().zip(&widths).map(|(column, &width)| column.cell(item, width, fmt)).collect();
+            out += &format!("│{}│\n", cells.join("│"));
+        }
+
+        // Totals of the displayed operations
+        let credit: Amount = rows.iter().filter(|item| item.op.flow.is_credit()).map(|item| item.op.amount).sum();
+        let debit: Amount = rows.iter().filter(|item| item.op.flow.is_debit()).map(|item| item.op.amount).sum();
+        let rest = inner - SEARCH_TOTALS_LABEL - 20;
+
+        out += &border('├', '┤', Some('┴'), Some('┬'), '┼');
+        out += &format!("│{:<26}│{:>18}│{:<rest$}│\n", "Total credit", fmt.amount(credit).style(paint(Style::new().green())), "");
+        out += &format!("│{:<26}│{:>18}│{:<rest$}│\n", "Total debit", fmt.amount(debit).style(paint(Style::new().red())), "");
+        out += &format!("│{:<26}│{:>18}│{:<rest$}│\n", "Net", fmt.amount(credit - debit).style(paint(Style::new().yellow().bold())), "");
+        out += &border('└', '┘', None, Some('┴'), '┴');
+        out
+    }
     /// view to resume the codexi
-    pub fn view_resume(resume: &ResumeResult) {
+    pub fn view_resume(resume: &ResumeResult, title: Option<&str>, fmt: &Formatter) {
 
         let title_style = Style::new().cyan().bold();
         let label_style = Style::new().dimmed();
@@ -104,43 +674,115 @@ impl Codexi {
         let note_style = Style::new().blue().italic();
 
         println!("┌────────────────────────────────────────────────────────────────────────────────┐");
-        let title_text = format!("{:<79}", "codexi resume");
-        println!("│ {}│", title_text.style(title_style));
+        let title_text = Self::title_text(title, "codexi resume", 79);
+        println!("│ {}│", title_text.style(paint(title_style)));
         println!("├──────────────────────┬──────────────────┬──────────────────────────────────────┤");
         println!("│{:<22}│{:>18}│ latest date transactions: {:>10} │",
-                "number of transactions".style(label_style),
+                "number of transactions".style(paint(label_style)),
                 resume.current_nb_transaction,
-                resume.latest_transaction_date.style(value_style));
+                Self::fmt_opt_date(resume.latest_transaction_date).style(paint(value_style)));
 
         println!("│{:<22}│{:>18}│ latest date init: {:>18} │",
-                "number of init".style(label_style),
+                "number of init".style(paint(label_style)),
                 resume.current_nb_init,
-                resume.latest_init_date.style(value_style));
+                Self::fmt_opt_date(resume.latest_init_date).style(paint(value_style)));
 
         println!("│{:<22}│{:>18}│ latest date adjustment: {:>12} │",
-                "number of adjustments".style(label_style),
+                "number of adjustments".style(paint(label_style)),
                 resume.current_nb_adjust,
-                resume.latest_adjust_date.style(value_style));
+                Self::fmt_opt_date(resume.latest_adjust_date).style(paint(value_style)));
 
         println!("│{:<22}│{:>18}│ latest date closing: {:>15} │",
-                "number of closings ".style(label_style),
+                "number of closings ".style(paint(label_style)),
                 resume.current_nb_close,
-                resume.latest_close_date.style(value_style));
+                Self::fmt_opt_date(resume.latest_close_date).style(paint(value_style)));
 
         println!("│{:<22}│{:>18}│                                      │",
-            "total operations".style(label_style),
-            resume.current_nb_op.style(value_style).bold());
+            "total operations".style(paint(label_style)),
+            resume.current_nb_op.style(paint(value_style.bold())));
 
         println!("│{:<22}│{:>18}│                                      │",
-            "current balance".style(label_style),
-            format!("{:.2}", resume.current_balance).separate_with_commas().style(value_style).bold());
+            "adjustments total".style(paint(label_style)),
+            fmt.signed_amount(resume.adjustment_total).style(paint(value_style)));
+
+        println!("│{:<22}│{:>18}│                                      │",
+            "current balance".style(paint(label_style)),
+            fmt.amount(resume.current_balance).style(paint(value_style.bold())));
 
         println!("└──────────────────────┴──────────────────┴──────────────────────────────────────┘");
         println!();
-        println!("{}", "Note: 'latest date' corresponds to the most recent date for each operation type.".style(note_style));
-        println!("{}", "Remember to regularly perform closing operations to maintain accurate financial records.".style(note_style));
+        println!("{}", "Note: 'latest date' corresponds to the most recent date for each operation type.".style(paint(note_style)));
+        println!("{}", "Remember to regularly perform closing operations to maintain accurate financial records.".style(paint(note_style)));
+        println!();
+    }
+    /// view the differences between the codexi and an incoming file
+    pub fn view_diff(entries: &[DiffEntry], stat: bool) {
+        let title_style = Style::new().cyan().bold();
+
+        println!("{}", "codexi diff".style(paint(title_style)));
+        println!();
+
+        if entries.is_empty() {
+            println!("{}", "No difference".style(paint(Style::new().dimmed())));
+        }
+
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for entry in entries {
+            match entry {
+                DiffEntry::Added(op) => {
+                    added += 1;
+                    println!("{}", format!("+ {}", op).style(paint(Style::new().green())));
+                }
+                DiffEntry::Removed(op) => {
+                    removed += 1;
+                    println!("{}", format!("- {}", op).style(paint(Style::new().red())));
+                }
+                DiffEntry::Changed { before, .. } => {
+                    changed += 1;
+                    println!("{}", format!("~ {} | {}", before.date, before.description).style(paint(Style::new().yellow())));
+                    for (field, old, new) in entry.changed_fields() {
+                        println!("    {:<12} {} → {}", field, old.style(paint(Style::new().red())), new.style(paint(Style::new().green())));
+                    }
+                }
+            }
+        }
+
+        if stat {
+            println!();
+            println!("{} {} {}",
+                format!("+{}", added).style(paint(Style::new().green())),
+                format!("-{}", removed).style(paint(Style::new().red())),
+                format!("~{}", changed).style(paint(Style::new().yellow())));
+        }
         println!();
     }
+    /// view the summary of an import, with the failed rows
+    pub fn view_import_summary(summary: &ImportSummary) {
+        println!("{}", summary.summary());
+        for (line, error) in &summary.errors {
+            println!("  {} {}", format!("line {}:", line).style(paint(Style::new().red())), error);
+        }
+        for (line, warning) in &summary.warnings {
+            println!("  {} {}", format!("line {}:", line).style(paint(Style::new().yellow())), warning);
+        }
+        for conflict in &summary.conflicts {
+            println!("  {} {}", "not merged:".style(paint(Style::new().yellow())), conflict);
+        }
+    }
+    /// Build the title bar text, prefixed with the ledger title if any (ex: "Joint Account · codexi resume")
+    fn title_text(title: Option<&str>, label: &str, width: usize) -> String {
+        match title {
+            Some(t) => Self::truncate_desc(&format!("{} · {}", t, label), width),
+            None => format!("{:<width$}", label, width = width),
+        }
+    }
+    /// Format an optional date for display (placeholder when missing)
+    fn fmt_opt_date(date: Option<NaiveDate>) -> String {
+        match date {
+            Some(d) => d.format("%Y-%m-%d").to_string(),
+            None => "__________".to_string(),
+        }
+    }
     /// Truncate description for display
     fn truncate_desc(desc: &str, max_width: usize) -> String {
         // If the visible length is already OK → simple formatting
@@ -157,3 +799,61 @@ impl Codexi {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use anyhow::Result;
+    use super::super::operation_kind::OperationKind;
+    use super::super::regular_kind::RegularKind;
+    use crate::core::helpers::CurrencyPosition;
+
+    #[test]
+    fn test_render_search_without_balance_column() -> Result<()> {
+        let op = Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 1234.5, "Lunch")?;
+        let rows = vec![SearchItem { index: 1, op: &op, balance: Amount::from(98765.43) }];
+
+        let default = Codexi::render_search(&rows, None, DEFAULT_DESC_WIDTH, &SearchColumn::DEFAULT, &Formatter::default());
+        assert!(default.contains("Balance"));
+        assert!(default.contains("98,765.43"));
+
+        let columns: Vec<SearchColumn> = SearchColumn::DEFAULT.into_iter().filter(|c| *c != SearchColumn::Balance).collect();
+        let without = Codexi::render_search(&rows, None, DEFAULT_DESC_WIDTH, &columns, &Formatter::default());
+        assert!(!without.contains("Balance"), "The balance header must be gone.");
+        assert!(!without.contains("98,765.43"), "The running balance must not be rendered.");
+        assert!(without.contains("1,234.50"));
+
+        // Every line of the table has the same width
+        let widths: Vec<usize> = without.lines()
+            .filter(|l| !l.contains('\u{1b}'))
+            .map(|l| l.chars().count())
+            .collect();
+        assert!(widths.windows(2).all(|w| w[0] == w[1]), "The borders must line up: {:?}", widths);
+
+        let reordered = Codexi::render_search(&rows, None, DEFAULT_DESC_WIDTH, &[SearchColumn::Description, SearchColumn::Date], &Formatter::default());
+        let header = reordered.lines().nth(3).unwrap_or_default();
+        assert!(header.find("Description") < header.find("Date"), "The columns follow the given order.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_search_with_formatter() -> Result<()> {
+        let op = Operation::new(OperationKind::Regular(RegularKind::Transaction), OperationFlow::Debit, "2025-11-05", 1234.56, "Lunch")?;
+        let rows = vec![SearchItem { index: 1, op: &op, balance: Amount::from(98765.43) }];
+        let euro = Formatter {
+            grouping: " ".to_string(),
+            decimal: ",".to_string(),
+            currency: Some("€".to_string()),
+            currency_position: CurrencyPosition::After,
+        };
+
+        let table = Codexi::render_search(&rows, None, DEFAULT_DESC_WIDTH, &SearchColumn::DEFAULT, &euro);
+        assert!(table.contains("1 234,56 €"), "{}", table);
+        assert!(table.contains("98 765,43 €"));
+        assert!(!table.contains("1,234.56"), "The given formatter is used, not the default one.");
+
+        Ok(())
+    }
+}
@@ -1,131 +1,674 @@
 // src/main.rs
 
 use std::env;
-use anyhow::{Result};
+use std::io::{self, IsTerminal};
+use std::process;
+use anyhow::{Result, anyhow};
 use clap::{Parser};
-use std::path::PathBuf;
+use chrono::{Local, NaiveDate};
+use std::path::{Path, PathBuf};
 
 mod core;
 
 use crate::core::helpers::init_logger;
 use crate::core::helpers::get_data_dir;
 use crate::core::helpers::get_final_backup_path;
+use crate::core::helpers::get_default_backup_dir;
+use crate::core::helpers::confirm;
+use crate::core::helpers::pretty_error;
+use crate::core::helpers::parse_flexible_date_range;
+use crate::core::helpers::read_passphrase;
+use crate::core::crypto::is_encrypted_file;
+use crate::core::helpers::{set_colors, Formatter, NumberStyle, JsonStyle};
+use crate::core::helpers::{set_dir_overrides, list_accounts, DirOverrides, DEFAULT_ACCOUNT};
+use crate::core::presets::Presets;
+use crate::core::config::Config;
+use crate::core::repl::Repl;
 use crate::core::command::{
     Cli,
     Commands,
     ReportName,
     DataAction,
     SystemAction,
+    RecurringAction,
+    LogFormat,
+    NormalizeDesc,
+    OutputFormat,
+    NumberFormat,
+    SplitBy,
+    Column,
+    SortBy,
+    SearchIn,
+    Breakdown,
 };
 use crate::core::wallet::{
     Codexi,
+    BackupLimits,
+    ExportPeriod,
+    FutureDatePolicy,
+    SearchSort,
+    SearchField,
+    SearchPage,
+    SearchFilter,
+    BalanceFilter,
+    OperationEdit,
+    DescriptionStyle,
     OperationKind,
     OperationFlow,
     RegularKind,
+    BankCsvMapping,
+    BankAmountColumns,
+    DEFAULT_DESC_WIDTH,
+    SearchColumn,
+    DataSchema,
+    RecoverySource,
+    RecurringRule,
+    RecurringRules,
 };
 
 fn main() -> Result<()> {
 
-    let cli = Cli::parse();
+    let argv: Vec<String> = env::args().collect();
+    let mut cli = Cli::parse_from(&argv);
+
+    // The config file gives the defaults of the flags not given
+    let (config, config_warning) = Config::load_or_default();
+    config.apply(&mut cli);
 
     let lvl = cli.verbose;
-    init_logger(lvl);
+    init_logger(lvl, cli.log_format == LogFormat::Json);
+    if let Some(warning) = config_warning {
+        log::warn!("{}", warning);
+    }
+    let number_style = cli.number_format.map(|format| match format {
+        NumberFormat::Us => NumberStyle::Us,
+        NumberFormat::Eu => NumberStyle::Eu,
+    });
+    let formatter = config.formatter(number_style, cli.currency.clone());
+    // https://no-color.org: a non-empty NO_COLOR disables the colors
+    set_colors(!cli.no_color && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()));
+    set_dir_overrides(DirOverrides {
+        archive_dir: cli.archive_dir.clone(),
+        snapshot_dir: cli.snapshot_dir.clone(),
+        account: cli.account.clone(),
+    });
+
+    let pretty_errors = cli.pretty_errors;
+    let result = execute(cli, &argv, &formatter);
+    if pretty_errors && let Err(e) = &result {
+        eprintln!("{}", pretty_error(e, &formatter));
+        process::exit(1);
+    }
+    result
+}
 
+/// Run a parsed command line (argv is kept to save it as a preset)
+fn execute(cli: Cli, argv: &[String], formatter: &Formatter) -> Result<()> {
+
+    // A count-only search prints a single number, ready for scripts
+    if matches!(cli.command, Commands::Search { count_only: true, .. }) {
+        log::set_max_level(log::LevelFilter::Off);
+    }
+
+    // Pretty json on a terminal, compact when piped
+    let json_style = JsonStyle::from_flags(cli.json_compact, cli.json_pretty, io::stdout().is_terminal());
     // current directory
     let cwd = env::current_dir()?;
     // app directory
     let data_dir = get_data_dir()?;
 
+    // The recovery runs before the load, which fails on an unreadable codexi.dat
+    if let Commands::System(system_args) = &cli.command
+        && let SystemAction::Recover { backup_dir, yes } = &system_args.action
+    {
+        return recover(&data_dir, backup_dir, *yes);
+    }
+
     let mut codexi = Codexi::load(&data_dir)?;
+    codexi.future_dates = if cli.allow_future {
+        FutureDatePolicy::Allow
+    } else {
+        FutureDatePolicy::Reject
+    };
+    codexi.description_style = match cli.normalize_desc {
+        NormalizeDesc::Off => DescriptionStyle::Keep,
+        NormalizeDesc::Spaces => DescriptionStyle::Collapse,
+        NormalizeDesc::Title => DescriptionStyle::TitleCase,
+    };
 
     match cli.command {
 
-        Commands::Init { initial_amount, date } => {
-            codexi.initialize(initial_amount, &date)?;
+        Commands::Init { initial_amount, date, if_empty } => {
+            let previous = codexi.clone();
+            codexi.initialize(initial_amount, &date, if_empty)?;
+            previous.snapshot(Some("before init"))?;
             codexi.save(&data_dir)?;
         },
 
-        Commands::Debit { date, amount, description } => {
-            codexi.add_operation(
-                OperationKind::Regular(RegularKind::Transaction),
+        Commands::Debit { date, amount, description, kind, category } => {
+            let previous = codexi.clone();
+            codexi.add_operation_with_category(
+                OperationKind::Regular(RegularKind::try_from_str(&kind)?),
                 OperationFlow::Debit,
                 &date,
                 amount,
-                &description.join(" ")
+                &description.join(" "),
+                category.as_deref(),
             )?;
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            previous.snapshot(Some("before debit"))?;
             codexi.save(&data_dir)?;
         },
 
-        Commands::Credit { date, amount, description } => {
-            codexi.add_operation(
-                OperationKind::Regular(RegularKind::Transaction),
+        Commands::Credit { date, amount, description, kind, category } => {
+            let previous = codexi.clone();
+            codexi.add_operation_with_category(
+                OperationKind::Regular(RegularKind::try_from_str(&kind)?),
                 OperationFlow::Credit,
                 &date,
                 amount,
-                &description.join(" ")
+                &description.join(" "),
+                category.as_deref(),
             )?;
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            previous.snapshot(Some("before credit"))?;
             codexi.save(&data_dir)?;
         },
 
-        Commands::Rm { index } => {
+        Commands::Rm { index, id } => {
+            let index = operation_index(&codexi, index, id)?;
+            let previous = codexi.clone();
             codexi.delete_operation(index)?;
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            previous.snapshot(Some("before rm"))?;
+            codexi.save(&data_dir)?;
+        },
+
+        Commands::Flag { index, id } => {
+            let index = operation_index(&codexi, index, id)?;
+            let previous = codexi.clone();
+            codexi.set_flag(index, true)?;
+            previous.snapshot(Some("before flag"))?;
+            codexi.save(&data_dir)?;
+        },
+
+        Commands::Unflag { index, id } => {
+            let index = operation_index(&codexi, index, id)?;
+            let previous = codexi.clone();
+            codexi.set_flag(index, false)?;
+            previous.snapshot(Some("before unflag"))?;
+            codexi.save(&data_dir)?;
+        },
+
+        Commands::Edit { index, id, date, amount, kind, flow, desc, append_desc, prepend_desc, separator } => {
+            let index = operation_index(&codexi, index, id)?;
+            let previous = codexi.clone();
+            codexi.edit_operation(index, &OperationEdit {
+                date: date.as_deref(),
+                amount,
+                kind: kind.as_deref(),
+                flow: flow.as_deref(),
+                description: desc.as_deref(),
+                append: append_desc.as_deref(),
+                prepend: prepend_desc.as_deref(),
+                separator: &separator,
+            })?;
+            previous.snapshot(Some("before edit"))?;
+            codexi.save(&data_dir)?;
+        },
+
+        Commands::Accounts {} => {
+            for account in list_accounts()? {
+                let marker = if account == cli.account.as_deref().unwrap_or(DEFAULT_ACCOUNT) { "*" } else { " " };
+                println!("{} {}", marker, account);
+            }
+        },
+
+        Commands::Repl {} => {
+            Repl::new(codexi, cli.strict_balance).run(&data_dir)?;
+        },
+
+        Commands::Verify {} => {
+            let problems = codexi.verify();
+            if problems.is_empty() {
+                println!("{} operation(s) checked, no problem found.", codexi.operations.len());
+            } else {
+                for problem in &problems {
+                    println!("- {}", problem);
+                }
+                return Err(anyhow!("{} problem(s) found in the codexi.", problems.len()));
+            }
+        },
+
+        Commands::Undo {} => {
+            let (restored, info) = Codexi::undo(&data_dir)?;
+            println!("Undone: {} restored ({} operations).", info.note.as_deref().unwrap_or(&info.file_name), restored.operations.len());
+        },
+
+        Commands::Apply { file } => {
+            let previous = codexi.clone();
+            let result = codexi.apply_batch(&PathBuf::from(&file))?;
+            Codexi::view_import_summary(&result);
+            if !result.errors.is_empty() {
+                return Err(anyhow!("{} was not applied, fix the lines above and run it again.", file));
+            }
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            let snapshot_path = previous.snapshot(Some("before apply"))?;
+            println!("Snapshot taken before apply: {}", snapshot_path.display());
             codexi.save(&data_dir)?;
         },
 
+        Commands::Batch {} => {
+            let previous = codexi.clone();
+            let result = codexi.add_records(io::stdin().lock(), cli.strict_balance)?;
+            Codexi::view_import_summary(&result);
+            // The valid lines are kept, even when others are rejected
+            if result.imported > 0 {
+                let snapshot_path = previous.snapshot(Some("before batch"))?;
+                println!("Snapshot taken before batch: {}", snapshot_path.display());
+                codexi.save(&data_dir)?;
+            }
+            if !result.errors.is_empty() {
+                return Err(anyhow!("{} line(s) were not added, fix them and pipe them again.", result.errors.len()));
+            }
+        },
+
+        Commands::Recategorize { index, id, kind, flow } => {
+            let index = operation_index(&codexi, index, id)?;
+            let previous = codexi.clone();
+            codexi.recategorize(index, &kind, flow.as_deref())?;
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            let snapshot_path = previous.snapshot(Some("before recategorize"))?;
+            println!("Snapshot taken before recategorize: {}", snapshot_path.display());
+            codexi.save(&data_dir)?;
+        },
+
+        Commands::Show { index, id } => {
+            let index = operation_index(&codexi, index, id)?;
+            let op = codexi.operations.get(index)
+                .ok_or_else(|| anyhow!("Operation index {} is out of bounds.", index))?;
+            Codexi::view_operation(index, op, formatter);
+        },
+
+        Commands::RmMatch { from, to, text, kind, flow, day, weekday, amount_min, amount_max, expect_removed, max_removed } => {
+            let indices: Vec<usize> = codexi.search(&SearchFilter { from, to, text: text.into_iter().collect(), kind, flow, day, weekday, amount_min, amount_max, ..Default::default() }, None)?
+                .iter()
+                .map(|item| item.index as usize)
+                .collect();
+
+            let previous = codexi.clone();
+            let removed = codexi.delete_operations(&indices, expect_removed, max_removed)?;
+            if cli.strict_balance {
+                codexi.check_running_balance()?;
+            }
+            let snapshot_path = previous.snapshot(Some("before rm-match"))?;
+            println!("Snapshot taken before rm-match: {}", snapshot_path.display());
+            codexi.save(&data_dir)?;
+            println!("{} operation(s) removed.", removed);
+        },
+
+        Commands::SetTitle { title } => {
+            let previous = codexi.clone();
+            codexi.set_title(&title.join(" "));
+            if codexi.title != previous.title {
+                previous.snapshot(Some("before set-title"))?;
+                codexi.save(&data_dir)?;
+            }
+        },
+
+        Commands::Schema {} => {
+            let schema = DataSchema::current();
+            match cli.format {
+                OutputFormat::Json => println!("{}", json_style.format(&schema)?),
+                OutputFormat::Human => Codexi::view_schema(&schema),
+            }
+        },
+
+        Commands::Run { ref name, ref args } => {
+            let preset_argv = Presets::command_line(name, args)?;
+            let mut preset_cli = Cli::try_parse_from(&preset_argv)?;
+            // The global flags given before `run` win over the saved ones
+            preset_cli.merge_globals(&cli);
+            if !matches!(preset_cli.command, Commands::Report(_) | Commands::Search { .. }) {
+                return Err(anyhow!("The preset '{}' is not a report or a search.", name));
+            }
+            return execute(preset_cli, &preset_argv, formatter);
+        },
+
         Commands::Report(report_args) => {
+            if let Some(name) = report_args.save_as.as_deref() {
+                Presets::save_as(name, argv)?;
+                if cli.format == OutputFormat::Human {
+                    println!("Preset '{}' saved, run it with: codexi run {}", name, name);
+                }
+            }
+            let json = cli.format == OutputFormat::Json;
             match report_args.report_name {
-                ReportName::Balance { from, to, day, month, year } => {
-                    let balance = codexi.balance(from, to, day, month, year)?;
-                    Codexi::view_balance(&balance);
+                ReportName::Balance { from, to, day, month, year, compare, amount_min, amount_max, average, running, target, projected_close, breakdown, top, threshold, exclude_system, exclude_kind } => {
+                    if json && (breakdown.is_some() || target.is_some() || projected_close.is_some()) {
+                        return Err(anyhow!("--format json does not support --breakdown, --target and --projected-close."));
+                    }
+                    let exclude_kinds = exclude_kind
+                        .iter()
+                        .map(|k| OperationKind::try_from_str(k))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let filter = BalanceFilter { from, to, day, month, year, amount_min, amount_max, exclude_system, exclude_kinds };
+                    if running {
+                        let items = codexi.running_balance(&filter)?;
+                        if json {
+                            println!("{}", json_style.format(&items)?);
+                        } else {
+                            Codexi::view_running_balance(&items, codexi.title.as_deref(), formatter);
+                        }
+                    } else if let (true, Some(month)) = (compare, filter.month.as_deref()) {
+                        let result = codexi.balance_compare(month)?;
+                        if json {
+                            println!("{}", json_style.format(&result)?);
+                        } else {
+                            Codexi::view_balance_compare(&result, formatter);
+                        }
+                    } else {
+                        let mut balance = codexi.balance(&filter)?;
+                        if average {
+                            balance.average = codexi.average_spend(
+                                balance.debit,
+                                filter.from.as_deref(),
+                                filter.to.as_deref(),
+                                filter.day.as_deref(),
+                                filter.month.as_deref(),
+                                filter.year.as_deref(),
+                            )?;
+                        }
+                        if json {
+                            println!("{}", json_style.format(&balance)?);
+                        } else {
+                            Codexi::view_balance(&balance, codexi.title.as_deref(), formatter);
+                        }
+                        if let Some(Breakdown::Payee) = breakdown {
+                            let payees = codexi.balance_by_payee(&filter, top, threshold)?;
+                            Codexi::view_payee_breakdown(&payees, codexi.title.as_deref(), formatter);
+                        }
+                    }
+                    if let Some(target) = target {
+                        let goal = codexi.savings_goal(target)?;
+                        Codexi::view_goal(&goal, formatter);
+                    }
+                    if let Some(date) = projected_close.as_deref() {
+                        let date = parse_flexible_date_range(date, false)?.format("%Y-%m-%d").to_string();
+                        let preview = codexi.close_preview(&date)?;
+                        Codexi::view_close_preview(&date, preview.as_ref(), formatter);
+                    }
+                },
+                ReportName::Monthly { from, to, trend } => {
+                    let months = codexi.monthly_balances(from, to)?;
+                    Codexi::view_monthly(&months, trend, codexi.title.as_deref(), formatter);
                 },
-                ReportName::Resume {} => {
+                ReportName::Resume { json: resume_json } => {
                     let resume = codexi.resume()?;
-                    Codexi::view_resume(&resume);
+                    if json || resume_json {
+                        println!("{}", json_style.format(&resume)?);
+                    } else {
+                        Codexi::view_resume(&resume, codexi.title.as_deref(), formatter);
+                    }
+                },
+                ReportName::Closes {} => {
+                    let entries = codexi.close_history()?;
+                    Codexi::view_closes(&entries, codexi.title.as_deref(), formatter);
+                },
+                ReportName::Gaps { max_gap } => {
+                    let gaps = codexi.gaps(max_gap);
+                    Codexi::view_gaps(&gaps, max_gap, codexi.title.as_deref());
+                },
+                ReportName::Activity { from, to } => {
+                    let activity = codexi.activity(from, to)?;
+                    Codexi::view_activity(&activity, codexi.title.as_deref(), formatter);
+                },
+                ReportName::Discretionary { from, to } => {
+                    let discretionary = codexi.discretionary(from, to)?;
+                    Codexi::view_discretionary(&discretionary, codexi.title.as_deref(), formatter);
+                },
+                ReportName::Categories { from, to } => {
+                    let categories = codexi.balance_by_category(from, to)?;
+                    Codexi::view_categories(&categories, codexi.title.as_deref(), formatter);
+                },
+                ReportName::Stats { from, to } => {
+                    let stats = codexi.stats(from, to)?;
+                    Codexi::view_stats(&stats, codexi.title.as_deref(), formatter);
                 },
             }
         },
 
-        Commands::Search { from, to, text, kind, flow, day, amount_min, amount_max, latest } => {
-            let results = codexi.search(
+        Commands::Search { from, to, text, regex, text_in, kind, only_system, only_regular, flow, day, weekday, amount_min, amount_max, flagged, sort, latest, page, page_size, export, export_only, count_only, save_as, desc_width, columns, no_balance_col } => {
+            if let Some(name) = save_as.as_deref() {
+                Presets::save_as(name, argv)?;
+                if !count_only && cli.format == OutputFormat::Human {
+                    println!("Preset '{}' saved, run it with: codexi run {}", name, name);
+                }
+            }
+            let system = if only_system {
+                Some(true)
+            } else if only_regular {
+                Some(false)
+            } else {
+                None
+            };
+            let mut search_page = match page {
+                Some(number) => Some(SearchPage::new(number, page_size)?),
+                None => None,
+            };
+            let filter = SearchFilter {
                 from,
                 to,
                 text,
+                regex,
+                text_in: match text_in {
+                    SearchIn::Desc => SearchField::Desc,
+                    SearchIn::Category => SearchField::Category,
+                    SearchIn::All => SearchField::All,
+                },
                 kind,
+                system,
                 flow,
                 day,
+                weekday,
                 amount_min,
                 amount_max,
+                flagged,
+                sort: match sort {
+                    SortBy::Date => SearchSort::Date,
+                    SortBy::Amount => SearchSort::Amount,
+                    SortBy::DescAmount => SearchSort::DescAmount,
+                },
                 latest,
-            )?;
+            };
+            let results = codexi.search(&filter, search_page.as_mut())?;
 
-            Codexi::view_search(&results);
+            if count_only {
+                if cli.format == OutputFormat::Json {
+                    println!("{}", json_style.format(&serde_json::json!({"count": results.len()}))?);
+                } else {
+                    println!("{}", results.len());
+                }
+                return Ok(());
+            }
+            if let Some(export_path) = export {
+                let operations: Vec<_> = results.iter().map(|item| item.op.clone()).collect();
+                Codexi::export_operations(&operations, &PathBuf::from(&export_path), json_style)?;
+                if cli.format == OutputFormat::Human {
+                    println!("{} operation(s) exported to {}", operations.len(), export_path);
+                }
+            }
+            if !export_only && cli.format == OutputFormat::Json {
+                println!("{}", json_style.format(&results)?);
+            } else if !export_only {
+                let mut columns: Vec<SearchColumn> = if columns.is_empty() {
+                    SearchColumn::DEFAULT.to_vec()
+                } else {
+                    columns.into_iter().map(|c| match c {
+                        Column::Index => SearchColumn::Index,
+                        Column::Date => SearchColumn::Date,
+                        Column::Type => SearchColumn::Flow,
+                        Column::Amount => SearchColumn::Amount,
+                        Column::Balance => SearchColumn::Balance,
+                        Column::Desc => SearchColumn::Description,
+                    }).collect()
+                };
+                if no_balance_col {
+                    columns.retain(|c| *c != SearchColumn::Balance);
+                }
+                Codexi::view_search(&results, codexi.title.as_deref(), desc_width, &columns, search_page.as_ref(), formatter);
+            }
         },
 
         Commands::Data(data_args) => {
             match data_args.action {
                 DataAction::Export(export_args) => {
-                    if export_args.toml {
+                    if let Some(split_by) = export_args.split_by {
+                        // one file per month/year
+                        let period = match split_by {
+                            SplitBy::Month => ExportPeriod::Month,
+                            SplitBy::Year => ExportPeriod::Year,
+                        };
+                        let extension = if export_args.toml { "toml" } else if export_args.json { "json" } else { "csv" };
+                        let files = codexi.export_split(&cwd, period, extension, !export_args.no_header, export_args.gzip, json_style)?;
+                        for file in &files {
+                            println!("{}", file.display());
+                        }
+                        println!("{} file(s) written.", files.len());
+                    } else if export_args.toml {
                         // export to readable format(toml)
-                        codexi.export_toml(&cwd)?;
+                        codexi.export_toml(&cwd, export_args.gzip)?;
+                    } else if export_args.json {
+                        // export to readable format(json)
+                        codexi.export_json(&cwd, export_args.gzip, json_style)?;
                     } else if export_args.csv {
                         // export to readable format(csv)
-                        codexi.export_csv(&cwd)?;
+                        codexi.export_csv(&cwd, !export_args.no_header, export_args.gzip)?;
+                    }
+                }
+                DataAction::Import(import_args) if import_args.validate_only => {
+                    // Pre-flight check: parse and validate, change nothing
+                    let (incoming, mut problems) = if import_args.toml {
+                        (Codexi::import_toml(&cwd)?, Vec::new())
+                    } else if import_args.json {
+                        (Codexi::import_json(&cwd)?, Vec::new())
+                    } else {
+                        let (incoming, summary) = Codexi::import_csv(&cwd, import_args.lenient)?;
+                        let errors = summary.errors.iter().map(|(line, e)| format!("Line {}: {}", line, e)).collect();
+                        (incoming, errors)
+                    };
+                    problems.extend(incoming.validate());
+                    if cli.strict_balance
+                        && let Err(e) = incoming.check_running_balance()
+                    {
+                        problems.push(e.to_string());
+                    }
+
+                    if problems.is_empty() {
+                        println!("{} operation(s) parsed, no problem found.", incoming.operations.len());
+                    } else {
+                        for problem in &problems {
+                            println!("- {}", problem);
+                        }
+                        return Err(anyhow!("{} problem(s) found, nothing was imported.", problems.len()));
                     }
                 }
                 DataAction::Import(import_args) => {
-                    if import_args.toml {
-                        let _ = codexi.snapshot();
+                    if let Ok(snapshot_path) = codexi.snapshot(Some("before import")) {
+                        println!("Snapshot taken before import: {}", snapshot_path.display());
+                    }
+                    let imported = if import_args.toml {
                         // import from readable format(toml)
-                        let codexi = Codexi::import_toml(&cwd)?;
-                        codexi.save(&data_dir)?;
-                    } else if import_args.csv {
-                        let _ = codexi.snapshot();
+                        let mut imported = Codexi::import_toml(&cwd)?;
+                        imported.title = imported.title.or(codexi.title.clone());
+                        imported
+                    } else if import_args.json {
+                        // import from readable format(json)
+                        let mut imported = Codexi::import_json(&cwd)?;
+                        imported.title = imported.title.or(codexi.title.clone());
+                        imported
+                    } else {
                         // import from readable format(csv)
-                        let codexi = Codexi::import_csv(&cwd)?;
+                        let (mut imported, summary) = Codexi::import_csv(&cwd, import_args.lenient)?;
+                        Codexi::view_import_summary(&summary);
+                        imported.title = codexi.title.clone();
+                        imported
+                    };
+                    let codexi = if import_args.merge || import_args.dedup {
+                        let summary = codexi.merge_import(imported, import_args.dedup)?;
+                        print!("Merged into the codexi: ");
+                        Codexi::view_import_summary(&summary);
+                        codexi
+                    } else {
+                        imported
+                    };
+                    if cli.strict_balance {
+                        codexi.check_running_balance()?;
+                    }
+                    codexi.save(&data_dir)?;
+                }
+
+                DataAction::ImportBank(bank_args) => {
+                    let amount = match (bank_args.amount_col, bank_args.debit_col, bank_args.credit_col) {
+                        (Some(signed), _, _) => BankAmountColumns::Signed(signed),
+                        (None, Some(debit), Some(credit)) => BankAmountColumns::Split { debit, credit },
+                        _ => return Err(anyhow!("Either --amount-col or --debit-col/--credit-col is required.")),
+                    };
+                    let mapping = BankCsvMapping {
+                        date_col: bank_args.date_col,
+                        date_format: bank_args.date_format,
+                        desc_col: bank_args.desc_col,
+                        amount,
+                        delimiter: bank_args.delimiter as u8,
+                    };
+
+                    let previous = codexi.clone();
+                    let result = codexi.import_bank_csv(&PathBuf::from(bank_args.file), &mapping, bank_args.skip_existing)?;
+                    if cli.strict_balance {
+                        codexi.check_running_balance()?;
+                    }
+                    let snapshot_path = previous.snapshot(Some("before bank import"))?;
+                    println!("Snapshot taken before bank import: {}", snapshot_path.display());
+                    codexi.save(&data_dir)?;
+
+                    Codexi::view_import_summary(&result);
+                }
+
+                DataAction::Dedupe { dry_run } => {
+                    let count = codexi.dedupe(true)?;
+                    if !dry_run && count > 0 {
+                        let snapshot_path = codexi.snapshot(Some("before dedupe"))?;
+                        println!("Snapshot taken before dedupe: {}", snapshot_path.display());
+                        codexi.dedupe(false)?;
                         codexi.save(&data_dir)?;
+                        println!("{} duplicated operation(s) removed.", count);
+                    } else {
+                        println!("{} duplicated operation(s) found.", count);
                     }
                 }
 
+                DataAction::Diff(diff_args) => {
+                    let incoming = if diff_args.toml {
+                        Codexi::import_toml(&cwd)?
+                    } else if diff_args.json {
+                        Codexi::import_json(&cwd)?
+                    } else {
+                        Codexi::import_csv(&cwd, false)?.0
+                    };
+                    let entries = codexi.diff(&incoming);
+                    Codexi::view_diff(&entries, diff_args.stat);
+                }
+
                 DataAction::RestoreSnapshot{ snapshot_file } => {
                     let codexi = Codexi::restore_snapshot(&snapshot_file)?;
                     codexi.save(&data_dir)?;
@@ -136,8 +679,27 @@ fn main() -> Result<()> {
                     Codexi::view_snapshot(&datas);
                 }
 
-                DataAction::Snapshot{} => {
-                    let _ = codexi.snapshot()?;
+                DataAction::PruneSnapshot { keep, yes } => {
+                    let keep = keep.ok_or_else(|| anyhow!("The number of snapshots to keep is required: --keep or snapshot_keep in config.toml."))?;
+                    let files = Codexi::snapshots_beyond(keep)?;
+                    if files.is_empty() {
+                        println!("No snapshot to delete ({} kept).", keep);
+                        return Ok(());
+                    }
+                    for (name, size) in &files {
+                        println!("  {} ({} bytes)", name, size);
+                    }
+                    if !yes {
+                        println!("{} snapshot(s) would be deleted, run again with --yes to delete them.", files.len());
+                        return Ok(());
+                    }
+                    let (count, freed) = Codexi::prune_snapshots(keep)?;
+                    println!("{} snapshot(s) deleted, {} bytes freed.", count, freed);
+                }
+
+                DataAction::Snapshot{ note } => {
+                    let snapshot_path = codexi.snapshot(note.as_deref())?;
+                    println!("Snapshot created: {}", snapshot_path.display());
                 }
             }
         },
@@ -145,32 +707,217 @@ fn main() -> Result<()> {
         Commands::System(system_args) => {
             match system_args.action {
                 SystemAction::Adjust { physical_balance, date} => {
+                    let previous = codexi.clone();
                     codexi.adjust_balance(physical_balance, &date)?;
+                    if cli.strict_balance {
+                        codexi.check_running_balance()?;
+                    }
+                    previous.snapshot(Some("before adjust"))?;
                     codexi.save(&data_dir)?;
                 },
-                SystemAction::Close { date, description } => {
-                    codexi.close_period(&date, description)?;
+                SystemAction::Close { date, description, keep_open_copy } => {
+                    let copy_path = keep_open_copy.map(PathBuf::from);
+                    let previous = codexi.clone();
+                    if codexi.close_period(&date, description, copy_path.as_deref())? {
+                        previous.snapshot(Some("before close"))?;
+                        if let Some(copy_path) = copy_path {
+                            println!("Closed operations copied to {}", copy_path.display());
+                        }
+                        codexi.save(&data_dir)?;
+                    } else {
+                        println!("Nothing to close on or before {}.", date);
+                    }
+                },
+                SystemAction::CloseBatch { from, to, description } => {
+                    let previous = codexi.clone();
+                    codexi.close_batch(&from, &to, description)?;
+                    let snapshot_path = previous.snapshot(Some("before close-batch"))?;
+                    println!("Snapshot taken before close-batch: {}", snapshot_path.display());
                     codexi.save(&data_dir)?;
                 },
+                SystemAction::Reopen { filename } => {
+                    let previous = codexi.clone();
+                    let archive_path = codexi.reopen_period(&filename)?;
+                    let snapshot_path = previous.snapshot(Some("before reopen"))?;
+                    println!("Snapshot taken before reopen: {}", snapshot_path.display());
+                    codexi.save(&data_dir)?;
+                    Codexi::keep_reopened_archive(&archive_path, &snapshot_path)?;
+                    println!("Period reopened, {} kept with the snapshot for undo.", filename);
+                },
                 SystemAction::List {} => {
                     let results = Codexi::list_archives()?;
                     Codexi::view_archive(&results);
                 },
+                SystemAction::PruneArchives { before, yes } => {
+                    let files = Codexi::archives_before(&before)?;
+                    if files.is_empty() {
+                        println!("No archive closed before {}.", before);
+                        return Ok(());
+                    }
+                    let names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+                    Codexi::view_archive(&names);
+                    if !yes && !confirm(&format!("Delete these {} archive(s)?", files.len()))? {
+                        println!("Nothing deleted.");
+                        return Ok(());
+                    }
+                    let (count, freed) = Codexi::prune_archives(&before)?;
+                    println!("{} archive(s) deleted, {} bytes freed.", count, freed);
+                },
+                SystemAction::PruneBackups { dir, keep, yes } => {
+                    let files = Codexi::backups_beyond(&dir, keep)?;
+                    if files.is_empty() {
+                        println!("No backup to delete in {} ({} kept).", dir.display(), keep);
+                        return Ok(());
+                    }
+                    for (name, size) in &files {
+                        println!("  {} ({} bytes)", name, size);
+                    }
+                    if !yes {
+                        println!("{} backup(s) would be deleted, run again with --yes to delete them.", files.len());
+                        return Ok(());
+                    }
+                    let (count, freed) = Codexi::prune_backups(&dir, keep)?;
+                    println!("{} backup(s) deleted, {} bytes freed.", count, freed);
+                },
                 SystemAction::View {filename} => {
-                    let codexi = Codexi::load_archive(&filename)?;
-                    let results = codexi.search(None, None, None, None, None, None, None, None, None)?;
-                    Codexi::view_search(&results);
+                    let archive = Codexi::load_archive(&filename)?;
+                    let results = archive.search(&SearchFilter::default(), None)?;
+                    Codexi::view_search(&results, codexi.title.as_deref(), DEFAULT_DESC_WIDTH, &SearchColumn::DEFAULT, None, formatter);
                 },
-                SystemAction::Backup{ target_dir } => {
-                    let final_backup_path = get_final_backup_path(target_dir.as_deref())?;
-                    Codexi::backup(&final_backup_path)?;
+                SystemAction::Backup{ target_dir, max_file_size, max_total_size, encrypt } => {
+                    let limits = BackupLimits {
+                        max_file_size: max_file_size * 1024 * 1024,
+                        max_total_size: max_total_size * 1024 * 1024,
+                    };
+                    if encrypt {
+                        // "backup.zip.enc" is a full path, like "backup.zip"
+                        let target = target_dir.as_deref().map(|t| t.strip_suffix(".enc").unwrap_or(t));
+                        let mut final_backup_path = get_final_backup_path(target)?.into_os_string();
+                        final_backup_path.push(".enc");
+                        let passphrase = read_passphrase(true)?;
+                        Codexi::backup_encrypted(&PathBuf::from(final_backup_path), &limits, &passphrase)?;
+                    } else {
+                        let final_backup_path = get_final_backup_path(target_dir.as_deref())?;
+                        Codexi::backup(&final_backup_path, &limits)?;
+                    }
                 },
-                SystemAction::Restore{ filename } => {
+                SystemAction::Restore{ filename, dry_run } => {
                     let full_path = PathBuf::from(filename);
-                    Codexi::restore(&full_path)?;
+                    let passphrase = backup_passphrase(&full_path)?;
+                    if dry_run {
+                        let plan = Codexi::restore_plan(&full_path, passphrase.as_deref())?;
+                        for entry in &plan {
+                            println!("  {} {}", if entry.exists { "overwrite" } else { "create   " }, entry.path.display());
+                        }
+                        let overwritten = plan.iter().filter(|e| e.exists).count();
+                        println!("{} file(s) would be restored, {} overwritten. Run again without --dry-run to restore.", plan.len(), overwritten);
+                        return Ok(());
+                    }
+                    if let Some(safety_path) = Codexi::restore(&full_path, passphrase.as_deref())? {
+                        println!("Safety backup of the previous data: {}", safety_path.display());
+                    }
+                },
+                SystemAction::Recover { .. } => unreachable!("handled before the load"),
+                SystemAction::InspectBackup{ filename } => {
+                    let full_path = PathBuf::from(filename);
+                    let passphrase = backup_passphrase(&full_path)?;
+                    let (backup, archives) = Codexi::inspect_backup(&full_path, passphrase.as_deref())?;
+                    let resume = backup.resume()?;
+                    Codexi::view_resume(&resume, backup.title.as_deref(), formatter);
+                    println!("{} archive file(s) in the backup.", archives);
+                },
+            }
+        },
+
+        Commands::Recurring(recurring_args) => {
+            let mut rules = RecurringRules::load(&data_dir)?;
+            match recurring_args.action {
+                RecurringAction::Add { flow, amount, day, start, kind, description } => {
+                    let start = NaiveDate::parse_from_str(&start, "%Y-%m-%d")
+                        .map_err(|_| anyhow!("Invalid start date '{}' (expected YYYY-MM-DD).", start))?;
+                    let rule = RecurringRule::new(
+                        OperationKind::Regular(RegularKind::try_from_str(&kind)?),
+                        OperationFlow::try_from_str(&flow)?,
+                        amount,
+                        &description.join(" "),
+                        day,
+                        start,
+                    )?;
+                    rules.rules.push(rule);
+                    rules.save(&data_dir)?;
+                    println!("Recurring rule #{} added, generate its operations with: codexi recurring materialize", rules.rules.len() - 1);
+                },
+                RecurringAction::List {} => {
+                    Codexi::view_recurring(&rules, formatter);
+                },
+                RecurringAction::Remove { index } => {
+                    let rule = rules.remove(index)?;
+                    rules.save(&data_dir)?;
+                    println!("Recurring rule '{}' removed.", rule.description);
+                },
+                RecurringAction::Materialize {} => {
+                    let previous = codexi.clone();
+                    let summary = rules.materialize(&mut codexi, Local::now().date_naive())?;
+                    if cli.strict_balance {
+                        codexi.check_running_balance()?;
+                    }
+                    previous.snapshot(Some("before recurring"))?;
+                    codexi.save(&data_dir)?;
+                    rules.save(&data_dir)?;
+                    println!("{} operation(s) generated, {} skipped in a closed period.", summary.generated, summary.skipped.len());
                 },
             }
         },
     }
     Ok(())
 }
+
+/// Salvage an unreadable codexi.dat (see Codexi::recover), after confirmation
+fn recover(data_dir: &Path, backup_dirs: &[PathBuf], yes: bool) -> Result<()> {
+    let mut backup_dirs = backup_dirs.to_vec();
+    if backup_dirs.is_empty() {
+        backup_dirs.push(get_default_backup_dir()?);
+    }
+
+    let recovery = Codexi::recover(data_dir, &backup_dirs)?;
+    for (source, reason) in &recovery.failures {
+        println!("Unreadable: {} ({})", source, reason);
+    }
+
+    let from = match &recovery.source {
+        RecoverySource::Data => {
+            println!("codexi.dat is readable ({} operations), nothing to recover.", recovery.codexi.operations.len());
+            return Ok(());
+        }
+        RecoverySource::Snapshot(file_name) => format!("snapshot {}", file_name),
+        RecoverySource::Backup(path) => format!("backup {} (the archives are not restored, see `system restore`)", path.display()),
+    };
+    println!("Recoverable: {} operations from the {}.", recovery.codexi.operations.len(), from);
+
+    if !yes && !confirm("Replace codexi.dat with the recovered data?")? {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    if let Some(corrupt_path) = recovery.codexi.replace_data(data_dir)? {
+        println!("The unreadable file was kept as {}", corrupt_path.display());
+    }
+    println!("codexi.dat recovered from the {}.", from);
+    Ok(())
+}
+
+/// Get the passphrase of a backup file, only when it is encrypted
+fn backup_passphrase(backup_path: &Path) -> Result<Option<String>> {
+    if is_encrypted_file(backup_path)? {
+        read_passphrase(false).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Get the index of the operation targeted by a command, from its index or its id
+fn operation_index(codexi: &Codexi, index: Option<usize>, id: Option<String>) -> Result<usize> {
+    match id {
+        Some(id) => codexi.index_of_id(&id),
+        None => index.ok_or_else(|| anyhow!("An index or an id is required.")),
+    }
+}
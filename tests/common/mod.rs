@@ -0,0 +1,35 @@
+// tests/common/mod.rs
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Result, anyhow};
+
+/// Run the codexi binary with its data in the given directory, return the stdout
+pub fn codexi(data_home: &Path, args: &[&str]) -> Result<String> {
+    codexi_with_env(data_home, &[], args)
+}
+/// Same as codexi, with extra environment variables
+pub fn codexi_with_env(data_home: &Path, envs: &[(&str, &str)], args: &[&str]) -> Result<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_codexi"))
+        .args(args)
+        .env("XDG_DATA_HOME", data_home)
+        .env_remove("CODEXI_ACCOUNT")
+        .env_remove("NO_COLOR")
+        .env("RUST_LOG", "off")
+        .envs(envs.iter().copied())
+        .current_dir(data_home)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("codexi {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// A codexi with an initial amount, a debit and a credit
+pub fn setup(data_home: &Path) -> Result<()> {
+    codexi(data_home, &["init", "100", "2025-01-01"])?;
+    codexi(data_home, &["debit", "2025-01-05", "12.50", "Grocer", "-", "food"])?;
+    codexi(data_home, &["credit", "2025-01-10", "40", "Salary"])?;
+    Ok(())
+}
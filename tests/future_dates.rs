@@ -0,0 +1,36 @@
+// tests/future_dates.rs
+
+mod common;
+
+use anyhow::Result;
+use chrono::{Duration, Local};
+
+use common::{codexi, setup};
+
+#[test]
+fn test_future_debit_rejected_by_default() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    let next_year = (Local::now().date_naive() + Duration::days(365)).format("%Y-%m-%d").to_string();
+
+    assert!(codexi(dir.path(), &["debit", &next_year, "10", "Typo"]).is_err(), "A future date is rejected by default.");
+    codexi(dir.path(), &["--allow-future", "debit", &next_year, "10", "Planned"])?;
+
+    // The former opt-in is a hidden no-op, kept for the scripts
+    codexi(dir.path(), &["--strict-dates", "debit", "2025-01-12", "5", "Coffee"])?;
+    assert!(!codexi(dir.path(), &["--help"])?.contains("--strict-dates"));
+
+    Ok(())
+}
+
+#[test]
+fn test_init_today_accepted_by_default() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+
+    codexi(dir.path(), &["init", "100", &today])?;
+    let balance: serde_json::Value = serde_json::from_str(&codexi(dir.path(), &["--format", "json", "report", "balance"])?)?;
+    assert_eq!(balance["total"], 100.0);
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+// tests/import_merge.rs
+
+mod common;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use common::{codexi, setup};
+
+/// Descriptions of the operations of the ledger, in order
+fn descriptions(data_home: &std::path::Path) -> Result<Vec<String>> {
+    let items: Value = serde_json::from_str(&codexi(data_home, &["--format", "json", "search"])?)?;
+    Ok(items.as_array().into_iter().flatten().map(|item| item["description"].as_str().unwrap_or_default().to_string()).collect())
+}
+
+#[test]
+fn test_import_replace() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    codexi(dir.path(), &["data", "export", "--csv"])?;
+    codexi(dir.path(), &["debit", "2025-01-20", "5", "Bread"])?;
+
+    codexi(dir.path(), &["data", "import", "--csv"])?;
+    assert_eq!(descriptions(dir.path())?, ["INITIAL AMOUNT", "Grocer - food", "Salary"], "The import replaces the ledger by default.");
+
+    Ok(())
+}
+
+#[test]
+fn test_import_merge() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    codexi(dir.path(), &["data", "export", "--csv"])?;
+    codexi(dir.path(), &["debit", "2025-01-20", "5", "Bread"])?;
+
+    // The Init of the file is already in the ledger, the other operations are appended
+    let output = codexi(dir.path(), &["data", "import", "--csv", "--merge"])?;
+    assert!(output.contains("Merged into the codexi: imported 2"), "{}", output);
+    assert_eq!(descriptions(dir.path())?, ["INITIAL AMOUNT", "Grocer - food", "Grocer - food", "Salary", "Salary", "Bread"]);
+
+    // With --dedup, nothing is added twice
+    codexi(dir.path(), &["data", "import", "--csv", "--dedup"])?;
+    assert_eq!(descriptions(dir.path())?.len(), 6);
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+// tests/json_output.rs
+
+mod common;
+
+use std::process::Command;
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use common::{codexi, setup};
+
+#[test]
+fn test_balance_json() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    let balance: Value = serde_json::from_str(&codexi(dir.path(), &["--format", "json", "report", "balance"])?)?;
+    assert_eq!(balance["credit"], 140.0);
+    assert_eq!(balance["debit"], 12.5);
+    assert_eq!(balance["total"], 127.5);
+    assert_eq!(balance["count"], 3);
+    assert!(balance["average"].is_null());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_json() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    // The global flag is also accepted after the subcommand
+    let items: Value = serde_json::from_str(&codexi(dir.path(), &["search", "--flow", "debit", "--format", "json"])?)?;
+    let items = items.as_array().ok_or_else(|| anyhow!("An array is expected."))?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["index"], 1);
+    assert_eq!(items[0]["date"], "2025-01-05");
+    assert_eq!(items[0]["amount"], 12.5);
+    assert_eq!(items[0]["flow"], "Debit");
+    assert_eq!(items[0]["description"], "Grocer - food");
+    assert_eq!(items[0]["balance"], 87.5, "Running balance after the operation.");
+
+    Ok(())
+}
+
+#[test]
+fn test_search_count_only_json() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    let count: Value = serde_json::from_str(&codexi(dir.path(), &["--format", "json", "search", "--flow", "debit", "--count-only"])?)?;
+    assert_eq!(count, serde_json::json!({"count": 1}));
+    assert_eq!(codexi(dir.path(), &["search", "--count-only"])?.trim(), "3", "A bare number without --format json.");
+
+    // No log line, even in verbose mode (the load is logged at debug level)
+    let output = Command::new(env!("CARGO_BIN_EXE_codexi"))
+        .args(["-v", "--format", "json", "search", "--count-only"])
+        .env("XDG_DATA_HOME", dir.path())
+        .env_remove("CODEXI_ACCOUNT")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?.trim(), r#"{"count":3}"#, "Compact when piped.");
+    assert!(output.stderr.is_empty(), "Unexpected log: {}", String::from_utf8_lossy(&output.stderr));
+
+    Ok(())
+}
+
+#[test]
+fn test_resume_json() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    let resume: Value = serde_json::from_str(&codexi(dir.path(), &["--format", "json", "report", "resume"])?)?;
+    assert_eq!(resume["current_nb_transaction"], 2);
+    assert_eq!(resume["current_nb_init"], 1);
+    assert_eq!(resume["current_balance"], 127.5);
+    assert_eq!(resume["latest_transaction_date"], "2025-01-10");
+    assert!(resume["latest_close_date"].is_null());
+
+    Ok(())
+}
+
+#[test]
+fn test_json_compact_and_pretty() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    let search = codexi(dir.path(), &["--format", "json", "search"])?;
+    assert_eq!(search.trim_end().lines().count(), 1, "The output is piped: compact by default.");
+
+    let pretty = codexi(dir.path(), &["--json-pretty", "--format", "json", "search"])?;
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(serde_json::from_str::<Value>(&pretty)?, serde_json::from_str::<Value>(&search)?);
+
+    // Both layouts of the export import back the same ledger
+    for flag in ["--json-compact", "--json-pretty"] {
+        codexi(dir.path(), &[flag, "data", "export", "--json"])?;
+        let export = std::fs::read_to_string(dir.path().join("codexi.json"))?;
+        assert_eq!(export.trim_end().lines().count() == 1, flag == "--json-compact", "{}", export);
+
+        codexi(dir.path(), &["debit", "2025-01-20", "5", "Bread"])?;
+        codexi(dir.path(), &["data", "import", "--json"])?;
+        assert_eq!(codexi(dir.path(), &["--format", "json", "search"])?, search, "{} export", flag);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_run_keeps_the_global_flags() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    codexi(dir.path(), &["report", "balance", "--save-as", "totals"])?;
+
+    let balance: Value = serde_json::from_str(&codexi(dir.path(), &["--format", "json", "run", "totals"])?)?;
+    assert_eq!(balance["total"], 127.5, "The flags given before run apply to the preset.");
+
+    let pretty = codexi(dir.path(), &["--json-pretty", "run", "totals", "--format", "json"])?;
+    assert!(pretty.lines().count() > 1);
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+// tests/no_color.rs
+
+mod common;
+
+use anyhow::Result;
+
+use common::{codexi, codexi_with_env, setup};
+
+const ESCAPE: char = '\x1b';
+
+#[test]
+fn test_no_color_flag() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    let colored = codexi(dir.path(), &["report", "balance"])?;
+    assert!(colored.contains(ESCAPE), "The tables are colored by default.");
+
+    for args in [&["--no-color", "report", "balance"][..], &["search", "--no-color"], &["report", "resume", "--no-color"]] {
+        let plain = codexi(dir.path(), args)?;
+        assert!(!plain.contains(ESCAPE), "No escape sequence expected with {:?}.", args);
+        assert!(plain.contains("│"), "The table is still printed.");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_no_color_env() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    let plain = codexi_with_env(dir.path(), &[("NO_COLOR", "1")], &["report", "balance"])?;
+    assert!(!plain.contains(ESCAPE));
+
+    let colored = codexi_with_env(dir.path(), &[("NO_COLOR", "")], &["report", "balance"])?;
+    assert!(colored.contains(ESCAPE), "An empty NO_COLOR is ignored.");
+
+    Ok(())
+}
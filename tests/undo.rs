@@ -0,0 +1,59 @@
+// tests/undo.rs
+
+mod common;
+
+use anyhow::Result;
+
+use common::{codexi, setup};
+
+/// The notes of the snapshots, oldest first
+fn snapshot_notes(data_home: &std::path::Path) -> Result<Vec<String>> {
+    let listing = codexi(data_home, &["--no-color", "data", "list-snapshot"])?;
+    Ok(listing.lines()
+        .filter_map(|line| line.split('│').nth(2))
+        .map(|note| note.trim().to_string())
+        .filter(|note| note.starts_with("before "))
+        .collect())
+}
+
+#[test]
+fn test_undo_flag_and_set_title() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+
+    codexi(dir.path(), &["flag", "1"])?;
+    codexi(dir.path(), &["set-title", "Home"])?;
+    assert_eq!(snapshot_notes(dir.path())?, ["before init", "before debit", "before credit", "before flag", "before set-title"]);
+
+    assert!(codexi(dir.path(), &["undo"])?.contains("before set-title"));
+    assert!(codexi(dir.path(), &["undo"])?.contains("before flag"));
+    assert!(codexi(dir.path(), &["undo"])?.contains("before credit"));
+
+    Ok(())
+}
+
+#[test]
+fn test_refused_command_takes_no_snapshot() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    setup(dir.path())?;
+    let before = snapshot_notes(dir.path())?;
+
+    assert!(codexi(dir.path(), &["debit", "2025-01-20", "5000", "Car"]).is_err(), "Insufficient funds.");
+    assert!(codexi(dir.path(), &["flag", "99"]).is_err(), "Out of bounds.");
+    assert!(codexi(dir.path(), &["recategorize", "99", "--kind", "fee"]).is_err(), "Out of bounds.");
+    assert!(codexi(dir.path(), &["rm-match", "-t", "salary", "--expect-removed", "2"]).is_err(), "One match only.");
+    assert!(codexi(dir.path(), &["system", "close-batch", "--from", "2025-03", "--to", "2025-01"]).is_err(), "Reversed range.");
+
+    let batch = dir.path().join("batch.csv");
+    std::fs::write(&batch, "2025-01-12 -2.50 Coffee\n2025-01-13 abc Tea\n")?;
+    assert!(codexi(dir.path(), &["apply", batch.to_str().unwrap()]).is_err(), "Invalid amount.");
+    assert_eq!(snapshot_notes(dir.path())?, before);
+
+    // A title set again is not a change
+    codexi(dir.path(), &["set-title", "Home"])?;
+    codexi(dir.path(), &["set-title", " Home "])?;
+    assert_eq!(snapshot_notes(dir.path())?.last().map(String::as_str), Some("before set-title"));
+    assert_eq!(snapshot_notes(dir.path())?.len(), before.len() + 1);
+
+    Ok(())
+}